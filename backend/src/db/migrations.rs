@@ -0,0 +1,857 @@
+use crate::models::money::MINOR_UNITS;
+use crate::services::product_service::catalog_counter_id;
+use chrono::Utc;
+use futures::stream::TryStreamExt;
+use mongodb::{
+    Collection, Database, IndexModel,
+    bson::{Bson, DateTime, Document, doc, oid::ObjectId},
+    options::{IndexOptions, ReturnDocument},
+};
+use std::error::Error;
+use std::time::Duration;
+
+/// Field uang yang dulu disimpan sebagai f64 (rupiah), path dengan titik
+/// juga masuk ke setiap elemen array, contoh: "items.price"
+const MONEY_FIELDS: &[(&str, &[&str])] = &[
+    ("products", &["price"]),
+    (
+        "sales",
+        &[
+            "items.price",
+            "items.subtotal",
+            "items.tax_amount",
+            "items.discount.amount",
+            "subtotal_amount",
+            "discount.amount",
+            "total_discount",
+            "tax_base",
+            "tax_amount",
+            "total_amount",
+            "paid_amount",
+            "remaining_amount",
+            "change_amount",
+            "refunded_amount",
+            "tenders.amount",
+            "tenders.change_amount",
+        ],
+    ),
+    (
+        "sale_returns",
+        &["items.price", "items.subtotal", "refund_amount"],
+    ),
+    ("sale_payments", &["amount"]),
+];
+
+const SALE_OBJECT_ID_MIGRATION: &str = "sale_object_ids";
+const MONEY_MIGRATION: &str = "money_minor_units";
+const PRODUCT_CHANGE_SEQ_MIGRATION: &str = "product_change_seq";
+const SALE_RETURN_AMOUNT_MIGRATION: &str = "sale_return_amounts";
+const PAYMENT_METHOD_CASH_MIGRATION: &str = "payment_method_cash_flags";
+const DISCOUNT_FIELDS_MIGRATION: &str = "discount_fields";
+const TENDER_SHIFT_ID_MIGRATION: &str = "tender_shift_ids";
+const TENDER_PAYMENT_ID_MIGRATION: &str = "tender_payment_ids";
+const VOUCHER_FIELDS_MIGRATION: &str = "voucher_fields";
+
+/// Nama/jenis metode pembayaran lama yang dianggap tunai
+const CASH_METHOD_PATTERN: &str = "tunai|cash";
+
+/// Urutan migrasi, migrasi baru selalu ditambahkan di akhir
+const MIGRATIONS: &[&str] = &[
+    SALE_OBJECT_ID_MIGRATION,
+    MONEY_MIGRATION,
+    PRODUCT_CHANGE_SEQ_MIGRATION,
+    SALE_RETURN_AMOUNT_MIGRATION,
+    PAYMENT_METHOD_CASH_MIGRATION,
+    DISCOUNT_FIELDS_MIGRATION,
+    TENDER_SHIFT_ID_MIGRATION,
+    TENDER_PAYMENT_ID_MIGRATION,
+    VOUCHER_FIELDS_MIGRATION,
+];
+
+/// Jalankan migrasi data yang belum pernah dijalankan, dicatat di collection `migrations`
+pub async fn run_migrations(db: &Database) -> Result<(), Box<dyn Error>> {
+    let migrations: Collection<Document> = db.collection("migrations");
+
+    for &name in MIGRATIONS {
+        if migrations.find_one(doc! { "_id": name }).await?.is_some() {
+            continue;
+        }
+
+        log::info!("Menjalankan migrasi {}", name);
+        match name {
+            SALE_OBJECT_ID_MIGRATION => migrate_sale_object_ids(db).await?,
+            MONEY_MIGRATION => migrate_money_to_minor_units(db).await?,
+            PRODUCT_CHANGE_SEQ_MIGRATION => migrate_product_change_seq(db).await?,
+            SALE_RETURN_AMOUNT_MIGRATION => migrate_sale_return_amounts(db).await?,
+            PAYMENT_METHOD_CASH_MIGRATION => migrate_payment_method_cash_flags(db).await?,
+            DISCOUNT_FIELDS_MIGRATION => migrate_discount_fields(db).await?,
+            TENDER_SHIFT_ID_MIGRATION => migrate_tender_shift_ids(db).await?,
+            TENDER_PAYMENT_ID_MIGRATION => migrate_tender_payment_ids(db).await?,
+            VOUCHER_FIELDS_MIGRATION => migrate_voucher_fields(db).await?,
+            _ => unreachable!("migrasi {} belum punya fungsi", name),
+        }
+        migrations
+            .insert_one(doc! {
+                "_id": name,
+                "applied_at": DateTime::from_chrono(Utc::now()),
+            })
+            .await?;
+    }
+
+    ensure_indexes(db).await?;
+
+    Ok(())
+}
+
+/// Index untuk query list yang sering dipakai, create_index aman dipanggil berulang
+async fn ensure_indexes(db: &Database) -> Result<(), Box<dyn Error>> {
+    let sales: Collection<Document> = db.collection("sales");
+    sales
+        .create_index(
+            IndexModel::builder()
+                .keys(doc! { "user_id": 1, "sale_date": -1 })
+                .build(),
+        )
+        .await?;
+
+    // Nomor invoice unik per toko. Data lama yang sudah terlanjur dobel membuat index
+    // gagal dibuat; aplikasi tetap jalan tapi dicatat supaya datanya dibereskan
+    if let Err(e) = sales
+        .create_index(
+            IndexModel::builder()
+                .keys(doc! { "user_id": 1, "invoice_number": 1 })
+                .options(
+                    IndexOptions::builder()
+                        .unique(true)
+                        .partial_filter_expression(
+                            doc! { "invoice_number": { "$type": "string" } },
+                        )
+                        .build(),
+                )
+                .build(),
+        )
+        .await
+    {
+        log::error!("Index unik nomor invoice gagal dibuat: {}", e);
+    }
+
+    // client_id sale hasil sinkronisasi offline unik per toko
+    sales
+        .create_index(
+            IndexModel::builder()
+                .keys(doc! { "user_id": 1, "client_id": 1 })
+                .options(
+                    IndexOptions::builder()
+                        .unique(true)
+                        .partial_filter_expression(doc! { "client_id": { "$type": "string" } })
+                        .build(),
+                )
+                .build(),
+        )
+        .await?;
+
+    let products: Collection<Document> = db.collection("products");
+    products
+        .create_index(
+            IndexModel::builder()
+                .keys(doc! { "user_id": 1, "change_seq": 1 })
+                .build(),
+        )
+        .await?;
+
+    let product_tombstones: Collection<Document> = db.collection("product_tombstones");
+    product_tombstones
+        .create_index(
+            IndexModel::builder()
+                .keys(doc! { "user_id": 1, "change_seq": 1 })
+                .build(),
+        )
+        .await?;
+
+    let promotions: Collection<Document> = db.collection("promotions");
+    promotions
+        .create_index(
+            IndexModel::builder()
+                .keys(doc! { "user_id": 1, "active": 1, "starts_at": 1 })
+                .build(),
+        )
+        .await?;
+
+    // Kode voucher unik per toko
+    let vouchers: Collection<Document> = db.collection("vouchers");
+    vouchers
+        .create_index(
+            IndexModel::builder()
+                .keys(doc! { "user_id": 1, "code": 1 })
+                .options(IndexOptions::builder().unique(true).build())
+                .build(),
+        )
+        .await?;
+
+    let voucher_redemptions: Collection<Document> = db.collection("voucher_redemptions");
+    voucher_redemptions
+        .create_index(
+            IndexModel::builder()
+                .keys(doc! { "voucher_id": 1, "customer_id": 1, "status": 1 })
+                .build(),
+        )
+        .await?;
+    voucher_redemptions
+        .create_index(IndexModel::builder().keys(doc! { "sale_id": 1 }).build())
+        .await?;
+
+    // Z-report mengelompokkan void, retur dan cicilan menurut waktu kejadiannya
+    sales
+        .create_index(
+            IndexModel::builder()
+                .keys(doc! { "user_id": 1, "void.voided_at": 1 })
+                .build(),
+        )
+        .await?;
+    let sale_returns: Collection<Document> = db.collection("sale_returns");
+    sale_returns
+        .create_index(
+            IndexModel::builder()
+                .keys(doc! { "user_id": 1, "created_at": 1 })
+                .build(),
+        )
+        .await?;
+    sale_returns
+        .create_index(IndexModel::builder().keys(doc! { "shift_id": 1 }).build())
+        .await?;
+    let sale_payments: Collection<Document> = db.collection("sale_payments");
+    sale_payments
+        .create_index(
+            IndexModel::builder()
+                .keys(doc! { "user_id": 1, "paid_at": 1 })
+                .build(),
+        )
+        .await?;
+    sale_payments
+        .create_index(IndexModel::builder().keys(doc! { "sale_id": 1 }).build())
+        .await?;
+
+    let daily_closings: Collection<Document> = db.collection("daily_closings");
+    daily_closings
+        .create_index(
+            IndexModel::builder()
+                .keys(doc! { "user_id": 1, "date": 1 })
+                .options(IndexOptions::builder().unique(true).build())
+                .build(),
+        )
+        .await?;
+
+    // Penanda tanggal bisnis yang ditulis setiap transaksi, lihat `ensure_day_open`
+    let business_days: Collection<Document> = db.collection("business_days");
+    business_days
+        .create_index(
+            IndexModel::builder()
+                .keys(doc! { "user_id": 1, "date": 1 })
+                .options(IndexOptions::builder().unique(true).build())
+                .build(),
+        )
+        .await?;
+
+    // Hanya boleh ada satu shift terbuka per toko
+    let shifts: Collection<Document> = db.collection("shifts");
+    shifts
+        .create_index(
+            IndexModel::builder()
+                .keys(doc! { "user_id": 1 })
+                .options(
+                    IndexOptions::builder()
+                        .unique(true)
+                        .partial_filter_expression(doc! { "status": "open" })
+                        .name("user_id_open_shift".to_string())
+                        .build(),
+                )
+                .build(),
+        )
+        .await?;
+
+    // Nomor HP pelanggan unik per toko, pelanggan tanpa nomor HP tidak ikut dicek
+    let customers: Collection<Document> = db.collection("customers");
+    customers
+        .create_index(
+            IndexModel::builder()
+                .keys(doc! { "user_id": 1, "phone": 1 })
+                .options(
+                    IndexOptions::builder()
+                        .unique(true)
+                        .partial_filter_expression(doc! { "phone": { "$type": "string" } })
+                        .build(),
+                )
+                .build(),
+        )
+        .await?;
+
+    let point_transactions: Collection<Document> = db.collection("point_transactions");
+    point_transactions
+        .create_index(
+            IndexModel::builder()
+                .keys(doc! { "user_id": 1, "customer_id": 1, "created_at": -1 })
+                .build(),
+        )
+        .await?;
+
+    // Idempotency-Key unik per toko dan kedaluwarsa otomatis setelah 24 jam
+    let idempotency_keys: Collection<Document> = db.collection("idempotency_keys");
+    idempotency_keys
+        .create_index(
+            IndexModel::builder()
+                .keys(doc! { "user_id": 1, "key": 1 })
+                .options(IndexOptions::builder().unique(true).build())
+                .build(),
+        )
+        .await?;
+    idempotency_keys
+        .create_index(
+            IndexModel::builder()
+                .keys(doc! { "created_at": 1 })
+                .options(
+                    IndexOptions::builder()
+                        .expire_after(Duration::from_secs(24 * 60 * 60))
+                        .build(),
+                )
+                .build(),
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// Sale lama menyimpan user_id dan items.product_id sebagai string hex
+/// sehingga tidak cocok dengan filter ObjectId. Ubah ke ObjectId, aman dijalankan ulang
+async fn migrate_sale_object_ids(db: &Database) -> Result<(), Box<dyn Error>> {
+    let collection: Collection<Document> = db.collection("sales");
+    let mut cursor = collection
+        .find(doc! { "$or": [
+            { "user_id": { "$type": "string" } },
+            { "items.product_id": { "$type": "string" } },
+        ] })
+        .await?;
+    let mut migrated = 0;
+
+    while let Some(mut document) = cursor.try_next().await? {
+        let mut changed = false;
+        for field in ["user_id", "items.product_id"] {
+            let path: Vec<&str> = field.split('.').collect();
+            changed |= convert_path(&mut document, &path, hex_to_object_id);
+        }
+
+        if changed {
+            let id = document.get("_id").cloned().unwrap_or(Bson::Null);
+            collection.replace_one(doc! { "_id": id }, document).await?;
+            migrated += 1;
+        }
+    }
+
+    log::info!("Migrasi ObjectId sale: {} dokumen diubah", migrated);
+
+    Ok(())
+}
+
+/// tenders.shift_id sempat disimpan sebagai string hex, ubah ke ObjectId seperti field ID lain
+async fn migrate_tender_shift_ids(db: &Database) -> Result<(), Box<dyn Error>> {
+    let collection: Collection<Document> = db.collection("sales");
+    let mut cursor = collection
+        .find(doc! { "tenders.shift_id": { "$type": "string" } })
+        .await?;
+    let mut migrated = 0;
+
+    while let Some(mut document) = cursor.try_next().await? {
+        if convert_path(&mut document, &["tenders", "shift_id"], hex_to_object_id) {
+            let id = document.get("_id").cloned().unwrap_or(Bson::Null);
+            collection.replace_one(doc! { "_id": id }, document).await?;
+            migrated += 1;
+        }
+    }
+
+    log::info!("Migrasi shift_id tender: {} dokumen diubah", migrated);
+
+    Ok(())
+}
+
+/// Tandai tender hasil cicilan lama dengan payment_id. Cicilan selalu ditambahkan di akhir
+/// `tenders`, jadi dicocokkan dari belakang; sale yang sudah punya payment_id dilewati
+async fn migrate_tender_payment_ids(db: &Database) -> Result<(), Box<dyn Error>> {
+    let sales: Collection<Document> = db.collection("sales");
+    let payments: Collection<Document> = db.collection("sale_payments");
+    let sale_ids = payments.distinct("sale_id", doc! {}).await?;
+    let mut migrated = 0;
+
+    for sale_id in sale_ids {
+        let Some(mut document) = sales
+            .find_one(doc! {
+                "_id": &sale_id,
+                "tenders.0": { "$exists": true },
+                "tenders.payment_id": { "$exists": false },
+            })
+            .await?
+        else {
+            continue;
+        };
+
+        let sale_payments: Vec<Document> = payments
+            .find(doc! { "sale_id": &sale_id })
+            .sort(doc! { "paid_at": -1, "_id": -1 })
+            .await?
+            .try_collect()
+            .await?;
+
+        let Ok(tenders) = document.get_array_mut("tenders") else {
+            continue;
+        };
+        if mark_installment_tenders(tenders, &sale_payments) {
+            sales
+                .replace_one(doc! { "_id": &sale_id }, document)
+                .await?;
+            migrated += 1;
+        }
+    }
+
+    log::info!("Migrasi payment_id tender: {} dokumen diubah", migrated);
+
+    Ok(())
+}
+
+/// Pasangkan cicilan (terbaru lebih dulu) dengan tender dari belakang selama nominal dan
+/// nama metode pembayarannya sama
+fn mark_installment_tenders(tenders: &mut [Bson], payments_newest_first: &[Document]) -> bool {
+    let method_name = |document: &Document| {
+        document
+            .get_document("payment_method")
+            .ok()
+            .and_then(|method| method.get_str("name").ok())
+            .map(str::to_string)
+    };
+
+    let mut changed = false;
+    let mut remaining = tenders.iter_mut().rev();
+    for payment in payments_newest_first {
+        let Some(Bson::Document(tender)) = remaining.next() else {
+            break;
+        };
+        if tender.get("amount") != payment.get("amount")
+            || method_name(tender) != method_name(payment)
+        {
+            break;
+        }
+        if let Some(id) = payment.get("_id") {
+            tender.insert("payment_id", id.clone());
+            changed = true;
+        }
+    }
+    changed
+}
+
+/// Ubah nominal rupiah (f64) menjadi Int64 sen. Nilai Int64 sudah format baru dan tidak
+/// disentuh, jadi aman dijalankan ulang jika migrasi sempat terhenti
+async fn migrate_money_to_minor_units(db: &Database) -> Result<(), Box<dyn Error>> {
+    for (collection_name, fields) in MONEY_FIELDS {
+        let collection: Collection<Document> = db.collection(collection_name);
+        let mut cursor = collection.find(doc! {}).await?;
+        let mut migrated = 0;
+
+        while let Some(mut document) = cursor.try_next().await? {
+            let mut changed = false;
+            for field in *fields {
+                let path: Vec<&str> = field.split('.').collect();
+                changed |= convert_path(&mut document, &path, rupiah_to_minor);
+            }
+
+            if changed {
+                let id = document.get("_id").cloned().unwrap_or(Bson::Null);
+                collection.replace_one(doc! { "_id": id }, document).await?;
+                migrated += 1;
+            }
+        }
+
+        log::info!(
+            "Migrasi uang {}: {} dokumen diubah",
+            collection_name,
+            migrated
+        );
+    }
+
+    Ok(())
+}
+
+/// Beri nomor urut katalog ke produk lama supaya ikut terkirim di delta sync pertama.
+/// Hanya produk tanpa change_seq yang disentuh, jadi aman dijalankan ulang
+async fn migrate_product_change_seq(db: &Database) -> Result<(), Box<dyn Error>> {
+    let products: Collection<Document> = db.collection("products");
+    let counters: Collection<Document> = db.collection("counters");
+
+    let mut cursor = products
+        .find(doc! { "change_seq": { "$exists": false } })
+        .sort(doc! { "user_id": 1, "_id": 1 })
+        .await?;
+    let mut migrated = 0;
+
+    while let Some(product) = cursor.try_next().await? {
+        let user_id = product.get_object_id("user_id")?;
+
+        let counter = counters
+            .find_one_and_update(
+                doc! { "_id": catalog_counter_id(user_id) },
+                doc! {
+                    "$inc": { "seq": 1_i64 },
+                    "$setOnInsert": { "user_id": user_id },
+                },
+            )
+            .upsert(true)
+            .return_document(ReturnDocument::After)
+            .await?
+            .ok_or("Counter katalog gagal dibuat")?;
+
+        products
+            .update_one(
+                doc! { "_id": product.get_object_id("_id")? },
+                doc! { "$set": { "change_seq": counter.get_i64("seq")? } },
+            )
+            .await?;
+        migrated += 1;
+    }
+
+    log::info!("Migrasi change_seq produk: {} dokumen diubah", migrated);
+
+    Ok(())
+}
+
+/// Retur lama selalu dikembalikan penuh sebagai uang, nilai returnya sama dengan refund
+async fn migrate_sale_return_amounts(db: &Database) -> Result<(), Box<dyn Error>> {
+    let collection: Collection<Document> = db.collection("sale_returns");
+    let result = collection
+        .update_many(
+            doc! { "return_amount": { "$exists": false } },
+            vec![doc! { "$set": {
+                "return_amount": "$refund_amount",
+                "credited_amount": 0_i64,
+            } }],
+        )
+        .await?;
+
+    log::info!(
+        "Migrasi nilai retur: {} dokumen diubah",
+        result.modified_count
+    );
+
+    Ok(())
+}
+
+/// Metode pembayaran lama belum punya `is_cash`, tandai yang nama/jenisnya tunai.
+/// Salinan metode di tender sale ikut ditandai supaya kas shift dan kembalian benar
+async fn migrate_payment_method_cash_flags(db: &Database) -> Result<(), Box<dyn Error>> {
+    let cash = doc! { "$regex": CASH_METHOD_PATTERN, "$options": "i" };
+
+    let methods: Collection<Document> = db.collection("payment_methods");
+    let flagged = methods
+        .update_many(
+            doc! {
+                "is_cash": { "$exists": false },
+                "$or": [{ "name": cash.clone() }, { "type": cash.clone() }],
+            },
+            doc! { "$set": { "is_cash": true } },
+        )
+        .await?;
+    methods
+        .update_many(
+            doc! { "is_cash": { "$exists": false } },
+            doc! { "$set": { "is_cash": false } },
+        )
+        .await?;
+
+    let sales: Collection<Document> = db.collection("sales");
+    let tenders = sales
+        .update_many(
+            doc! { "$or": [
+                { "tenders.payment_method.name": cash.clone() },
+                { "tenders.payment_method.type": cash.clone() },
+            ] },
+            doc! { "$set": { "tenders.$[tender].payment_method.is_cash": true } },
+        )
+        .array_filters(vec![doc! { "$or": [
+            { "tender.payment_method.name": cash.clone() },
+            { "tender.payment_method.type": cash.clone() },
+        ] }])
+        .await?;
+    let legacy = sales
+        .update_many(
+            doc! { "$or": [
+                { "payment_method.name": cash.clone() },
+                { "payment_method.type": cash },
+            ] },
+            doc! { "$set": { "payment_method.is_cash": true } },
+        )
+        .await?;
+
+    log::info!(
+        "Migrasi metode pembayaran tunai: {} metode, {} sale",
+        flagged.modified_count,
+        tenders.modified_count + legacy.modified_count
+    );
+
+    Ok(())
+}
+
+/// `value` diskon lama berisi persen atau nominal (rupiah di sale sebelum migrasi uang,
+/// sen setelahnya) sehingga tidak bisa masuk MONEY_FIELDS. Dipecah menjadi `percent` untuk
+/// diskon persen; diskon fixed di sale cukup memakai `amount` yang sudah dalam sen
+async fn migrate_discount_fields(db: &Database) -> Result<(), Box<dyn Error>> {
+    let targets: [(&str, Converter); 2] = [
+        ("sales", split_sale_discount),
+        ("sale_drafts", split_draft_discount),
+    ];
+
+    for (collection_name, convert) in targets {
+        let collection: Collection<Document> = db.collection(collection_name);
+        let mut cursor = collection
+            .find(doc! { "$or": [
+                { "discount.value": { "$exists": true } },
+                { "items.discount.value": { "$exists": true } },
+            ] })
+            .await?;
+        let mut migrated = 0;
+
+        while let Some(mut document) = cursor.try_next().await? {
+            let mut changed = convert_path(&mut document, &["discount"], convert);
+            changed |= convert_path(&mut document, &["items", "discount"], convert);
+
+            if changed {
+                let id = document.get("_id").cloned().unwrap_or(Bson::Null);
+                collection.replace_one(doc! { "_id": id }, document).await?;
+                migrated += 1;
+            }
+        }
+
+        log::info!(
+            "Migrasi field diskon {}: {} dokumen diubah",
+            collection_name,
+            migrated
+        );
+    }
+
+    Ok(())
+}
+
+/// `value` voucher dipecah seperti diskon di draft: `percent` untuk voucher persen,
+/// `amount` untuk voucher fixed (voucher dibuat setelah migrasi uang, sudah dalam sen)
+async fn migrate_voucher_fields(db: &Database) -> Result<(), Box<dyn Error>> {
+    let collection: Collection<Document> = db.collection("vouchers");
+    let mut cursor = collection
+        .find(doc! { "value": { "$exists": true } })
+        .await?;
+    let mut migrated = 0;
+
+    while let Some(document) = cursor.try_next().await? {
+        let id = document.get("_id").cloned().unwrap_or(Bson::Null);
+        let mut voucher = Bson::Document(document);
+
+        if !split_draft_discount(&mut voucher) {
+            continue;
+        }
+        if let Bson::Document(document) = voucher {
+            collection.replace_one(doc! { "_id": id }, document).await?;
+            migrated += 1;
+        }
+    }
+
+    log::info!("Migrasi field voucher: {} dokumen diubah", migrated);
+
+    Ok(())
+}
+
+/// Pengubah satu nilai BSON, mengembalikan true jika nilainya diubah
+type Converter = fn(&mut Bson) -> bool;
+
+/// Terapkan `convert` ke field di `path`, termasuk ke setiap elemen array di tengah path
+fn convert_path(document: &mut Document, path: &[&str], convert: Converter) -> bool {
+    let Some(value) = document.get_mut(path[0]) else {
+        return false;
+    };
+
+    if path.len() == 1 {
+        return convert(value);
+    }
+
+    match value {
+        Bson::Document(inner) => convert_path(inner, &path[1..], convert),
+        Bson::Array(items) => {
+            let mut changed = false;
+            for item in items.iter_mut() {
+                if let Bson::Document(inner) = item {
+                    changed |= convert_path(inner, &path[1..], convert);
+                }
+            }
+            changed
+        }
+        _ => false,
+    }
+}
+
+/// Ambil `value` numerik dari diskon tersimpan beserta penanda diskon persen
+fn take_discount_value(value: &mut Bson) -> Option<(&mut Document, bool, f64)> {
+    let Bson::Document(discount) = value else {
+        return None;
+    };
+    let number = match discount.get("value")? {
+        Bson::Double(number) => *number,
+        Bson::Int32(number) => *number as f64,
+        Bson::Int64(number) => *number as f64,
+        _ => return None,
+    };
+    discount.remove("value");
+    let is_percent = discount.get_str("discount_type") == Ok("percent");
+    Some((discount, is_percent, number))
+}
+
+fn split_sale_discount(value: &mut Bson) -> bool {
+    let Some((discount, is_percent, number)) = take_discount_value(value) else {
+        return false;
+    };
+    if is_percent {
+        discount.insert("percent", number);
+    }
+    true
+}
+
+/// Draft dibuat setelah migrasi uang, nominal fixed-nya sudah dalam sen
+fn split_draft_discount(value: &mut Bson) -> bool {
+    let Some((discount, is_percent, number)) = take_discount_value(value) else {
+        return false;
+    };
+    if is_percent {
+        discount.insert("percent", number);
+    } else {
+        discount.insert("amount", number.round() as i64);
+    }
+    true
+}
+
+fn hex_to_object_id(value: &mut Bson) -> bool {
+    match value {
+        Bson::String(hex) => match ObjectId::parse_str(hex.as_str()) {
+            Ok(oid) => {
+                *value = Bson::ObjectId(oid);
+                true
+            }
+            Err(_) => false,
+        },
+        _ => false,
+    }
+}
+
+fn rupiah_to_minor(value: &mut Bson) -> bool {
+    match value {
+        Bson::Double(rupiah) => {
+            *value = Bson::Int64((*rupiah * MINOR_UNITS as f64).round() as i64);
+            true
+        }
+        // Nominal lama yang kebetulan tersimpan sebagai integer juga masih dalam rupiah
+        Bson::Int32(rupiah) => {
+            *value = Bson::Int64(*rupiah as i64 * MINOR_UNITS);
+            true
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn convert_path_reaches_every_array_element() {
+        let mut document = doc! {
+            "items": [{ "price": 1500.5 }, { "price": 2000_i32 }, { "price": 10_i64 }],
+        };
+        assert!(convert_path(
+            &mut document,
+            &["items", "price"],
+            rupiah_to_minor
+        ));
+        let prices: Vec<&Bson> = document
+            .get_array("items")
+            .unwrap()
+            .iter()
+            .map(|item| item.as_document().unwrap().get("price").unwrap())
+            .collect();
+        assert_eq!(
+            prices,
+            vec![
+                &Bson::Int64(150_050),
+                &Bson::Int64(200_000),
+                &Bson::Int64(10)
+            ]
+        );
+    }
+
+    #[test]
+    fn split_sale_discount_keeps_percent_and_drops_fixed_value() {
+        let mut percent = Bson::Document(doc! {
+            "discount_type": "percent", "value": 10.0, "amount": 1_000_i64,
+        });
+        assert!(split_sale_discount(&mut percent));
+        assert_eq!(
+            percent,
+            Bson::Document(
+                doc! { "discount_type": "percent", "amount": 1_000_i64, "percent": 10.0 }
+            )
+        );
+
+        let mut fixed = Bson::Document(doc! {
+            "discount_type": "fixed", "value": 50.0, "amount": 5_000_i64,
+        });
+        assert!(split_sale_discount(&mut fixed));
+        assert_eq!(
+            fixed,
+            Bson::Document(doc! { "discount_type": "fixed", "amount": 5_000_i64 })
+        );
+        assert!(!split_sale_discount(&mut fixed));
+    }
+
+    #[test]
+    fn split_draft_discount_moves_fixed_value_to_amount() {
+        let mut fixed = Bson::Document(doc! { "discount_type": "fixed", "value": 5_000.0 });
+        assert!(split_draft_discount(&mut fixed));
+        assert_eq!(
+            fixed,
+            Bson::Document(doc! { "discount_type": "fixed", "amount": 5_000_i64 })
+        );
+    }
+
+    #[test]
+    fn mark_installment_tenders_matches_from_the_end() {
+        let cash = doc! { "name": "Tunai" };
+        let transfer = doc! { "name": "Transfer" };
+        let (first, second) = (ObjectId::new(), ObjectId::new());
+        let mut tenders = vec![
+            Bson::Document(doc! { "payment_method": cash.clone(), "amount": 50_000_i64 }),
+            Bson::Document(doc! { "payment_method": cash.clone(), "amount": 20_000_i64 }),
+            Bson::Document(doc! { "payment_method": transfer.clone(), "amount": 30_000_i64 }),
+        ];
+        let payments = [
+            doc! { "_id": second, "payment_method": transfer, "amount": 30_000_i64 },
+            doc! { "_id": first, "payment_method": cash.clone(), "amount": 20_000_i64 },
+        ];
+
+        assert!(mark_installment_tenders(&mut tenders, &payments));
+        let payment_id = |tender: &Bson| tender.as_document().unwrap().get("payment_id").cloned();
+        assert_eq!(payment_id(&tenders[0]), None);
+        assert_eq!(payment_id(&tenders[1]), Some(Bson::ObjectId(first)));
+        assert_eq!(payment_id(&tenders[2]), Some(Bson::ObjectId(second)));
+    }
+
+    #[test]
+    fn mark_installment_tenders_stops_at_first_mismatch() {
+        let mut tenders = vec![Bson::Document(
+            doc! { "payment_method": { "name": "Tunai" }, "amount": 70_000_i64 },
+        )];
+        let payments = [
+            doc! { "_id": ObjectId::new(), "payment_method": { "name": "Tunai" }, "amount": 20_000_i64 },
+        ];
+
+        assert!(!mark_installment_tenders(&mut tenders, &payments));
+    }
+}
@@ -1 +1,2 @@
+pub mod migrations;
 pub mod mongo;
@@ -90,10 +90,9 @@ impl From<ValidationErrors> for ApiError {
         // Ubah ValidationErrors menjadi satu string yang readable
         let msg = err
             .field_errors()
-            .iter()
-            .map(|(_field, errs)| {
-                let reasons = errs
-                    .iter()
+            .values()
+            .map(|errs| {
+                errs.iter()
                     .map(|e| {
                         e.message
                             .as_ref()
@@ -101,8 +100,7 @@ impl From<ValidationErrors> for ApiError {
                             .unwrap_or_else(|| "tidak valid".into())
                     })
                     .collect::<Vec<_>>()
-                    .join(", ");
-                format!("{reasons}")
+                    .join(", ")
             })
             .collect::<Vec<_>>()
             .join(" | ");
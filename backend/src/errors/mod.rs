@@ -15,6 +15,10 @@ impl From<ServiceError> for ApiError {
                 ApiError::InternalError(msg)
             }
             ServiceError::Conflict(msg) => ApiError::Conflict(msg),
+            ServiceError::TransactionConflict(msg) => ApiError::Conflict(format!(
+                "Data sedang diproses transaksi lain, silakan coba lagi ({})",
+                msg
+            )),
             ServiceError::Unexpected(msg) => ApiError::InternalError(msg),
             ServiceError::Unauthorized(msg) => ApiError::Unauthorized(msg),
         }
@@ -25,4 +25,7 @@ pub enum ServiceError {
 
     #[error("Unauthorized: {0}")]
     Unauthorized(String),
+
+    #[error("Transaction Conflict: {0}")]
+    TransactionConflict(String),
 }
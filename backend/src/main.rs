@@ -24,6 +24,9 @@ async fn main() -> std::io::Result<()> {
         std::env::set_var("RUST_BACKTRACE", "1");
         env_logger::init();
     }
+    db::migrations::run_migrations(&db_client)
+        .await
+        .expect("Failed to run migrations");
     HttpServer::new(move || {
         let logger = Logger::default();
         App::new()
@@ -0,0 +1,105 @@
+use super::money::Money;
+use crate::utils::opt_object_id_as_string;
+use mongodb::bson::{DateTime, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+/// Pelanggan toko, dirujuk oleh `Sale.customer_id`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Customer {
+    #[serde(
+        rename = "_id",
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "opt_object_id_as_string"
+    )]
+    pub id: Option<ObjectId>,
+
+    pub user_id: ObjectId,
+    pub name: String,
+    pub phone: Option<String>, // unik per toko jika diisi
+    pub address: Option<String>,
+    pub notes: Option<String>,
+
+    #[serde(default)]
+    pub loyalty_points: i64, // saldo poin, diubah hanya lewat point_transactions
+
+    #[serde(default)]
+    pub created_at: Option<DateTime>,
+    #[serde(default)]
+    pub updated_at: Option<DateTime>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CustomerDTO {
+    #[validate(length(min = 1, max = 100, message = "Nama pelanggan 1-100 karakter"))]
+    pub name: String,
+
+    pub phone: Option<String>, // panjang dicek setelah di-trim, lihat normalize_phone
+
+    #[validate(length(max = 255, message = "Alamat maksimal 255 karakter"))]
+    pub address: Option<String>,
+
+    #[validate(length(max = 255, message = "Catatan maksimal 255 karakter"))]
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateCustomerDTO {
+    #[validate(length(min = 1, max = 100, message = "Nama pelanggan 1-100 karakter"))]
+    pub name: Option<String>,
+
+    pub phone: Option<String>, // string kosong menghapus nomor HP
+
+    #[validate(length(max = 255, message = "Alamat maksimal 255 karakter"))]
+    pub address: Option<String>,
+
+    #[validate(length(max = 255, message = "Catatan maksimal 255 karakter"))]
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CustomerListQuery {
+    pub search: Option<String>, // pencarian sebagian nama atau nomor HP
+    pub page: Option<u64>,
+    pub limit: Option<u64>,
+}
+
+/// Ringkasan riwayat belanja pelanggan (sale yang di-void tidak dihitung)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CustomerPurchaseSummary {
+    pub transaction_count: i64,
+    pub total_amount: Money,
+    pub paid_amount: Money,
+    pub remaining_amount: Money,
+    pub refunded_amount: Money,
+    pub last_purchase_at: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CustomerResponse {
+    pub id: String,
+    pub user_id: String,
+    pub name: String,
+    pub phone: Option<String>,
+    pub address: Option<String>,
+    pub notes: Option<String>,
+    pub loyalty_points: i64,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+}
+
+impl From<Customer> for CustomerResponse {
+    fn from(customer: Customer) -> Self {
+        CustomerResponse {
+            id: customer.id.expect("Customer.id harus ada").to_hex(),
+            user_id: customer.user_id.to_hex(),
+            name: customer.name,
+            phone: customer.phone,
+            address: customer.address,
+            notes: customer.notes,
+            loyalty_points: customer.loyalty_points,
+            created_at: customer.created_at.map(|t| t.to_chrono().to_rfc3339()),
+            updated_at: customer.updated_at.map(|t| t.to_chrono().to_rfc3339()),
+        }
+    }
+}
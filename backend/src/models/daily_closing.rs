@@ -0,0 +1,121 @@
+use super::money::Money;
+use super::sale::PaymentMethodTotal;
+use crate::utils::opt_object_id_as_string;
+use mongodb::bson::{DateTime, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+/// Laporan Z (tutup hari) untuk satu tanggal bisnis toko. Setiap kejadian dihitung di
+/// tanggal terjadinya: void/retur/cicilan untuk sale hari sebelumnya masuk ke hari ini
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ZReport {
+    pub date: String, // YYYY-MM-DD, zona waktu toko
+
+    pub transaction_count: i64, // sale tanggal ini, termasuk yang kemudian di-void
+    pub gross_sales: Money,     // harga x qty sebelum diskon
+    pub discount_amount: Money,
+    pub net_sales: Money, // gross_sales - discount_amount
+    pub tax_amount: Money,
+    pub total_amount: Money, // grand total termasuk pajak
+    pub average_basket: Money,
+
+    pub paid_amount: Money,      // dibayar saat sale dibuat
+    pub remaining_amount: Money, // sisa tagihan saat sale dibuat
+    pub change_amount: Money,
+    #[serde(default)]
+    pub installment_amount: Money, // cicilan yang diterima tanggal ini
+    pub refunded_amount: Money,    // uang retur yang dikembalikan tanggal ini
+
+    pub void_count: i64, // void yang dilakukan tanggal ini
+    pub void_amount: Money,
+
+    pub payment_methods: Vec<PaymentMethodTotal>, // termasuk cicilan
+}
+
+/// Tanggal yang sudah ditutup, sale baru tidak boleh masuk ke tanggal ini
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DailyClosing {
+    #[serde(
+        rename = "_id",
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "opt_object_id_as_string"
+    )]
+    pub id: Option<ObjectId>,
+
+    pub user_id: ObjectId,
+    pub date: String,
+    pub report: ZReport, // snapshot saat ditutup
+    pub notes: Option<String>,
+    pub closed_by: ObjectId,
+    pub closed_at: DateTime,
+}
+
+pub enum ZReportStatus {
+    Open(ZReport),
+    Closed(DailyClosing),
+}
+
+#[derive(Debug, Serialize)]
+pub struct ZReportResponse {
+    pub closed: bool,
+    pub closed_at: Option<String>,
+    pub notes: Option<String>,
+    pub report: ZReport,
+}
+
+impl From<ZReportStatus> for ZReportResponse {
+    fn from(status: ZReportStatus) -> Self {
+        match status {
+            ZReportStatus::Open(report) => ZReportResponse {
+                closed: false,
+                closed_at: None,
+                notes: None,
+                report,
+            },
+            ZReportStatus::Closed(closing) => ZReportResponse {
+                closed: true,
+                closed_at: Some(closing.closed_at.to_chrono().to_rfc3339()),
+                notes: closing.notes,
+                report: closing.report,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ZReportQuery {
+    pub date: Option<String>, // YYYY-MM-DD, default hari ini
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CloseDayDTO {
+    pub date: Option<String>, // YYYY-MM-DD, default hari ini
+
+    #[validate(length(max = 255, message = "Catatan maksimal 255 karakter"))]
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DailyClosingResponse {
+    pub id: String,
+    pub user_id: String,
+    pub date: String,
+    pub report: ZReport,
+    pub notes: Option<String>,
+    pub closed_by: String,
+    pub closed_at: String,
+}
+
+impl From<DailyClosing> for DailyClosingResponse {
+    fn from(closing: DailyClosing) -> Self {
+        DailyClosingResponse {
+            id: closing.id.expect("DailyClosing.id harus ada").to_hex(),
+            user_id: closing.user_id.to_hex(),
+            date: closing.date,
+            report: closing.report,
+            notes: closing.notes,
+            closed_by: closing.closed_by.to_hex(),
+            closed_at: closing.closed_at.to_chrono().to_rfc3339(),
+        }
+    }
+}
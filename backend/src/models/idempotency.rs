@@ -0,0 +1,29 @@
+use crate::utils::opt_object_id_as_string;
+use mongodb::bson::{DateTime, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+
+/// Idempotency-Key dari klien POS beserta hash request, sale dan respons yang dihasilkan.
+/// Ditulis di transaksi yang sama dengan sale, `sale_id` dan `response` hanya kosong pada key lama
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IdempotencyKey {
+    #[serde(
+        rename = "_id",
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "opt_object_id_as_string"
+    )]
+    pub id: Option<ObjectId>,
+
+    pub user_id: ObjectId,
+    pub key: String,
+    pub request_hash: String, // SHA-256 hex dari payload SaleDTO
+    pub sale_id: Option<ObjectId>,
+    #[serde(default)]
+    pub response: Option<String>, // SaleResponse (JSON) yang dikirim ke request pertama
+    pub created_at: DateTime, // dihapus otomatis oleh TTL index
+}
+
+/// Hasil POST /sales dengan Idempotency-Key
+pub enum IdempotentSale {
+    Created(Box<super::sale::Sale>),
+    Replayed(serde_json::Value), // retry: respons dari request pertama, tidak insert lagi
+}
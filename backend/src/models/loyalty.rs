@@ -0,0 +1,78 @@
+use super::money::Money;
+use crate::utils::opt_object_id_as_string;
+use mongodb::bson::{DateTime, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+
+/// Mutasi poin loyalti pelanggan. Transaksi yang menambah poin (earn, void_redeem)
+/// sekaligus menjadi "lot" dengan sisa poin dan tanggal kedaluwarsa sendiri;
+/// penukaran memakai lot yang paling cepat kedaluwarsa lebih dulu
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PointTransaction {
+    #[serde(
+        rename = "_id",
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "opt_object_id_as_string"
+    )]
+    pub id: Option<ObjectId>,
+
+    pub user_id: ObjectId,
+    pub customer_id: ObjectId,
+    pub sale_id: Option<ObjectId>,
+
+    // "earn", "redeem", "expire", "void_earn" (tarik poin sale yang di-void),
+    // "void_redeem" (kembalikan poin yang ditukar di sale yang di-void)
+    pub transaction_type: String,
+    pub points: i64, // plus menambah saldo, minus mengurangi
+    pub balance_after: i64,
+
+    #[serde(default)]
+    pub remaining_points: i64, // sisa poin lot yang belum ditukar/kedaluwarsa
+    #[serde(default)]
+    pub expires_at: Option<DateTime>,
+
+    pub created_at: DateTime,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PointHistoryQuery {
+    pub page: Option<u64>,
+    pub limit: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PointTransactionResponse {
+    pub id: String,
+    pub sale_id: Option<String>,
+    pub transaction_type: String,
+    pub points: i64,
+    pub balance_after: i64,
+    pub remaining_points: i64,
+    pub expires_at: Option<String>,
+    pub created_at: String,
+}
+
+impl From<PointTransaction> for PointTransactionResponse {
+    fn from(transaction: PointTransaction) -> Self {
+        PointTransactionResponse {
+            id: transaction
+                .id
+                .expect("PointTransaction.id harus ada")
+                .to_hex(),
+            sale_id: transaction.sale_id.map(|id| id.to_hex()),
+            transaction_type: transaction.transaction_type,
+            points: transaction.points,
+            balance_after: transaction.balance_after,
+            remaining_points: transaction.remaining_points,
+            expires_at: transaction.expires_at.map(|t| t.to_chrono().to_rfc3339()),
+            created_at: transaction.created_at.to_chrono().to_rfc3339(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct CustomerPointsResponse {
+    pub customer_id: String,
+    pub loyalty_points: i64,
+    pub points_value: Money, // nilai tukar saldo poin saat ini
+    pub transactions: Vec<PointTransactionResponse>,
+}
@@ -1,4 +1,19 @@
+pub mod customer;
+pub mod daily_closing;
+pub mod idempotency;
+pub mod loyalty;
+pub mod money;
+pub mod pagination;
 pub mod product;
+pub mod promotion;
+pub mod receivable;
 pub mod sale;
+pub mod sale_draft;
+pub mod sale_payment;
+pub mod sale_return;
+pub mod sale_sync;
+pub mod shift;
+pub mod store_setting;
 pub mod user;
+pub mod voucher;
 pub mod payment_method;
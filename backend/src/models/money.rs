@@ -0,0 +1,218 @@
+use mongodb::bson::Bson;
+use serde::{Deserialize, Serialize};
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Mul, Sub, SubAssign};
+use validator::ValidationError;
+
+/// Jumlah satuan terkecil (sen) dalam 1 rupiah
+pub const MINOR_UNITS: i64 = 100;
+
+/// Nominal uang dalam satuan terkecil (sen) supaya total tidak terkena pembulatan f64.
+/// Disimpan sebagai Int64 di BSON dan angka bulat di JSON, contoh: Rp 15.000 => 1500000
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+pub struct Money(i64);
+
+impl Money {
+    pub const ZERO: Money = Money(0);
+
+    /// Batas nominal dari input klien (Rp 1 triliun). Jauh di bawah batas i64, jadi hitungan
+    /// turunan (pajak, kembalian, total) dari nominal yang lolos batas ini tidak bisa overflow
+    pub const MAX_AMOUNT: Money = Money(100_000_000_000_000);
+
+    pub fn from_minor(minor: i64) -> Self {
+        Money(minor)
+    }
+
+    /// Dari nominal rupiah utuh, contoh: `Money::from_rupiah(15_000)`
+    pub fn from_rupiah(rupiah: i64) -> Self {
+        Money(rupiah * MINOR_UNITS)
+    }
+
+    /// Dari nominal rupiah pecahan format lama (f64), dibulatkan ke sen terdekat
+    pub fn from_rupiah_f64(rupiah: f64) -> Self {
+        Money((rupiah * MINOR_UNITS as f64).round() as i64)
+    }
+
+    pub fn minor(self) -> i64 {
+        self.0
+    }
+
+    /// Nominal dalam rupiah (f64), hanya untuk field JSON format lama
+    pub fn to_rupiah_f64(self) -> f64 {
+        self.0 as f64 / MINOR_UNITS as f64
+    }
+
+    pub fn is_positive(self) -> bool {
+        self.0 > 0
+    }
+
+    /// Persentase dari nominal ini (dibulatkan ke sen terdekat), contoh: 11% PPN
+    pub fn percent(self, rate: f64) -> Money {
+        self.scale(rate / 100.0)
+    }
+
+    /// Kalikan dengan faktor pecahan lalu bulatkan ke sen terdekat
+    pub fn scale(self, factor: f64) -> Money {
+        Money((self.0 as f64 * factor).round() as i64)
+    }
+
+    /// `self * quantity`, `None` jika hasilnya melewati `MAX_AMOUNT`
+    pub fn checked_mul(self, quantity: i64) -> Option<Money> {
+        self.0
+            .checked_mul(quantity)
+            .filter(|minor| minor.abs() <= Self::MAX_AMOUNT.0)
+            .map(Money)
+    }
+
+    /// Total nominal, `None` jika di tengah penjumlahan melewati `MAX_AMOUNT`
+    pub fn checked_sum<I: IntoIterator<Item = Money>>(amounts: I) -> Option<Money> {
+        amounts.into_iter().try_fold(Money::ZERO, |total, amount| {
+            total
+                .0
+                .checked_add(amount.0)
+                .filter(|minor| minor.abs() <= Self::MAX_AMOUNT.0)
+                .map(Money)
+        })
+    }
+
+    /// `self * numerator / denominator` dengan pembulatan ke sen terdekat,
+    /// dipakai untuk membagi nominal secara proporsional tanpa f64
+    pub fn mul_div(self, numerator: i64, denominator: i64) -> Money {
+        if denominator == 0 {
+            return Money::ZERO;
+        }
+        let product = self.0 as i128 * numerator as i128;
+        let denominator = denominator as i128;
+        let half = denominator.abs() / 2;
+        let rounded = if (product < 0) == (denominator < 0) {
+            (product.abs() + half) / denominator.abs()
+        } else {
+            -((product.abs() + half) / denominator.abs())
+        };
+        Money(rounded as i64)
+    }
+}
+
+impl Add for Money {
+    type Output = Money;
+
+    fn add(self, other: Money) -> Money {
+        Money(self.0 + other.0)
+    }
+}
+
+impl Sub for Money {
+    type Output = Money;
+
+    fn sub(self, other: Money) -> Money {
+        Money(self.0 - other.0)
+    }
+}
+
+impl AddAssign for Money {
+    fn add_assign(&mut self, other: Money) {
+        self.0 += other.0;
+    }
+}
+
+impl SubAssign for Money {
+    fn sub_assign(&mut self, other: Money) {
+        self.0 -= other.0;
+    }
+}
+
+impl Mul<i64> for Money {
+    type Output = Money;
+
+    fn mul(self, quantity: i64) -> Money {
+        Money(self.0 * quantity)
+    }
+}
+
+impl Sum for Money {
+    fn sum<I: Iterator<Item = Money>>(iter: I) -> Money {
+        iter.fold(Money::ZERO, Add::add)
+    }
+}
+
+impl<'a> Sum<&'a Money> for Money {
+    fn sum<I: Iterator<Item = &'a Money>>(iter: I) -> Money {
+        iter.copied().sum()
+    }
+}
+
+impl From<Money> for Bson {
+    fn from(money: Money) -> Bson {
+        Bson::Int64(money.0)
+    }
+}
+
+/// Validator untuk nominal yang wajib lebih dari 0
+pub fn validate_positive_money(money: &Money) -> Result<(), ValidationError> {
+    if money.is_positive() {
+        return Ok(());
+    }
+    Err(ValidationError::new("positive_money").with_message("Nominal harus lebih dari 0".into()))
+}
+
+/// Validator untuk nominal yang boleh 0 tapi tidak boleh minus
+pub fn validate_non_negative_money(money: &Money) -> Result<(), ValidationError> {
+    if money.0 >= 0 {
+        return Ok(());
+    }
+    Err(ValidationError::new("non_negative_money")
+        .with_message("Nominal tidak boleh minus".into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mul_div_rounds_half_away_from_zero() {
+        assert_eq!(Money(1_000).mul_div(1, 3), Money(333));
+        assert_eq!(Money(1_000).mul_div(2, 3), Money(667));
+        assert_eq!(Money(5).mul_div(1, 2), Money(3));
+        assert_eq!(Money(-5).mul_div(1, 2), Money(-3));
+        assert_eq!(Money(5).mul_div(-1, 2), Money(-3));
+        assert_eq!(Money(5).mul_div(1, -2), Money(-3));
+    }
+
+    #[test]
+    fn mul_div_handles_zero_denominator_and_large_values() {
+        assert_eq!(Money(1_000).mul_div(1, 0), Money::ZERO);
+        // Hasil kali melebihi i64 tapi hasil akhirnya muat
+        assert_eq!(Money(i64::MAX / 2).mul_div(4, 4), Money(i64::MAX / 2));
+    }
+
+    #[test]
+    fn rupiah_conversions_round_to_nearest_sen() {
+        assert_eq!(Money::from_rupiah(15_000), Money(1_500_000));
+        assert_eq!(Money::from_rupiah_f64(15_000.5), Money(1_500_050));
+        assert_eq!(Money::from_rupiah_f64(0.015), Money(2));
+        assert_eq!(Money(1_500_050).to_rupiah_f64(), 15_000.5);
+        assert_eq!(Money(1_000).percent(11.0), Money(110));
+    }
+
+    #[test]
+    fn checked_mul_rejects_amounts_over_the_limit() {
+        let price = Money::from_rupiah(15_000);
+        assert_eq!(price.checked_mul(3), Some(Money::from_rupiah(45_000)));
+        assert_eq!(price.checked_mul(2_000_000_000), None);
+        assert_eq!(Money::from_minor(i64::MAX).checked_mul(2), None);
+    }
+
+    #[test]
+    fn checked_sum_rejects_overflow_and_amounts_over_the_limit() {
+        let amounts = [Money::from_rupiah(10_000), Money::from_rupiah(5_000)];
+        assert_eq!(Money::checked_sum(amounts), Some(Money::from_rupiah(15_000)));
+        assert_eq!(Money::checked_sum([Money::MAX_AMOUNT, Money::from_minor(1)]), None);
+        assert_eq!(
+            Money::checked_sum([Money::from_minor(i64::MAX), Money::from_minor(i64::MAX)]),
+            None
+        );
+    }
+}
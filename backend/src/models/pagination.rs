@@ -0,0 +1,58 @@
+use serde::Serialize;
+
+pub const DEFAULT_PAGE_LIMIT: u64 = 20;
+pub const MAX_PAGE_LIMIT: u64 = 100;
+/// Batas page supaya `(page - 1) * limit` untuk skip tidak overflow
+pub const MAX_PAGE: u64 = 1_000_000;
+
+/// Metadata paging yang dikirim bersama data list
+#[derive(Debug, Serialize)]
+pub struct Pagination {
+    pub page: u64,
+    pub limit: u64,
+    pub total: u64,
+    pub total_pages: u64,
+}
+
+impl Pagination {
+    pub fn new(page: u64, limit: u64, total: u64) -> Self {
+        Pagination {
+            page,
+            limit,
+            total,
+            total_pages: total.div_ceil(limit.max(1)),
+        }
+    }
+}
+
+/// Normalisasi page/limit dari query: page 1..=MAX_PAGE, limit 1..=MAX_PAGE_LIMIT
+pub fn page_and_limit(page: Option<u64>, limit: Option<u64>) -> (u64, u64) {
+    let page = page.unwrap_or(1).clamp(1, MAX_PAGE);
+    let limit = limit.unwrap_or(DEFAULT_PAGE_LIMIT).clamp(1, MAX_PAGE_LIMIT);
+    (page, limit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn page_and_limit_clamps_values_from_query() {
+        assert_eq!(page_and_limit(None, None), (1, DEFAULT_PAGE_LIMIT));
+        assert_eq!(page_and_limit(Some(0), Some(0)), (1, 1));
+        assert_eq!(
+            page_and_limit(Some(u64::MAX), Some(u64::MAX)),
+            (MAX_PAGE, MAX_PAGE_LIMIT)
+        );
+    }
+
+    #[test]
+    fn largest_page_skip_does_not_overflow() {
+        let (page, limit) = page_and_limit(Some(u64::MAX), Some(u64::MAX));
+        assert!(
+            (page - 1)
+                .checked_mul(limit)
+                .is_some_and(|skip| skip <= i64::MAX as u64)
+        );
+    }
+}
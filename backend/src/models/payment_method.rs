@@ -4,7 +4,7 @@ use serde::{Serialize, Deserialize};
 use validator::Validate;
 
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct PaymentMethod {
     #[serde(
         rename = "_id",
@@ -13,15 +13,45 @@ pub struct PaymentMethod {
     )]
     pub id: Option<ObjectId>,
     pub name: String,
-    pub is_active: bool
+    pub is_active: bool,
+    #[serde(default)]
+    pub is_cash: bool, // hanya pembayaran tunai yang boleh lebih bayar (ada kembalian)
 }
 
-
 #[derive(Debug, Deserialize, Serialize, Validate)]
 pub struct PaymentMethodDTO {
     #[validate(length(min = 1, message = "Kolom name wajib diisi!"))]
     pub name: String,
     #[serde(default = "default_is_active")]
-    pub is_active: bool
+    pub is_active: bool,
+    #[serde(default)]
+    pub is_cash: bool,
+}
+
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdatePaymentMethodDTO {
+    #[validate(length(min = 1, message = "Kolom name wajib diisi!"))]
+    pub name: Option<String>,
+    pub is_active: Option<bool>,
+    pub is_cash: Option<bool>,
 }
 
+#[derive(Debug, Serialize)]
+pub struct PaymentMethodResponse {
+    pub id: String,
+    pub name: String,
+    pub is_active: bool,
+    pub is_cash: bool,
+}
+
+impl From<PaymentMethod> for PaymentMethodResponse {
+    fn from(method: PaymentMethod) -> Self {
+        PaymentMethodResponse {
+            id: method.id.expect("PaymentMethod.id harus ada").to_hex(),
+            name: method.name,
+            is_active: method.is_active,
+            is_cash: method.is_cash,
+        }
+    }
+}
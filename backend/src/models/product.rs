@@ -1,7 +1,8 @@
+use super::money::Money;
 use crate::utils::opt_object_id_as_string;
 use bson::{DateTime, oid::ObjectId};
 use serde::{Deserialize, Serialize};
-use validator::Validate;
+use validator::{Validate, ValidationError};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Product {
@@ -14,35 +15,95 @@ pub struct Product {
     pub user_id: ObjectId,
     pub name: String,
     pub sku: String,
-    pub price: f64,
+    pub price: Money,
     pub stock: u32,
 
     #[serde(serialize_with = "opt_object_id_as_string")]
     pub category_id: Option<ObjectId>,
 
+    // Pajak: tarif khusus produk (override tarif toko) atau bebas pajak
+    #[serde(default)]
+    pub tax_rate: Option<f64>,
+    #[serde(default)]
+    pub tax_exempt: bool,
+
+    // Nomor urut perubahan katalog toko, naik setiap produk dibuat/diubah (termasuk stok)
+    #[serde(default)]
+    pub change_seq: i64,
+
     #[serde(default)]
     pub created_at: Option<DateTime>,
     #[serde(default)]
     pub updated_at: Option<DateTime>,
 }
 
+fn validate_price(price: &Money) -> Result<(), ValidationError> {
+    if *price >= Money::from_rupiah(100) {
+        return Ok(());
+    }
+    Err(ValidationError::new("min_price").with_message("Harga minimal Rp 100".into()))
+}
+
+/// Harga bisa dikirim sebagai `price_minor` (sen) atau `price` (rupiah, format klien lama),
+/// tidak boleh keduanya
+fn resolve_price(
+    price: Option<f64>,
+    price_minor: Option<Money>,
+) -> Result<Option<Money>, ValidationError> {
+    let resolved = match (price, price_minor) {
+        (Some(_), Some(_)) => {
+            return Err(ValidationError::new("price").with_message(
+                "Kirim price (rupiah) atau price_minor (sen), tidak keduanya".into(),
+            ));
+        }
+        (Some(rupiah), None) => Some(Money::from_rupiah_f64(rupiah)),
+        (None, minor) => minor,
+    };
+    if let Some(price) = &resolved {
+        validate_price(price)?;
+    }
+    Ok(resolved)
+}
+
 #[derive(Debug, Deserialize, Validate)]
+#[validate(schema(function = "validate_product_price"))]
 pub struct ProductDTO {
     #[validate(length(min = 1, message = "Kolom name wajib diisi!"))]
     pub name: String,
     pub sku: Option<String>,
 
-    #[validate(range(min = 100.0, message = "Harga minimal 100"))]
-    pub price: f64,
+    pub price: Option<f64>,         // rupiah, format lama
+    pub price_minor: Option<Money>, // sen
 
     #[validate(range(max = 99999, message = "Stok maksimal 99999"))]
     pub stock: u32,
 
     // Optional: kategori (boleh kosong)
     pub category_id: Option<String>,
+
+    #[validate(range(min = 0.0, max = 100.0, message = "Tarif pajak harus 0-100%"))]
+    pub tax_rate: Option<f64>,
+
+    #[serde(default)]
+    pub tax_exempt: bool,
+}
+
+impl ProductDTO {
+    pub fn price(&self) -> Option<Money> {
+        resolve_price(self.price, self.price_minor).ok().flatten()
+    }
+}
+
+fn validate_product_price(payload: &ProductDTO) -> Result<(), ValidationError> {
+    match resolve_price(payload.price, payload.price_minor)? {
+        Some(_) => Ok(()),
+        None => Err(ValidationError::new("price")
+            .with_message("Kolom price_minor (sen) atau price (rupiah) wajib diisi".into())),
+    }
 }
 
 #[derive(Debug, Deserialize, Validate)]
+#[validate(schema(function = "validate_update_product_price"))]
 pub struct UpdateProductDTO {
     #[validate(length(min = 1, message = "Kolom name tidak boleh kosong"))]
     pub name: Option<String>,
@@ -52,12 +113,29 @@ pub struct UpdateProductDTO {
     #[validate(range(max = 99999, message = "Stok maksimal 99999"))]
     pub stock: Option<u32>,
 
-    #[validate(range(min = 100.0, message = "Harga minimal 100"))]
-    pub price: Option<f64>,
+    pub price: Option<f64>,         // rupiah, format lama
+    pub price_minor: Option<Money>, // sen
 
     pub category_id: Option<String>,
+
+    #[validate(range(min = 0.0, max = 100.0, message = "Tarif pajak harus 0-100%"))]
+    pub tax_rate: Option<f64>,
+
+    pub tax_exempt: Option<bool>,
 }
 
+impl UpdateProductDTO {
+    pub fn price(&self) -> Option<Money> {
+        resolve_price(self.price, self.price_minor).ok().flatten()
+    }
+}
+
+fn validate_update_product_price(payload: &UpdateProductDTO) -> Result<(), ValidationError> {
+    resolve_price(payload.price, payload.price_minor).map(|_| ())
+}
+
+/// `price` tetap dalam rupiah supaya klien lama tidak salah membaca harga 100x lipat,
+/// klien baru memakai `price_minor` (sen) seperti nominal uang lain di API
 #[derive(Debug, Serialize)]
 pub struct ProductResponse {
     pub id: String,
@@ -67,9 +145,13 @@ pub struct ProductResponse {
     pub name: String,
     pub sku: String,
     pub price: f64,
+    pub price_minor: Money,
     pub stock: u32,
 
     pub category_id: Option<String>,
+    pub tax_rate: Option<f64>,
+    pub tax_exempt: bool,
+    pub change_seq: i64,
     pub created_at: Option<String>,
     pub updated_at: Option<String>,
 }
@@ -84,11 +166,87 @@ impl From<Product> for ProductResponse {
             user_id: p.user_id.to_hex(),
             name: p.name,
             sku: p.sku,
-            price: p.price,
+            price: p.price.to_rupiah_f64(),
+            price_minor: p.price,
             stock: p.stock,
             category_id: p.category_id.map(|c| c.to_hex()),
+            tax_rate: p.tax_rate,
+            tax_exempt: p.tax_exempt,
+            change_seq: p.change_seq,
             created_at: p.created_at.map(|t| t.to_chrono().to_rfc3339()),
             updated_at: p.updated_at.map(|t| t.to_chrono().to_rfc3339()),
         }
     }
 }
+
+/// Penanda produk yang sudah dihapus supaya klien offline ikut menghapusnya
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProductTombstone {
+    #[serde(
+        rename = "_id",
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "opt_object_id_as_string"
+    )]
+    pub id: Option<ObjectId>,
+
+    pub user_id: ObjectId,
+    pub product_id: ObjectId,
+    pub change_seq: i64,
+    pub deleted_at: DateTime,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ProductChangesQuery {
+    pub since: Option<i64>, // next_token dari respons sebelumnya, kosong = seluruh katalog
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeletedProductResponse {
+    pub product_id: String,
+    pub change_seq: i64,
+    pub deleted_at: String,
+}
+
+impl From<ProductTombstone> for DeletedProductResponse {
+    fn from(tombstone: ProductTombstone) -> Self {
+        DeletedProductResponse {
+            product_id: tombstone.product_id.to_hex(),
+            change_seq: tombstone.change_seq,
+            deleted_at: tombstone.deleted_at.to_chrono().to_rfc3339(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProductChangesResponse {
+    pub products: Vec<ProductResponse>,
+    pub deleted: Vec<DeletedProductResponse>,
+    pub next_token: i64, // kirim sebagai `since` pada permintaan berikutnya
+    pub has_more: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_price_accepts_rupiah_or_minor_units() {
+        assert_eq!(
+            resolve_price(Some(15_000.0), None).unwrap(),
+            Some(Money::from_rupiah(15_000))
+        );
+        assert_eq!(
+            resolve_price(None, Some(Money::from_rupiah(15_000))).unwrap(),
+            Some(Money::from_rupiah(15_000))
+        );
+        assert_eq!(resolve_price(None, None).unwrap(), None);
+    }
+
+    #[test]
+    fn resolve_price_rejects_both_units_and_low_price() {
+        assert!(resolve_price(Some(15_000.0), Some(Money::from_rupiah(15_000))).is_err());
+        assert!(resolve_price(Some(99.0), None).is_err());
+        assert!(resolve_price(None, Some(Money::from_minor(9_999))).is_err());
+    }
+}
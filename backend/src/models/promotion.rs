@@ -0,0 +1,134 @@
+use super::money::Money;
+use crate::utils::opt_object_id_as_string;
+use chrono::{DateTime as ChronoDateTime, Utc};
+use mongodb::bson::{DateTime, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+/// Aturan potongan promo, disimpan dengan field `type` sebagai penanda jenisnya
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PromotionRule {
+    /// Beli `buy_quantity` gratis `get_quantity`, unit termurah di tiap kelompok yang gratis
+    BuyXGetY {
+        buy_quantity: i32,
+        get_quantity: i32,
+    },
+    /// `quantity` unit dengan harga paket `price`
+    Bundle { quantity: i32, price: Money },
+    /// Diskon `percent` persen jika jumlah unit minimal `min_quantity`
+    QuantityDiscount { min_quantity: i32, percent: f64 },
+}
+
+/// Promo otomatis di kasir. Berlaku untuk produk di `product_ids` atau produk yang
+/// kategorinya ada di `category_ids`, selama `active` dan masih dalam masa berlaku
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Promotion {
+    #[serde(
+        rename = "_id",
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "opt_object_id_as_string"
+    )]
+    pub id: Option<ObjectId>,
+
+    pub user_id: ObjectId,
+    pub name: String,
+    pub rule: PromotionRule,
+
+    #[serde(default)]
+    pub product_ids: Vec<ObjectId>,
+    #[serde(default)]
+    pub category_ids: Vec<ObjectId>,
+
+    pub starts_at: DateTime,
+    pub ends_at: DateTime, // inklusif
+    pub active: bool,
+
+    #[serde(default)]
+    pub created_at: Option<DateTime>,
+    #[serde(default)]
+    pub updated_at: Option<DateTime>,
+}
+
+impl Promotion {
+    pub fn applies_to(&self, product_id: ObjectId, category_id: Option<ObjectId>) -> bool {
+        self.product_ids.contains(&product_id)
+            || category_id.is_some_and(|c| self.category_ids.contains(&c))
+    }
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct PromotionDTO {
+    #[validate(length(min = 1, max = 100, message = "Nama promo 1-100 karakter"))]
+    pub name: String,
+
+    pub rule: PromotionRule,
+
+    #[serde(default)]
+    pub product_ids: Vec<ObjectId>,
+    #[serde(default)]
+    pub category_ids: Vec<ObjectId>,
+
+    pub starts_at: ChronoDateTime<Utc>, // RFC 3339
+    pub ends_at: ChronoDateTime<Utc>,
+
+    pub active: Option<bool>, // default true
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdatePromotionDTO {
+    #[validate(length(min = 1, max = 100, message = "Nama promo 1-100 karakter"))]
+    pub name: Option<String>,
+
+    pub rule: Option<PromotionRule>,
+    pub product_ids: Option<Vec<ObjectId>>, // menggantikan daftar lama
+    pub category_ids: Option<Vec<ObjectId>>,
+    pub starts_at: Option<ChronoDateTime<Utc>>,
+    pub ends_at: Option<ChronoDateTime<Utc>>,
+    pub active: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PromotionListQuery {
+    pub search: Option<String>, // pencarian sebagian nama promo
+    pub active: Option<bool>,
+    pub page: Option<u64>,
+    pub limit: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PromotionResponse {
+    pub id: String,
+    pub user_id: String,
+    pub name: String,
+    pub rule: PromotionRule,
+    pub product_ids: Vec<String>,
+    pub category_ids: Vec<String>,
+    pub starts_at: String,
+    pub ends_at: String,
+    pub active: bool,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+}
+
+impl From<Promotion> for PromotionResponse {
+    fn from(promotion: Promotion) -> Self {
+        PromotionResponse {
+            id: promotion.id.expect("Promotion.id harus ada").to_hex(),
+            user_id: promotion.user_id.to_hex(),
+            name: promotion.name,
+            rule: promotion.rule,
+            product_ids: promotion.product_ids.iter().map(|id| id.to_hex()).collect(),
+            category_ids: promotion
+                .category_ids
+                .iter()
+                .map(|id| id.to_hex())
+                .collect(),
+            starts_at: promotion.starts_at.to_chrono().to_rfc3339(),
+            ends_at: promotion.ends_at.to_chrono().to_rfc3339(),
+            active: promotion.active,
+            created_at: promotion.created_at.map(|t| t.to_chrono().to_rfc3339()),
+            updated_at: promotion.updated_at.map(|t| t.to_chrono().to_rfc3339()),
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use super::money::Money;
+use serde::{Deserialize, Serialize};
+
+/// Saldo piutang satu pelanggan dari `Sale.remaining_amount` sale yang belum lunas.
+/// Sale tanpa pelanggan dikelompokkan dengan `customer_id` kosong
+#[derive(Debug, Serialize)]
+pub struct CustomerBalance {
+    pub customer_id: Option<String>,
+    pub customer_name: String,
+    pub phone: Option<String>,
+    pub outstanding_amount: Money,
+    pub invoice_count: i64,
+    pub oldest_invoice_date: Option<String>, // YYYY-MM-DD, zona waktu toko
+}
+
+/// Umur piutang dalam kelompok hari sejak tanggal sale
+#[derive(Debug, Default, Serialize)]
+pub struct AgingBuckets {
+    pub days_0_30: Money,
+    pub days_31_60: Money,
+    pub days_61_90: Money,
+    pub days_over_90: Money,
+    pub total: Money,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CustomerAging {
+    pub customer_id: Option<String>,
+    pub customer_name: String,
+    #[serde(flatten)]
+    pub buckets: AgingBuckets,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AgingReport {
+    pub as_of: String, // YYYY-MM-DD, zona waktu toko
+    pub totals: AgingBuckets,
+    pub customers: Vec<CustomerAging>,
+}
+
+/// Satu baris mutasi piutang: invoice menambah saldo, pembayaran menguranginya,
+/// retur mengurangi sebesar nilai barang dan menambah kembali uang yang di-refund
+#[derive(Debug, Serialize)]
+pub struct StatementEntry {
+    pub date: String,
+    pub entry_type: String, // "invoice", "payment", "return"
+    pub sale_id: String,
+    pub invoice_number: Option<String>,
+    pub description: String,
+    pub debit: Money,
+    pub credit: Money,
+    pub balance: Money,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CustomerStatement {
+    pub customer_id: String,
+    pub customer_name: String,
+    pub date_from: Option<String>,
+    pub date_to: Option<String>,
+    pub opening_balance: Money,
+    pub total_debit: Money,
+    pub total_credit: Money,
+    pub closing_balance: Money,
+    pub entries: Vec<StatementEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StatementQuery {
+    pub date_from: Option<String>, // YYYY-MM-DD
+    pub date_to: Option<String>,   // YYYY-MM-DD, inklusif
+}
@@ -1,21 +1,192 @@
-use crate::utils::{object_id_as_string, opt_object_id_as_string};
+use crate::utils::opt_object_id_as_string;
 use mongodb::bson::{DateTime, oid::ObjectId};
 use serde::{Deserialize, Serialize};
 use validator::Validate;
+use super::money::{Money, validate_non_negative_money, validate_positive_money};
 use super::payment_method::PaymentMethod;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DiscountType {
+    Percent,
+    Fixed,
+}
+
+/// Rincian diskon yang tersimpan: input kasir + nominal hasil hitungan.
+/// Diskon fixed cukup `amount`, nominal yang diinput sama dengan potongannya
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Discount {
+    pub discount_type: DiscountType,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub percent: Option<f64>, // hanya untuk diskon persen
+    pub amount: Money,
+}
+
+impl Discount {
+    /// Label untuk invoice/struk, contoh: "Diskon 10%" atau "Diskon"
+    pub fn label(&self) -> String {
+        match self.discount_type {
+            DiscountType::Percent => format!("Diskon {}%", self.percent.unwrap_or_default()),
+            DiscountType::Fixed => "Diskon".to_string(),
+        }
+    }
+}
+
+/// Promo otomatis yang mengenai item, `amount` adalah potongan untuk baris ini
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SaleItemPromotion {
+    pub promotion_id: ObjectId,
+    pub name: String,
+    pub amount: Money,
+}
+
+impl SaleItemPromotion {
+    /// Label untuk invoice/struk, contoh: "Promo Beli 2 Gratis 1"
+    pub fn label(&self) -> String {
+        format!("Promo {}", self.name)
+    }
+}
+
+/// Voucher yang dipakai di sale, potongannya dihitung setelah diskon transaksi
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SaleVoucher {
+    pub voucher_id: ObjectId,
+    pub code: String,
+    pub amount: Money,
+}
+
+impl SaleVoucher {
+    /// Label untuk invoice/struk, contoh: "Voucher HEMAT10"
+    pub fn label(&self) -> String {
+        format!("Voucher {}", self.code)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct SaleItem {
-    #[serde(serialize_with = "object_id_as_string")]
     pub product_id: ObjectId,
     pub product_name: String,
     pub sku: String,
     pub quantity: i32,
-    pub price: f64,
-    pub subtotal: f64
+    pub price: Money,
+    pub subtotal: Money, // price * quantity - diskon item - potongan promo
+
+    #[serde(default)]
+    pub discount: Option<Discount>,
+    #[serde(default)]
+    pub promotion: Option<SaleItemPromotion>, // item dengan diskon manual tidak ikut promo
+
+    #[serde(default)]
+    pub tax_rate: f64,
+    #[serde(default)]
+    pub tax_amount: Money,
+
+    #[serde(default)]
+    pub returned_quantity: i32,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl Sale {
+    /// Nominal yang benar-benar dibayar untuk `quantity` unit item ini: setelah diskon item,
+    /// porsi diskon transaksi, dan ditambah pajak jika harga belum termasuk pajak
+    pub fn paid_amount_for(&self, item: &SaleItem, quantity: i32) -> Money {
+        if item.quantity <= 0 {
+            return Money::ZERO;
+        }
+
+        let order_discount = self.discount.as_ref().map(|d| d.amount).unwrap_or_default()
+            + self.voucher.as_ref().map(|v| v.amount).unwrap_or_default();
+        let mut line_total = if self.subtotal_amount.is_positive() {
+            item.subtotal.mul_div(
+                (self.subtotal_amount - order_discount).minor(),
+                self.subtotal_amount.minor(),
+            )
+        } else {
+            item.subtotal
+        };
+        if !self.tax_inclusive {
+            line_total += item.tax_amount;
+        }
+
+        line_total.mul_div(quantity as i64, item.quantity as i64)
+    }
+
+    /// Uang tunai bersih yang masuk ke laci untuk sale ini (tender tunai - kembalian)
+    pub fn cash_paid_amount(&self) -> Money {
+        self.payment_tenders()
+            .iter()
+            .filter(|t| t.payment_method.is_cash)
+            .map(|t| t.amount - t.change_amount)
+            .sum()
+    }
+
+    /// Daftar pembayaran sale; sale lama (sebelum split tender) dianggap satu pembayaran
+    pub fn payment_tenders(&self) -> Vec<SaleTender> {
+        if !self.tenders.is_empty() {
+            return self.tenders.clone();
+        }
+
+        match &self.payment_method {
+            // Sale lama menyimpan uang yang diterima apa adanya, kelebihannya adalah kembalian
+            Some(payment_method) if self.paid_amount.is_positive() => vec![SaleTender {
+                payment_method: payment_method.clone(),
+                amount: self.paid_amount,
+                change_amount: (self.paid_amount - self.total_amount).max(Money::ZERO),
+                shift_id: self.shift_id,
+                payment_id: None,
+            }],
+            _ => Vec::new(),
+        }
+    }
+
+    /// Tender yang diterima saat sale dibuat, tanpa cicilan. Tender sintetis sale lama berisi
+    /// seluruh `paid_amount` termasuk cicilan, jadi nominalnya dikurangi `installment_amount`
+    pub fn sale_time_tenders(&self, installment_amount: Money) -> Vec<SaleTender> {
+        if !self.tenders.is_empty() {
+            return self
+                .tenders
+                .iter()
+                .filter(|t| t.payment_id.is_none())
+                .cloned()
+                .collect();
+        }
+
+        self.payment_tenders()
+            .into_iter()
+            .filter_map(|mut tender| {
+                tender.amount = self.paid_amount - installment_amount;
+                tender.change_amount = (tender.amount - self.total_amount).max(Money::ZERO);
+                tender.amount.is_positive().then_some(tender)
+            })
+            .collect()
+    }
+}
+
+/// Satu pembayaran dalam sale, sale bisa dibayar dengan beberapa metode sekaligus
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SaleTender {
+    pub payment_method: PaymentMethod,
+    pub amount: Money, // uang yang diserahkan pelanggan
+
+    #[serde(default)]
+    pub change_amount: Money, // kembalian dari tender ini (hanya tunai)
+
+    // Shift kasir yang menerima uangnya
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shift_id: Option<ObjectId>,
+
+    // Cicilan (sale_payments) yang menambahkan tender ini, kosong untuk pembayaran saat sale
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payment_id: Option<ObjectId>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SaleVoid {
+    pub reason: String,
+    pub voided_by: ObjectId,
+    pub voided_at: DateTime,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Sale {
     #[serde(
         rename = "_id",
@@ -24,23 +195,60 @@ pub struct Sale {
     )]
     pub id: Option<ObjectId>,
 
-    #[serde(serialize_with = "object_id_as_string")]
     pub user_id: ObjectId,
     pub customer_id: Option<ObjectId>,
     pub items: Vec<SaleItem>,
 
-    pub total_amount: f64,
+    #[serde(default)]
+    pub subtotal_amount: Money, // jumlah subtotal item, sebelum diskon transaksi
+    #[serde(default)]
+    pub discount: Option<Discount>, // diskon level transaksi
+    #[serde(default)]
+    pub voucher: Option<SaleVoucher>,
+    #[serde(default)]
+    pub total_discount: Money, // diskon item + promo + diskon transaksi + voucher
+
+    // Pajak dihitung setelah semua diskon
+    #[serde(default)]
+    pub tax_inclusive: bool,
+    #[serde(default)]
+    pub tax_base: Money, // DPP
+    #[serde(default)]
+    pub tax_amount: Money,
 
-    pub paid_amount: f64,
-    pub remaining_amount: f64,
-    pub status: String, // "paid", "partial", "unpaid"
+    pub total_amount: Money, // grand total yang harus dibayar (termasuk pajak)
+
+    pub paid_amount: Money, // jumlah yang masuk ke tagihan (uang diterima - kembalian)
+    pub remaining_amount: Money,
+    pub status: String, // "paid", "partial", "unpaid", "voided"
+
+    #[serde(default)]
+    pub change_amount: Money,
+
+    #[serde(default)]
+    pub refunded_amount: Money,
+
+    #[serde(default)]
+    pub points_earned: i64,
+    #[serde(default)]
+    pub points_redeemed: i64,
 
     pub invoice_number: Option<String>,
+    #[serde(default)]
+    pub client_id: Option<String>, // ID dari perangkat POS untuk sale hasil sinkronisasi offline
 
-    pub payment_method: Option<PaymentMethod>,
+    #[serde(default)]
+    pub tenders: Vec<SaleTender>,
+    #[serde(default)]
+    pub payment_method: Option<PaymentMethod>, // hanya terisi di sale lama
+    #[serde(default)]
+    pub shift_id: Option<ObjectId>, // shift kasir yang terbuka saat sale dibuat
     pub sale_date: Option<DateTime>,
     pub notes: Option<String>,
 
+    #[serde(default)]
+    pub void: Option<SaleVoid>,
+
     #[serde(default)]
     pub created_at: Option<DateTime>,
     #[serde(default)]
@@ -53,9 +261,25 @@ pub struct SaleItemDTO {
 
     #[validate(range(min = 1, message = "Jumlah item minimal 1"))]
     pub quantity: i32,
+
+    #[validate(nested)]
+    pub discount: Option<DiscountDTO>,
 }
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, Serialize, Validate, Clone)]
+pub struct DiscountDTO {
+    pub discount_type: DiscountType, // "percent" atau "fixed"
+
+    // Diisi sesuai discount_type: `percent` untuk diskon persen, `amount` (sen) untuk fixed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[validate(range(min = 0.0, max = 100.0, message = "Diskon persen harus antara 0 dan 100"))]
+    pub percent: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[validate(custom(function = "validate_non_negative_money", message = "Nominal diskon tidak boleh negatif"))]
+    pub amount: Option<Money>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
 pub struct SaleDTO {
     pub customer_id: Option<ObjectId>,
 
@@ -63,17 +287,178 @@ pub struct SaleDTO {
     #[validate(nested)]
     pub items: Vec<SaleItemDTO>,
 
+    #[validate(nested)]
+    pub discount: Option<DiscountDTO>,
+
+    // Boleh kosong (belum bayar) atau lebih dari satu metode pembayaran
+    #[serde(default)]
+    #[validate(nested)]
+    pub tenders: Vec<TenderDTO>,
+
+    // Format lama sebelum split tender, dipetakan menjadi satu tender di `create_sale`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub payment_method_id: Option<ObjectId>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[validate(custom(function = "validate_non_negative_money", message = "Jumlah bayar tidak boleh negatif"))]
+    pub paid_amount: Option<Money>,
 
-    #[validate(range(min = 0.0, message = "Jumlah bayar tidak boleh negatif"))]
-    pub paid_amount: f64,
+    // Tukar poin loyalti pelanggan sebagai pembayaran, butuh customer_id
+    #[validate(range(min = 1, message = "Poin yang ditukar minimal 1"))]
+    pub redeem_points: Option<i64>,
+
+    #[validate(length(min = 1, max = 32, message = "Kode voucher 1-32 karakter"))]
+    pub voucher_code: Option<String>,
 
     #[validate(length(min = 0, max = 255, message = "Catatan maksimal 255 karakter"))]
     pub notes: Option<String>,
 }
 
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct TenderDTO {
+    pub payment_method_id: ObjectId,
+
+    #[validate(custom(function = "validate_positive_money", message = "Jumlah bayar harus lebih dari 0"))]
+    pub amount: Money,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct VoidSaleDTO {
+    #[validate(length(min = 1, max = 255, message = "Alasan void wajib diisi (maksimal 255 karakter)"))]
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SaleVoidResponse {
+    pub reason: String,
+    pub voided_by: String,
+    pub voided_at: String,
+}
+
+impl From<SaleVoid> for SaleVoidResponse {
+    fn from(void: SaleVoid) -> Self {
+        SaleVoidResponse {
+            reason: void.reason,
+            voided_by: void.voided_by.to_hex(),
+            voided_at: void.voided_at.to_chrono().to_rfc3339(),
+        }
+    }
+}
+
+/// Query GET /api/sales: filter, urutan dan paging
+#[derive(Debug, Deserialize)]
+pub struct SaleListQuery {
+    pub date_from: Option<String>, // YYYY-MM-DD, berdasarkan sale_date
+    pub date_to: Option<String>,   // YYYY-MM-DD, inklusif
+    pub status: Option<String>,
+    pub payment_method_id: Option<String>,
+    pub customer_id: Option<String>,
+    pub shift_id: Option<String>,
+    pub invoice: Option<String>, // pencarian sebagian nomor invoice
+    pub sort_by: Option<String>, // sale_date, total_amount, invoice_number, created_at
+    pub sort_dir: Option<String>, // asc atau desc
+    pub page: Option<u64>,
+    pub limit: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReceiptQuery {
+    pub paper_width: Option<u32>, // 58 atau 80, default dari pengaturan toko
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SaleSummary {
+    pub transaction_count: i64,
+    pub total_amount: Money,
+    pub paid_amount: Money,
+    pub remaining_amount: Money,
+    pub refunded_amount: Money,
+    pub total_discount: Money,
+    pub tax_amount: Money,
+    #[serde(default)]
+    pub change_amount: Money,
+
+    #[serde(default)]
+    pub payment_methods: Vec<PaymentMethodTotal>,
+}
+
+/// Total uang masuk per metode pembayaran
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PaymentMethodTotal {
+    #[serde(alias = "_id")] // hasil $group aggregate
+    pub payment_method_id: Option<String>,
+    pub name: String,
+    pub tendered_amount: Money, // uang yang diterima
+    pub change_amount: Money,
+    pub amount: Money, // tendered_amount - change_amount
+    pub transaction_count: i64,
+}
 
+#[derive(Debug, Serialize)]
+pub struct SaleItemPromotionResponse {
+    pub promotion_id: String,
+    pub name: String,
+    pub amount: Money,
+}
 
+impl From<SaleItemPromotion> for SaleItemPromotionResponse {
+    fn from(promotion: SaleItemPromotion) -> Self {
+        SaleItemPromotionResponse {
+            promotion_id: promotion.promotion_id.to_hex(),
+            name: promotion.name,
+            amount: promotion.amount,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct SaleItemResponse {
+    pub product_id: String,
+    pub product_name: String,
+    pub sku: String,
+    pub quantity: i32,
+    pub price: Money,
+    pub subtotal: Money,
+    pub discount: Option<Discount>,
+    pub promotion: Option<SaleItemPromotionResponse>,
+    pub tax_rate: f64,
+    pub tax_amount: Money,
+    pub returned_quantity: i32,
+}
+
+impl From<SaleItem> for SaleItemResponse {
+    fn from(item: SaleItem) -> Self {
+        SaleItemResponse {
+            product_id: item.product_id.to_hex(),
+            product_name: item.product_name,
+            sku: item.sku,
+            quantity: item.quantity,
+            price: item.price,
+            subtotal: item.subtotal,
+            discount: item.discount,
+            promotion: item.promotion.map(SaleItemPromotionResponse::from),
+            tax_rate: item.tax_rate,
+            tax_amount: item.tax_amount,
+            returned_quantity: item.returned_quantity,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct SaleVoucherResponse {
+    pub voucher_id: String,
+    pub code: String,
+    pub amount: Money,
+}
+
+impl From<SaleVoucher> for SaleVoucherResponse {
+    fn from(voucher: SaleVoucher) -> Self {
+        SaleVoucherResponse {
+            voucher_id: voucher.voucher_id.to_hex(),
+            code: voucher.code,
+            amount: voucher.amount,
+        }
+    }
+}
 
 #[derive(Debug, Serialize)]
 pub struct SaleResponse {
@@ -81,18 +466,32 @@ pub struct SaleResponse {
     pub user_id: String,
 
     pub customer_id: Option<String>,
-    pub items: Vec<SaleItem>,
+    pub items: Vec<SaleItemResponse>,
 
-    pub total_amount: f64,
+    pub subtotal_amount: Money,
+    pub discount: Option<Discount>,
+    pub voucher: Option<SaleVoucherResponse>,
+    pub total_discount: Money,
+    pub tax_inclusive: bool,
+    pub tax_base: Money,
+    pub tax_amount: Money,
+    pub total_amount: Money,
 
-    pub paid_amount: f64,
-    pub remaining_amount: f64,
+    pub paid_amount: Money,
+    pub remaining_amount: Money,
+    pub change_amount: Money,
     pub status: String,
+    pub refunded_amount: Money,
+    pub points_earned: i64,
+    pub points_redeemed: i64,
 
     pub invoice_number: Option<String>,
-    pub payment_method: Option<PaymentMethod>,
+    pub client_id: Option<String>,
+    pub tenders: Vec<SaleTender>,
+    pub shift_id: Option<String>,
     pub sale_date: Option<String>,
     pub notes: Option<String>,
+    pub void: Option<SaleVoidResponse>,
 
     #[serde(default)]
     pub created_at: Option<String>,
@@ -103,22 +502,38 @@ pub struct SaleResponse {
 
 impl From<Sale> for SaleResponse {
     fn from(sale: Sale) -> Self {
+        let tenders = sale.payment_tenders();
+
         SaleResponse {
             id: sale.id.expect("Sale.id harus ada").to_hex(),
             user_id: sale.user_id.to_hex(),
             customer_id: sale.customer_id.map(|id| id.to_hex()),
-            items: sale.items,
+            items: sale.items.into_iter().map(SaleItemResponse::from).collect(),
 
+            subtotal_amount: sale.subtotal_amount,
+            discount: sale.discount,
+            voucher: sale.voucher.map(SaleVoucherResponse::from),
+            total_discount: sale.total_discount,
+            tax_inclusive: sale.tax_inclusive,
+            tax_base: sale.tax_base,
+            tax_amount: sale.tax_amount,
             total_amount: sale.total_amount,
 
             paid_amount: sale.paid_amount,
             remaining_amount: sale.remaining_amount,
+            change_amount: sale.change_amount,
             status: sale.status,
+            refunded_amount: sale.refunded_amount,
+            points_earned: sale.points_earned,
+            points_redeemed: sale.points_redeemed,
 
+            tenders,
             invoice_number: sale.invoice_number,
-            payment_method: sale.payment_method,
+            client_id: sale.client_id,
+            shift_id: sale.shift_id.map(|id| id.to_hex()),
             sale_date: sale.sale_date.map(|t| t.to_chrono().to_rfc3339()),
             notes: sale.notes,
+            void: sale.void.map(SaleVoidResponse::from),
 
             created_at: sale.created_at.map(|t| t.to_chrono().to_rfc3339()),
             updated_at: sale.updated_at.map(|t| t.to_chrono().to_rfc3339()),
@@ -0,0 +1,122 @@
+use super::sale::{DiscountDTO, SaleItemDTO, TenderDTO};
+use crate::utils::opt_object_id_as_string;
+use mongodb::bson::{DateTime, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+/// Keranjang yang ditahan kasir, belum memotong stok dan belum dibayar
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SaleDraft {
+    #[serde(
+        rename = "_id",
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "opt_object_id_as_string"
+    )]
+    pub id: Option<ObjectId>,
+
+    pub user_id: ObjectId,
+    pub label: Option<String>, // penanda keranjang, contoh: nama pelanggan / nomor meja
+    pub customer_id: Option<ObjectId>,
+    pub items: Vec<SaleItemDTO>,
+    pub discount: Option<DiscountDTO>,
+    pub notes: Option<String>,
+
+    #[serde(default)]
+    pub created_at: Option<DateTime>,
+    #[serde(default)]
+    pub updated_at: Option<DateTime>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct SaleDraftDTO {
+    #[validate(length(max = 100, message = "Label maksimal 100 karakter"))]
+    pub label: Option<String>,
+
+    pub customer_id: Option<ObjectId>,
+
+    #[validate(length(min = 1, message = "Daftar item tidak boleh kosong"))]
+    #[validate(nested)]
+    pub items: Vec<SaleItemDTO>,
+
+    #[validate(nested)]
+    pub discount: Option<DiscountDTO>,
+
+    #[validate(length(max = 255, message = "Catatan maksimal 255 karakter"))]
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateSaleDraftDTO {
+    #[validate(length(max = 100, message = "Label maksimal 100 karakter"))]
+    pub label: Option<String>,
+
+    pub customer_id: Option<ObjectId>,
+
+    // Jika diisi, menggantikan seluruh item di keranjang
+    #[validate(length(min = 1, message = "Daftar item tidak boleh kosong"))]
+    #[validate(nested)]
+    pub items: Option<Vec<SaleItemDTO>>,
+
+    #[validate(nested)]
+    pub discount: Option<DiscountDTO>,
+
+    #[validate(length(max = 255, message = "Catatan maksimal 255 karakter"))]
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct FinalizeSaleDraftDTO {
+    #[serde(default)]
+    #[validate(nested)]
+    pub tenders: Vec<TenderDTO>,
+
+    #[validate(range(min = 1, message = "Poin yang ditukar minimal 1"))]
+    pub redeem_points: Option<i64>,
+
+    #[validate(length(min = 1, max = 32, message = "Kode voucher 1-32 karakter"))]
+    pub voucher_code: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SaleDraftItemResponse {
+    pub product_id: String,
+    pub quantity: i32,
+    pub discount: Option<DiscountDTO>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SaleDraftResponse {
+    pub id: String,
+    pub user_id: String,
+    pub label: Option<String>,
+    pub customer_id: Option<String>,
+    pub items: Vec<SaleDraftItemResponse>,
+    pub discount: Option<DiscountDTO>,
+    pub notes: Option<String>,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+}
+
+impl From<SaleDraft> for SaleDraftResponse {
+    fn from(draft: SaleDraft) -> Self {
+        SaleDraftResponse {
+            id: draft.id.expect("SaleDraft.id harus ada").to_hex(),
+            user_id: draft.user_id.to_hex(),
+            label: draft.label,
+            customer_id: draft.customer_id.map(|id| id.to_hex()),
+            items: draft
+                .items
+                .into_iter()
+                .map(|item| SaleDraftItemResponse {
+                    product_id: item.product_id.to_hex(),
+                    quantity: item.quantity,
+                    discount: item.discount,
+                })
+                .collect(),
+            discount: draft.discount,
+            notes: draft.notes,
+            created_at: draft.created_at.map(|t| t.to_chrono().to_rfc3339()),
+            updated_at: draft.updated_at.map(|t| t.to_chrono().to_rfc3339()),
+        }
+    }
+}
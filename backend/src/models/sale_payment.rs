@@ -0,0 +1,66 @@
+use super::money::{Money, validate_positive_money};
+use super::payment_method::PaymentMethod;
+use crate::utils::opt_object_id_as_string;
+use mongodb::bson::{DateTime, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+/// Pembayaran cicilan yang dicatat setelah sale dibuat
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SalePayment {
+    #[serde(
+        rename = "_id",
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "opt_object_id_as_string"
+    )]
+    pub id: Option<ObjectId>,
+
+    pub user_id: ObjectId,
+    pub sale_id: ObjectId,
+
+    pub payment_method: PaymentMethod,
+    pub amount: Money,
+    pub paid_at: DateTime,
+    pub notes: Option<String>,
+
+    #[serde(default)]
+    pub created_at: Option<DateTime>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct SalePaymentDTO {
+    pub payment_method_id: ObjectId,
+
+    #[validate(custom(function = "validate_positive_money", message = "Jumlah bayar harus lebih dari 0"))]
+    pub amount: Money,
+
+    #[validate(length(max = 255, message = "Catatan maksimal 255 karakter"))]
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SalePaymentResponse {
+    pub id: String,
+    pub user_id: String,
+    pub sale_id: String,
+    pub payment_method: PaymentMethod,
+    pub amount: Money,
+    pub paid_at: String,
+    pub notes: Option<String>,
+    pub created_at: Option<String>,
+}
+
+impl From<SalePayment> for SalePaymentResponse {
+    fn from(payment: SalePayment) -> Self {
+        SalePaymentResponse {
+            id: payment.id.expect("SalePayment.id harus ada").to_hex(),
+            user_id: payment.user_id.to_hex(),
+            sale_id: payment.sale_id.to_hex(),
+            payment_method: payment.payment_method,
+            amount: payment.amount,
+            paid_at: payment.paid_at.to_chrono().to_rfc3339(),
+            notes: payment.notes,
+            created_at: payment.created_at.map(|t| t.to_chrono().to_rfc3339()),
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use super::money::Money;
+use crate::utils::opt_object_id_as_string;
+use mongodb::bson::{DateTime, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SaleReturnItem {
+    pub product_id: ObjectId,
+    pub product_name: String,
+    pub sku: String,
+    pub quantity: i32,
+    pub price: Money, // harga per unit yang dibayar saat dijual (setelah diskon & pajak)
+    pub subtotal: Money,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SaleReturn {
+    #[serde(
+        rename = "_id",
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "opt_object_id_as_string"
+    )]
+    pub id: Option<ObjectId>,
+
+    pub user_id: ObjectId,
+    pub sale_id: ObjectId,
+    pub items: Vec<SaleReturnItem>,
+
+    // Nilai barang yang diretur memotong sisa tagihan lebih dulu,
+    // hanya kelebihannya yang dikembalikan sebagai uang
+    #[serde(default)]
+    pub return_amount: Money,
+    #[serde(default)]
+    pub credited_amount: Money, // pengurang remaining_amount sale
+    pub refund_amount: Money,   // uang yang dikembalikan ke pelanggan
+    #[serde(default)]
+    pub cash_refund_amount: Option<Money>, // bagian refund yang diambil dari laci, kosong di retur lama
+    #[serde(default)]
+    pub shift_id: Option<ObjectId>, // shift kasir yang terbuka saat retur dibuat
+    pub reason: Option<String>,
+
+    #[serde(default)]
+    pub created_at: Option<DateTime>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct SaleReturnItemDTO {
+    pub product_id: ObjectId,
+
+    #[validate(range(min = 1, message = "Jumlah retur minimal 1"))]
+    pub quantity: i32,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct SaleReturnDTO {
+    #[validate(length(min = 1, message = "Daftar item retur tidak boleh kosong"))]
+    #[validate(nested)]
+    pub items: Vec<SaleReturnItemDTO>,
+
+    #[validate(length(max = 255, message = "Alasan retur maksimal 255 karakter"))]
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SaleReturnItemResponse {
+    pub product_id: String,
+    pub product_name: String,
+    pub sku: String,
+    pub quantity: i32,
+    pub price: Money,
+    pub subtotal: Money,
+}
+
+impl From<SaleReturnItem> for SaleReturnItemResponse {
+    fn from(item: SaleReturnItem) -> Self {
+        SaleReturnItemResponse {
+            product_id: item.product_id.to_hex(),
+            product_name: item.product_name,
+            sku: item.sku,
+            quantity: item.quantity,
+            price: item.price,
+            subtotal: item.subtotal,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct SaleReturnResponse {
+    pub id: String,
+    pub user_id: String,
+    pub sale_id: String,
+    pub items: Vec<SaleReturnItemResponse>,
+    pub return_amount: Money,
+    pub credited_amount: Money,
+    pub refund_amount: Money,
+    pub cash_refund_amount: Option<Money>,
+    pub shift_id: Option<String>,
+    pub reason: Option<String>,
+    pub created_at: Option<String>,
+}
+
+impl From<SaleReturn> for SaleReturnResponse {
+    fn from(sale_return: SaleReturn) -> Self {
+        SaleReturnResponse {
+            id: sale_return.id.expect("SaleReturn.id harus ada").to_hex(),
+            user_id: sale_return.user_id.to_hex(),
+            sale_id: sale_return.sale_id.to_hex(),
+            items: sale_return
+                .items
+                .into_iter()
+                .map(SaleReturnItemResponse::from)
+                .collect(),
+            return_amount: sale_return.return_amount,
+            credited_amount: sale_return.credited_amount,
+            refund_amount: sale_return.refund_amount,
+            cash_refund_amount: sale_return.cash_refund_amount,
+            shift_id: sale_return.shift_id.map(|id| id.to_hex()),
+            reason: sale_return.reason,
+            created_at: sale_return.created_at.map(|t| t.to_chrono().to_rfc3339()),
+        }
+    }
+}
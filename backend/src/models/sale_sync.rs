@@ -0,0 +1,42 @@
+use super::sale::SaleDTO;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+/// Batch sale yang dicatat perangkat POS selama offline
+#[derive(Debug, Deserialize, Validate)]
+pub struct SaleSyncDTO {
+    // Isi tiap sale divalidasi satu per satu supaya satu sale rusak tidak menggagalkan batch
+    #[validate(length(min = 1, max = 100, message = "Batch sinkronisasi berisi 1-100 sale"))]
+    pub sales: Vec<OfflineSaleDTO>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct OfflineSaleDTO {
+    #[validate(length(min = 1, max = 64, message = "client_id 1-64 karakter"))]
+    pub client_id: String, // UUID dari perangkat, kunci deteksi duplikat
+
+    pub sale_date: DateTime<Utc>, // waktu transaksi di perangkat (RFC 3339)
+
+    #[serde(flatten)]
+    #[validate(nested)]
+    pub sale: SaleDTO,
+}
+
+/// Hasil per sale supaya perangkat bisa mencocokkan data lokalnya
+#[derive(Debug, Serialize)]
+pub struct SaleSyncResult {
+    pub client_id: String,
+    pub status: String, // "accepted", "duplicate", "rejected"
+    pub sale_id: Option<String>,
+    pub invoice_number: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SaleSyncResponse {
+    pub accepted: usize,
+    pub duplicate: usize,
+    pub rejected: usize,
+    pub results: Vec<SaleSyncResult>,
+}
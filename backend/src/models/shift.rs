@@ -0,0 +1,144 @@
+use super::money::{Money, validate_non_negative_money};
+use super::sale::PaymentMethodTotal;
+use crate::utils::opt_object_id_as_string;
+use mongodb::bson::{DateTime, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+/// Sesi laci kasir: dibuka dengan modal awal, ditutup dengan uang tunai yang dihitung.
+/// Satu toko hanya boleh punya satu shift yang terbuka
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Shift {
+    #[serde(
+        rename = "_id",
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "opt_object_id_as_string"
+    )]
+    pub id: Option<ObjectId>,
+
+    pub user_id: ObjectId,
+    pub cashier_name: String,
+    pub status: String, // "open", "closed"
+
+    pub opening_float: Money, // modal awal di laci
+    pub opened_at: DateTime,
+    pub opening_notes: Option<String>,
+
+    #[serde(default)]
+    pub closed_at: Option<DateTime>,
+    #[serde(default)]
+    pub counted_cash: Option<Money>,
+    #[serde(default)]
+    pub summary: Option<ShiftSummary>, // snapshot saat shift ditutup
+    #[serde(default)]
+    pub closing_notes: Option<String>,
+}
+
+/// Rekap transaksi dan uang tunai di laci selama satu shift
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ShiftSummary {
+    pub transaction_count: i64, // tidak termasuk sale yang di-void
+    pub total_amount: Money,
+
+    pub cash_received: Money, // uang tunai yang diterima
+    pub cash_change: Money,   // kembalian yang diberikan dari laci
+    #[serde(default)]
+    pub cash_refunded: Money, // refund retur yang diambil dari laci
+    pub expected_cash: Money, // opening_float + cash_received - cash_change - cash_refunded
+
+    #[serde(default)]
+    pub counted_cash: Option<Money>,
+    #[serde(default)]
+    pub difference: Option<Money>, // counted_cash - expected_cash, minus berarti kurang
+
+    pub payment_methods: Vec<PaymentMethodTotal>,
+}
+
+/// Rekap shift yang sudah ditutup per kasir
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CashierShiftReport {
+    #[serde(alias = "_id")] // hasil $group aggregate
+    pub cashier_name: String,
+    pub shift_count: i64,
+    pub transaction_count: i64,
+    pub total_amount: Money,
+    pub expected_cash: Money,
+    pub counted_cash: Money,
+    pub difference: Money,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct OpenShiftDTO {
+    #[validate(length(min = 1, max = 100, message = "Nama kasir 1-100 karakter"))]
+    pub cashier_name: String,
+
+    #[validate(custom(
+        function = "validate_non_negative_money",
+        message = "Modal awal tidak boleh minus"
+    ))]
+    pub opening_float: Money,
+
+    #[validate(length(max = 255, message = "Catatan maksimal 255 karakter"))]
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CloseShiftDTO {
+    #[validate(custom(
+        function = "validate_non_negative_money",
+        message = "Uang tunai yang dihitung tidak boleh minus"
+    ))]
+    pub counted_cash: Money,
+
+    #[validate(length(max = 255, message = "Catatan maksimal 255 karakter"))]
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ShiftListQuery {
+    pub status: Option<String>,
+    pub cashier_name: Option<String>,
+    pub date_from: Option<String>, // YYYY-MM-DD, berdasarkan opened_at
+    pub date_to: Option<String>,
+    pub page: Option<u64>,
+    pub limit: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CashierReportQuery {
+    pub date_from: Option<String>, // YYYY-MM-DD, berdasarkan opened_at
+    pub date_to: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ShiftResponse {
+    pub id: String,
+    pub user_id: String,
+    pub cashier_name: String,
+    pub status: String,
+    pub opening_float: Money,
+    pub opened_at: String,
+    pub opening_notes: Option<String>,
+    pub closed_at: Option<String>,
+    pub counted_cash: Option<Money>,
+    pub summary: Option<ShiftSummary>,
+    pub closing_notes: Option<String>,
+}
+
+impl From<Shift> for ShiftResponse {
+    fn from(shift: Shift) -> Self {
+        ShiftResponse {
+            id: shift.id.expect("Shift.id harus ada").to_hex(),
+            user_id: shift.user_id.to_hex(),
+            cashier_name: shift.cashier_name,
+            status: shift.status,
+            opening_float: shift.opening_float,
+            opened_at: shift.opened_at.to_chrono().to_rfc3339(),
+            opening_notes: shift.opening_notes,
+            closed_at: shift.closed_at.map(|t| t.to_chrono().to_rfc3339()),
+            counted_cash: shift.counted_cash,
+            summary: shift.summary,
+            closing_notes: shift.closing_notes,
+        }
+    }
+}
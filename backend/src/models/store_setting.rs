@@ -0,0 +1,242 @@
+use super::money::{Money, validate_positive_money};
+use crate::utils::opt_object_id_as_string;
+use chrono::{Duration, FixedOffset, NaiveDate, Utc};
+use mongodb::bson::{DateTime, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+pub const DEFAULT_INVOICE_FORMAT: &str = "INV/{YYYY}/{MM}/{seq:05}";
+
+/// Pengaturan toko, satu dokumen per user
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StoreSetting {
+    #[serde(
+        rename = "_id",
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "opt_object_id_as_string"
+    )]
+    pub id: Option<ObjectId>,
+    pub user_id: ObjectId,
+
+    // Header toko di invoice/struk
+    #[serde(default)]
+    pub store_name: Option<String>,
+    #[serde(default)]
+    pub store_address: Option<String>,
+    #[serde(default)]
+    pub store_phone: Option<String>,
+
+    // Token: {YYYY}, {YY}, {MM}, {DD}, {seq} atau {seq:05} (nomor urut dengan padding)
+    pub invoice_format: String,
+    pub invoice_reset: String, // "never", "yearly", "monthly"
+
+    // Pajak (PPN): tarif dalam persen, harga jual sudah termasuk pajak atau belum
+    #[serde(default)]
+    pub tax_enabled: bool,
+    #[serde(default = "default_tax_rate")]
+    pub tax_rate: f64,
+    #[serde(default)]
+    pub tax_inclusive: bool,
+
+    // Struk thermal: lebar kertas 58 atau 80 (mm)
+    #[serde(default = "default_receipt_paper_width")]
+    pub receipt_paper_width: u32,
+    #[serde(default)]
+    pub receipt_footer: Option<String>,
+
+    // Zona waktu toko dalam menit dari UTC (WIB = 420), dipakai untuk batas hari laporan
+    #[serde(default = "default_utc_offset_minutes")]
+    pub utc_offset_minutes: i32,
+
+    // Program poin: 1 poin per `loyalty_earn_amount` belanja, 1 poin bernilai
+    // `loyalty_point_value` saat ditukar, kedaluwarsa setelah N hari (0 = tidak pernah)
+    #[serde(default)]
+    pub loyalty_enabled: bool,
+    #[serde(default = "default_loyalty_earn_amount")]
+    pub loyalty_earn_amount: Money,
+    #[serde(default = "default_loyalty_point_value")]
+    pub loyalty_point_value: Money,
+    #[serde(default = "default_loyalty_expiry_days")]
+    pub loyalty_expiry_days: u32,
+
+    #[serde(default)]
+    pub created_at: Option<DateTime>,
+    #[serde(default)]
+    pub updated_at: Option<DateTime>,
+}
+
+fn default_tax_rate() -> f64 {
+    11.0
+}
+
+fn default_receipt_paper_width() -> u32 {
+    58
+}
+
+fn default_utc_offset_minutes() -> i32 {
+    420
+}
+
+fn default_loyalty_earn_amount() -> Money {
+    Money::from_rupiah(10_000)
+}
+
+fn default_loyalty_point_value() -> Money {
+    Money::from_rupiah(100)
+}
+
+fn default_loyalty_expiry_days() -> u32 {
+    365
+}
+
+impl StoreSetting {
+    /// Pengaturan bawaan untuk user yang belum pernah menyimpan pengaturan
+    pub fn default_for(user_id: ObjectId) -> Self {
+        StoreSetting {
+            id: None,
+            user_id,
+            store_name: None,
+            store_address: None,
+            store_phone: None,
+            invoice_format: DEFAULT_INVOICE_FORMAT.to_string(),
+            invoice_reset: "monthly".to_string(),
+            tax_enabled: false,
+            tax_rate: default_tax_rate(),
+            tax_inclusive: false,
+            receipt_paper_width: default_receipt_paper_width(),
+            receipt_footer: None,
+            utc_offset_minutes: default_utc_offset_minutes(),
+            loyalty_enabled: false,
+            loyalty_earn_amount: default_loyalty_earn_amount(),
+            loyalty_point_value: default_loyalty_point_value(),
+            loyalty_expiry_days: default_loyalty_expiry_days(),
+            created_at: None,
+            updated_at: None,
+        }
+    }
+
+    fn utc_offset(&self) -> FixedOffset {
+        FixedOffset::east_opt(self.utc_offset_minutes * 60)
+            .unwrap_or_else(|| FixedOffset::east_opt(0).expect("offset 0 selalu valid"))
+    }
+
+    /// Zona waktu toko untuk operator tanggal MongoDB, contoh: "+07:00"
+    pub fn timezone(&self) -> String {
+        self.utc_offset().to_string()
+    }
+
+    /// Waktu UTC dalam zona waktu toko, untuk ditampilkan di invoice/struk
+    pub fn local_time(&self, time: chrono::DateTime<Utc>) -> chrono::DateTime<FixedOffset> {
+        time.with_timezone(&self.utc_offset())
+    }
+
+    /// Tanggal bisnis (zona waktu toko) dari sebuah waktu UTC
+    pub fn business_date(&self, time: chrono::DateTime<Utc>) -> NaiveDate {
+        self.local_time(time).date_naive()
+    }
+
+    /// Rentang waktu UTC [awal, akhir) untuk satu tanggal bisnis toko
+    pub fn business_day_bounds(
+        &self,
+        date: NaiveDate,
+    ) -> (chrono::DateTime<Utc>, chrono::DateTime<Utc>) {
+        let start = date
+            .and_hms_opt(0, 0, 0)
+            .expect("awal hari selalu valid")
+            .and_local_timezone(self.utc_offset())
+            .single()
+            .expect("offset tetap tidak ambigu")
+            .with_timezone(&Utc);
+        (start, start + Duration::days(1))
+    }
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateStoreSettingDTO {
+    #[validate(length(min = 1, max = 100, message = "Nama toko 1-100 karakter"))]
+    pub store_name: Option<String>,
+
+    #[validate(length(max = 255, message = "Alamat toko maksimal 255 karakter"))]
+    pub store_address: Option<String>,
+
+    #[validate(length(max = 20, message = "Nomor telepon toko maksimal 20 karakter"))]
+    pub store_phone: Option<String>,
+
+    #[validate(length(min = 1, max = 64, message = "Format invoice 1-64 karakter"))]
+    pub invoice_format: Option<String>,
+
+    pub invoice_reset: Option<String>,
+
+    pub tax_enabled: Option<bool>,
+
+    #[validate(range(min = 0.0, max = 100.0, message = "Tarif pajak harus 0-100%"))]
+    pub tax_rate: Option<f64>,
+
+    pub tax_inclusive: Option<bool>,
+
+    pub receipt_paper_width: Option<u32>,
+
+    #[validate(length(max = 255, message = "Footer struk maksimal 255 karakter"))]
+    pub receipt_footer: Option<String>,
+
+    #[validate(range(min = -720, max = 840, message = "Offset zona waktu harus antara -720 dan 840 menit"))]
+    pub utc_offset_minutes: Option<i32>,
+
+    pub loyalty_enabled: Option<bool>,
+
+    #[validate(custom(function = "validate_positive_money", message = "Kelipatan belanja per poin harus lebih dari 0"))]
+    pub loyalty_earn_amount: Option<Money>,
+
+    #[validate(custom(function = "validate_positive_money", message = "Nilai poin harus lebih dari 0"))]
+    pub loyalty_point_value: Option<Money>,
+
+    #[validate(range(max = 3650, message = "Masa berlaku poin maksimal 3650 hari"))]
+    pub loyalty_expiry_days: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StoreSettingResponse {
+    pub user_id: String,
+    pub store_name: Option<String>,
+    pub store_address: Option<String>,
+    pub store_phone: Option<String>,
+    pub invoice_format: String,
+    pub invoice_reset: String,
+    pub tax_enabled: bool,
+    pub tax_rate: f64,
+    pub tax_inclusive: bool,
+    pub receipt_paper_width: u32,
+    pub receipt_footer: Option<String>,
+    pub utc_offset_minutes: i32,
+    pub loyalty_enabled: bool,
+    pub loyalty_earn_amount: Money,
+    pub loyalty_point_value: Money,
+    pub loyalty_expiry_days: u32,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+}
+
+impl From<StoreSetting> for StoreSettingResponse {
+    fn from(setting: StoreSetting) -> Self {
+        StoreSettingResponse {
+            user_id: setting.user_id.to_hex(),
+            store_name: setting.store_name,
+            store_address: setting.store_address,
+            store_phone: setting.store_phone,
+            invoice_format: setting.invoice_format,
+            invoice_reset: setting.invoice_reset,
+            tax_enabled: setting.tax_enabled,
+            tax_rate: setting.tax_rate,
+            tax_inclusive: setting.tax_inclusive,
+            receipt_paper_width: setting.receipt_paper_width,
+            receipt_footer: setting.receipt_footer,
+            utc_offset_minutes: setting.utc_offset_minutes,
+            loyalty_enabled: setting.loyalty_enabled,
+            loyalty_earn_amount: setting.loyalty_earn_amount,
+            loyalty_point_value: setting.loyalty_point_value,
+            loyalty_expiry_days: setting.loyalty_expiry_days,
+            created_at: setting.created_at.map(|t| t.to_chrono().to_rfc3339()),
+            updated_at: setting.updated_at.map(|t| t.to_chrono().to_rfc3339()),
+        }
+    }
+}
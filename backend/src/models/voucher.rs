@@ -0,0 +1,183 @@
+use super::money::{Money, validate_non_negative_money};
+use super::sale::DiscountType;
+use crate::utils::opt_object_id_as_string;
+use chrono::{DateTime as ChronoDateTime, Utc};
+use mongodb::bson::{DateTime, oid::ObjectId};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+/// Kode voucher yang bisa ditukar di kasir, `code` unik per toko (huruf besar)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Voucher {
+    #[serde(
+        rename = "_id",
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "opt_object_id_as_string"
+    )]
+    pub id: Option<ObjectId>,
+
+    pub user_id: ObjectId,
+    pub code: String,
+    pub discount_type: DiscountType,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub percent: Option<f64>, // hanya untuk voucher persen
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub amount: Option<Money>, // hanya untuk voucher fixed
+
+    #[serde(default)]
+    pub min_spend: Money, // minimal belanja setelah diskon transaksi
+    #[serde(default)]
+    pub per_customer_limit: Option<i32>, // butuh customer_id di sale jika diisi
+    #[serde(default)]
+    pub usage_limit: Option<i32>, // batas pemakaian total
+    #[serde(default)]
+    pub used_count: i32, // diubah hanya lewat redeem/void sale
+
+    pub expires_at: DateTime,
+    pub active: bool,
+
+    #[serde(default)]
+    pub created_at: Option<DateTime>,
+    #[serde(default)]
+    pub updated_at: Option<DateTime>,
+}
+
+/// Catatan pemakaian voucher per sale, status "redeemed" atau "released" (sale di-void)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VoucherRedemption {
+    #[serde(
+        rename = "_id",
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "opt_object_id_as_string"
+    )]
+    pub id: Option<ObjectId>,
+
+    pub user_id: ObjectId,
+    pub voucher_id: ObjectId,
+    pub sale_id: ObjectId,
+    pub customer_id: Option<ObjectId>,
+    pub amount: Money,
+    pub status: String,
+
+    pub created_at: DateTime,
+    #[serde(default)]
+    pub released_at: Option<DateTime>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct VoucherDTO {
+    #[validate(length(min = 3, max = 32, message = "Kode voucher 3-32 karakter"))]
+    pub code: String,
+
+    pub discount_type: DiscountType, // "percent" atau "fixed"
+
+    // Diisi sesuai discount_type: `percent` untuk voucher persen, `amount` (sen) untuk fixed
+    #[validate(range(
+        min = 0.0,
+        max = 100.0,
+        message = "Voucher persen harus antara 0 dan 100"
+    ))]
+    pub percent: Option<f64>,
+    #[validate(custom(
+        function = "validate_non_negative_money",
+        message = "Nominal voucher tidak boleh negatif"
+    ))]
+    pub amount: Option<Money>,
+
+    #[validate(custom(
+        function = "validate_non_negative_money",
+        message = "Minimal belanja tidak boleh negatif"
+    ))]
+    pub min_spend: Option<Money>,
+
+    #[validate(range(min = 1, message = "Batas pemakaian per pelanggan minimal 1"))]
+    pub per_customer_limit: Option<i32>,
+
+    #[validate(range(min = 1, message = "Batas pemakaian voucher minimal 1"))]
+    pub usage_limit: Option<i32>,
+
+    pub expires_at: ChronoDateTime<Utc>, // RFC 3339
+    pub active: Option<bool>,            // default true
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateVoucherDTO {
+    #[validate(length(min = 3, max = 32, message = "Kode voucher 3-32 karakter"))]
+    pub code: Option<String>,
+
+    pub discount_type: Option<DiscountType>,
+
+    #[validate(range(
+        min = 0.0,
+        max = 100.0,
+        message = "Voucher persen harus antara 0 dan 100"
+    ))]
+    pub percent: Option<f64>,
+    #[validate(custom(
+        function = "validate_non_negative_money",
+        message = "Nominal voucher tidak boleh negatif"
+    ))]
+    pub amount: Option<Money>,
+
+    #[validate(custom(
+        function = "validate_non_negative_money",
+        message = "Minimal belanja tidak boleh negatif"
+    ))]
+    pub min_spend: Option<Money>,
+
+    #[validate(range(min = 1, message = "Batas pemakaian per pelanggan minimal 1"))]
+    pub per_customer_limit: Option<i32>,
+
+    #[validate(range(min = 1, message = "Batas pemakaian voucher minimal 1"))]
+    pub usage_limit: Option<i32>,
+
+    pub expires_at: Option<ChronoDateTime<Utc>>,
+    pub active: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VoucherListQuery {
+    pub search: Option<String>, // pencarian sebagian kode voucher
+    pub active: Option<bool>,
+    pub page: Option<u64>,
+    pub limit: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VoucherResponse {
+    pub id: String,
+    pub user_id: String,
+    pub code: String,
+    pub discount_type: DiscountType,
+    pub percent: Option<f64>,
+    pub amount: Option<Money>,
+    pub min_spend: Money,
+    pub per_customer_limit: Option<i32>,
+    pub usage_limit: Option<i32>,
+    pub used_count: i32,
+    pub expires_at: String,
+    pub active: bool,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+}
+
+impl From<Voucher> for VoucherResponse {
+    fn from(voucher: Voucher) -> Self {
+        VoucherResponse {
+            id: voucher.id.expect("Voucher.id harus ada").to_hex(),
+            user_id: voucher.user_id.to_hex(),
+            code: voucher.code,
+            discount_type: voucher.discount_type,
+            percent: voucher.percent,
+            amount: voucher.amount,
+            min_spend: voucher.min_spend,
+            per_customer_limit: voucher.per_customer_limit,
+            usage_limit: voucher.usage_limit,
+            used_count: voucher.used_count,
+            expires_at: voucher.expires_at.to_chrono().to_rfc3339(),
+            active: voucher.active,
+            created_at: voucher.created_at.map(|t| t.to_chrono().to_rfc3339()),
+            updated_at: voucher.updated_at.map(|t| t.to_chrono().to_rfc3339()),
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use actix_web::{
+    Error as ActixError, HttpRequest, HttpResponse, Result,
+    web::{Data, Json, Path, Query},
+};
+
+use crate::errors::ApiError;
+use crate::models::customer::{
+    CustomerDTO, CustomerListQuery, CustomerResponse, UpdateCustomerDTO,
+};
+use crate::models::loyalty::{CustomerPointsResponse, PointHistoryQuery, PointTransactionResponse};
+use crate::models::sale::{SaleListQuery, SaleResponse};
+use crate::services::customer_service::{
+    create_customer_service, delete_customer_service, get_customer_sales_service,
+    get_customer_service, get_customers_service, update_customer_service,
+};
+use crate::services::loyalty_service::get_customer_points_service;
+use crate::services::store_setting_service::find_store_setting;
+use crate::utils::extract_user_id_from_cookie;
+use mongodb::Database;
+use validator::Validate;
+
+pub async fn get_customers_handler(
+    req: HttpRequest,
+    db: Data<Database>,
+    query: Query<CustomerListQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let user_id_str = extract_user_id_from_cookie(&req)?;
+    let (customers, pagination) =
+        get_customers_service(&db, &user_id_str, query.into_inner()).await?;
+
+    let customers_response: Vec<CustomerResponse> =
+        customers.into_iter().map(CustomerResponse::from).collect();
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "data": customers_response,
+        "pagination": pagination,
+        "code": 200
+    })))
+}
+
+pub async fn get_customer_handler(
+    req: HttpRequest,
+    path: Path<String>,
+    db: Data<Database>,
+) -> Result<HttpResponse, ApiError> {
+    let customer_id = path.into_inner();
+    let user_id_str = extract_user_id_from_cookie(&req)?;
+    let customer = get_customer_service(&customer_id, &db, &user_id_str).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "data": CustomerResponse::from(customer),
+        "code": 200
+    })))
+}
+
+pub async fn post_customer_handler(
+    req: HttpRequest,
+    payload: Result<Json<CustomerDTO>, ActixError>,
+    db: Data<Database>,
+) -> Result<HttpResponse, ApiError> {
+    let user_id_str = extract_user_id_from_cookie(&req)?;
+    let data = payload?.into_inner();
+    data.validate()?;
+
+    let customer = create_customer_service(data, &db, &user_id_str).await?;
+
+    Ok(HttpResponse::Created().json(serde_json::json!({
+        "status": "success",
+        "data": CustomerResponse::from(customer),
+        "code": 201
+    })))
+}
+
+pub async fn patch_customer_handler(
+    req: HttpRequest,
+    payload: Result<Json<UpdateCustomerDTO>, ActixError>,
+    db: Data<Database>,
+    path: Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let customer_id = path.into_inner();
+    let user_id_str = extract_user_id_from_cookie(&req)?;
+    let data = payload?.into_inner();
+    data.validate()?;
+
+    let customer = update_customer_service(&customer_id, data, &db, &user_id_str).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "data": CustomerResponse::from(customer),
+        "code": 200
+    })))
+}
+
+pub async fn delete_customer_handler(
+    req: HttpRequest,
+    db: Data<Database>,
+    path: Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let customer_id = path.into_inner();
+    let user_id_str = extract_user_id_from_cookie(&req)?;
+    delete_customer_service(&customer_id, &db, &user_id_str).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "code": 204
+    })))
+}
+
+pub async fn get_customer_sales_handler(
+    req: HttpRequest,
+    db: Data<Database>,
+    path: Path<String>,
+    query: Query<SaleListQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let customer_id = path.into_inner();
+    let user_id_str = extract_user_id_from_cookie(&req)?;
+    let (sales, pagination, summary) =
+        get_customer_sales_service(&customer_id, &db, &user_id_str, query.into_inner()).await?;
+
+    let sales_response: Vec<SaleResponse> = sales.into_iter().map(SaleResponse::from).collect();
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "data": sales_response,
+        "summary": summary,
+        "pagination": pagination,
+        "code": 200
+    })))
+}
+
+pub async fn get_customer_points_handler(
+    req: HttpRequest,
+    db: Data<Database>,
+    path: Path<String>,
+    query: Query<PointHistoryQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let customer_id = path.into_inner();
+    let user_id_str = extract_user_id_from_cookie(&req)?;
+    let (customer, transactions, pagination) =
+        get_customer_points_service(&customer_id, query.into_inner(), &db, &user_id_str).await?;
+    let setting = find_store_setting(customer.user_id, &db).await?;
+
+    let points_response = CustomerPointsResponse {
+        customer_id: customer.id.expect("Customer.id harus ada").to_hex(),
+        loyalty_points: customer.loyalty_points,
+        points_value: setting.loyalty_point_value * customer.loyalty_points,
+        transactions: transactions
+            .into_iter()
+            .map(PointTransactionResponse::from)
+            .collect(),
+    };
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "data": points_response,
+        "pagination": pagination,
+        "code": 200
+    })))
+}
@@ -0,0 +1,21 @@
+use super::handler::{
+    delete_customer_handler, get_customer_handler, get_customer_points_handler,
+    get_customer_sales_handler, get_customers_handler, patch_customer_handler,
+    post_customer_handler,
+};
+use crate::middlewares::auth_middleware::AuthMiddleware;
+use actix_web::web;
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/customers")
+            .wrap(AuthMiddleware)
+            .route("", web::get().to(get_customers_handler))
+            .route("", web::post().to(post_customer_handler))
+            .route("{id}", web::get().to(get_customer_handler))
+            .route("{id}", web::patch().to(patch_customer_handler))
+            .route("{id}", web::delete().to(delete_customer_handler))
+            .route("{id}/sales", web::get().to(get_customer_sales_handler))
+            .route("{id}/points", web::get().to(get_customer_points_handler)),
+    );
+}
@@ -1,8 +1,17 @@
 use actix_web::web;
 mod auth;
+mod customers;
+mod payment_methods;
 mod products;
+mod promotions;
+mod receivables;
+mod reports;
 mod users;
+mod vouchers;
 mod sales;
+mod sale_drafts;
+mod settings;
+mod shifts;
 
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(
@@ -10,7 +19,16 @@ pub fn config(cfg: &mut web::ServiceConfig) {
             .configure(users::routes::config)
             .configure(auth::routes::config)
             .configure(products::routes::config)
-            .configure(sales::routes::config),
+            .configure(customers::routes::config)
+            .configure(payment_methods::routes::config)
+            .configure(promotions::routes::config)
+            .configure(vouchers::routes::config)
+            .configure(sales::routes::config)
+            .configure(sale_drafts::routes::config)
+            .configure(settings::routes::config)
+            .configure(reports::routes::config)
+            .configure(receivables::routes::config)
+            .configure(shifts::routes::config),
     );
 }
 
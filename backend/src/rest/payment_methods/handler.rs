@@ -0,0 +1,72 @@
+use actix_web::{
+    Error as ActixError, HttpRequest, HttpResponse, Result,
+    web::{Data, Json, Path},
+};
+
+use crate::errors::ApiError;
+use crate::models::payment_method::{
+    PaymentMethodDTO, PaymentMethodResponse, UpdatePaymentMethodDTO,
+};
+use crate::services::payment_method_service::{
+    create_payment_method_service, get_payment_methods_service, update_payment_method_service,
+};
+use crate::utils::extract_user_id_from_cookie;
+use mongodb::Database;
+use validator::Validate;
+
+pub async fn get_payment_methods_handler(
+    req: HttpRequest,
+    db: Data<Database>,
+) -> Result<HttpResponse, ApiError> {
+    extract_user_id_from_cookie(&req)?;
+    let methods = get_payment_methods_service(&db).await?;
+
+    let methods_response: Vec<PaymentMethodResponse> = methods
+        .into_iter()
+        .map(PaymentMethodResponse::from)
+        .collect();
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "data": methods_response,
+        "code": 200
+    })))
+}
+
+pub async fn post_payment_method_handler(
+    req: HttpRequest,
+    payload: Result<Json<PaymentMethodDTO>, ActixError>,
+    db: Data<Database>,
+) -> Result<HttpResponse, ApiError> {
+    extract_user_id_from_cookie(&req)?;
+    let data = payload?.into_inner();
+    data.validate()?;
+
+    let method = create_payment_method_service(data, &db).await?;
+
+    Ok(HttpResponse::Created().json(serde_json::json!({
+        "status": "success",
+        "data": PaymentMethodResponse::from(method),
+        "code": 201
+    })))
+}
+
+pub async fn patch_payment_method_handler(
+    req: HttpRequest,
+    payload: Result<Json<UpdatePaymentMethodDTO>, ActixError>,
+    db: Data<Database>,
+    path: Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let payment_method_id = path.into_inner();
+    extract_user_id_from_cookie(&req)?;
+    let data = payload?.into_inner();
+    data.validate()?;
+
+    let method = update_payment_method_service(&payment_method_id, data, &db).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "data": PaymentMethodResponse::from(method),
+        "code": 200
+    })))
+}
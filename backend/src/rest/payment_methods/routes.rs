@@ -0,0 +1,15 @@
+use super::handler::{
+    get_payment_methods_handler, patch_payment_method_handler, post_payment_method_handler,
+};
+use crate::middlewares::auth_middleware::AuthMiddleware;
+use actix_web::web;
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/payment-methods")
+            .wrap(AuthMiddleware)
+            .route("", web::get().to(get_payment_methods_handler))
+            .route("", web::post().to(post_payment_method_handler))
+            .route("{id}", web::patch().to(patch_payment_method_handler)),
+    );
+}
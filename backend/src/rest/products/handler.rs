@@ -1,13 +1,15 @@
 use actix_web::{
     Error as ActixError, HttpRequest, HttpResponse, Result,
-    web::{Data, Json, Path},
+    web::{Data, Json, Path, Query},
 };
 
 use crate::errors::ApiError;
-use crate::models::product::{ProductDTO, ProductResponse, UpdateProductDTO};
+use crate::models::product::{
+    ProductChangesQuery, ProductDTO, ProductResponse, UpdateProductDTO,
+};
 use crate::services::product_service::{
-    create_product_service, delete_product_service, get_product_service, get_products_service,
-    update_product_service,
+    create_product_service, delete_product_service, get_product_changes_service,
+    get_product_service, get_products_service, update_product_service,
 };
 use crate::utils::extract_user_id_from_cookie;
 use mongodb::Database;
@@ -30,6 +32,21 @@ pub async fn get_products_handler(
     })))
 }
 
+pub async fn get_product_changes_handler(
+    req: HttpRequest,
+    db: Data<Database>,
+    query: Query<ProductChangesQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let user_id_str = extract_user_id_from_cookie(&req)?;
+    let changes = get_product_changes_service(&db, &user_id_str, query.into_inner()).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "data": changes,
+        "code": 200
+    })))
+}
+
 pub async fn get_product_handler(
     req: HttpRequest,
     path: Path<String>,
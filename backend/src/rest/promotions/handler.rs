@@ -0,0 +1,107 @@
+use actix_web::{
+    Error as ActixError, HttpRequest, HttpResponse, Result,
+    web::{Data, Json, Path, Query},
+};
+
+use crate::errors::ApiError;
+use crate::models::promotion::{
+    PromotionDTO, PromotionListQuery, PromotionResponse, UpdatePromotionDTO,
+};
+use crate::services::promotion_service::{
+    create_promotion_service, delete_promotion_service, get_promotion_service,
+    get_promotions_service, update_promotion_service,
+};
+use crate::utils::extract_user_id_from_cookie;
+use mongodb::Database;
+use validator::Validate;
+
+pub async fn get_promotions_handler(
+    req: HttpRequest,
+    db: Data<Database>,
+    query: Query<PromotionListQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let user_id_str = extract_user_id_from_cookie(&req)?;
+    let (promotions, pagination) =
+        get_promotions_service(&db, &user_id_str, query.into_inner()).await?;
+
+    let promotions_response: Vec<PromotionResponse> = promotions
+        .into_iter()
+        .map(PromotionResponse::from)
+        .collect();
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "data": promotions_response,
+        "pagination": pagination,
+        "code": 200
+    })))
+}
+
+pub async fn get_promotion_handler(
+    req: HttpRequest,
+    path: Path<String>,
+    db: Data<Database>,
+) -> Result<HttpResponse, ApiError> {
+    let promotion_id = path.into_inner();
+    let user_id_str = extract_user_id_from_cookie(&req)?;
+    let promotion = get_promotion_service(&promotion_id, &db, &user_id_str).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "data": PromotionResponse::from(promotion),
+        "code": 200
+    })))
+}
+
+pub async fn post_promotion_handler(
+    req: HttpRequest,
+    payload: Result<Json<PromotionDTO>, ActixError>,
+    db: Data<Database>,
+) -> Result<HttpResponse, ApiError> {
+    let user_id_str = extract_user_id_from_cookie(&req)?;
+    let data = payload?.into_inner();
+    data.validate()?;
+
+    let promotion = create_promotion_service(data, &db, &user_id_str).await?;
+
+    Ok(HttpResponse::Created().json(serde_json::json!({
+        "status": "success",
+        "data": PromotionResponse::from(promotion),
+        "code": 201
+    })))
+}
+
+pub async fn patch_promotion_handler(
+    req: HttpRequest,
+    payload: Result<Json<UpdatePromotionDTO>, ActixError>,
+    db: Data<Database>,
+    path: Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let promotion_id = path.into_inner();
+    let user_id_str = extract_user_id_from_cookie(&req)?;
+    let data = payload?.into_inner();
+    data.validate()?;
+
+    let promotion = update_promotion_service(&promotion_id, data, &db, &user_id_str).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "data": PromotionResponse::from(promotion),
+        "code": 200
+    })))
+}
+
+pub async fn delete_promotion_handler(
+    req: HttpRequest,
+    db: Data<Database>,
+    path: Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let promotion_id = path.into_inner();
+    let user_id_str = extract_user_id_from_cookie(&req)?;
+    delete_promotion_service(&promotion_id, &db, &user_id_str).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "code": 204
+    })))
+}
@@ -0,0 +1,18 @@
+use super::handler::{
+    delete_promotion_handler, get_promotion_handler, get_promotions_handler,
+    patch_promotion_handler, post_promotion_handler,
+};
+use crate::middlewares::auth_middleware::AuthMiddleware;
+use actix_web::web;
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/promotions")
+            .wrap(AuthMiddleware)
+            .route("", web::get().to(get_promotions_handler))
+            .route("", web::post().to(post_promotion_handler))
+            .route("{id}", web::get().to(get_promotion_handler))
+            .route("{id}", web::patch().to(patch_promotion_handler))
+            .route("{id}", web::delete().to(delete_promotion_handler)),
+    );
+}
@@ -0,0 +1,75 @@
+use actix_web::{
+    HttpRequest, HttpResponse, Result,
+    web::{Data, Path, Query},
+};
+
+use crate::errors::ApiError;
+use crate::models::receivable::StatementQuery;
+use crate::services::receivable_service::{
+    get_aging_report_service, get_customer_balance_service, get_customer_statement_service,
+    get_receivables_service,
+};
+use crate::utils::extract_user_id_from_cookie;
+use mongodb::Database;
+
+pub async fn get_receivables_handler(
+    req: HttpRequest,
+    db: Data<Database>,
+) -> Result<HttpResponse, ApiError> {
+    let user_id_str = extract_user_id_from_cookie(&req)?;
+    let balances = get_receivables_service(&db, &user_id_str).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "data": balances,
+        "code": 200
+    })))
+}
+
+pub async fn get_aging_report_handler(
+    req: HttpRequest,
+    db: Data<Database>,
+) -> Result<HttpResponse, ApiError> {
+    let user_id_str = extract_user_id_from_cookie(&req)?;
+    let report = get_aging_report_service(&db, &user_id_str).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "data": report,
+        "code": 200
+    })))
+}
+
+pub async fn get_customer_balance_handler(
+    req: HttpRequest,
+    db: Data<Database>,
+    path: Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let customer_id = path.into_inner();
+    let user_id_str = extract_user_id_from_cookie(&req)?;
+    let balance = get_customer_balance_service(&customer_id, &db, &user_id_str).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "data": balance,
+        "code": 200
+    })))
+}
+
+pub async fn get_customer_statement_handler(
+    req: HttpRequest,
+    db: Data<Database>,
+    path: Path<String>,
+    query: Query<StatementQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let customer_id = path.into_inner();
+    let user_id_str = extract_user_id_from_cookie(&req)?;
+    let statement =
+        get_customer_statement_service(&customer_id, query.into_inner(), &db, &user_id_str).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "data": statement,
+        "code": 200
+    })))
+}
@@ -0,0 +1,23 @@
+use super::handler::{
+    get_aging_report_handler, get_customer_balance_handler, get_customer_statement_handler,
+    get_receivables_handler,
+};
+use crate::middlewares::auth_middleware::AuthMiddleware;
+use actix_web::web;
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/receivables")
+            .wrap(AuthMiddleware)
+            .route("", web::get().to(get_receivables_handler))
+            .route("aging", web::get().to(get_aging_report_handler))
+            .route(
+                "customers/{id}",
+                web::get().to(get_customer_balance_handler),
+            )
+            .route(
+                "customers/{id}/statement",
+                web::get().to(get_customer_statement_handler),
+            ),
+    );
+}
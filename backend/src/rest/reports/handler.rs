@@ -0,0 +1,46 @@
+use actix_web::{
+    Error as ActixError, HttpRequest, HttpResponse, Result,
+    web::{Data, Json, Query},
+};
+
+use crate::errors::ApiError;
+use crate::models::daily_closing::{
+    CloseDayDTO, DailyClosingResponse, ZReportQuery, ZReportResponse,
+};
+use crate::services::daily_closing_service::{close_day_service, get_z_report_service};
+use crate::utils::extract_user_id_from_cookie;
+use mongodb::Database;
+use validator::Validate;
+
+pub async fn get_z_report_handler(
+    req: HttpRequest,
+    db: Data<Database>,
+    query: Query<ZReportQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let user_id_str = extract_user_id_from_cookie(&req)?;
+    let report = get_z_report_service(query.into_inner().date, &db, &user_id_str).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "data": ZReportResponse::from(report),
+        "code": 200
+    })))
+}
+
+pub async fn close_day_handler(
+    req: HttpRequest,
+    payload: Result<Json<CloseDayDTO>, ActixError>,
+    db: Data<Database>,
+) -> Result<HttpResponse, ApiError> {
+    let user_id_str = extract_user_id_from_cookie(&req)?;
+    let data = payload?.into_inner();
+    data.validate()?;
+
+    let closing = close_day_service(data, &db, &user_id_str).await?;
+
+    Ok(HttpResponse::Created().json(serde_json::json!({
+        "status": "success",
+        "data": DailyClosingResponse::from(closing),
+        "code": 201
+    })))
+}
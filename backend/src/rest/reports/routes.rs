@@ -0,0 +1,12 @@
+use super::handler::{close_day_handler, get_z_report_handler};
+use crate::middlewares::auth_middleware::AuthMiddleware;
+use actix_web::web;
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/reports")
+            .wrap(AuthMiddleware)
+            .route("z-report", web::get().to(get_z_report_handler))
+            .route("z-report/close", web::post().to(close_day_handler)),
+    );
+}
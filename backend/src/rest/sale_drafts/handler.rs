@@ -0,0 +1,123 @@
+use actix_web::{
+    Error as ActixError, HttpRequest, HttpResponse, Result,
+    web::{Data, Json, Path},
+};
+
+use crate::errors::ApiError;
+use crate::models::sale::SaleResponse;
+use crate::models::sale_draft::{
+    FinalizeSaleDraftDTO, SaleDraftDTO, SaleDraftResponse, UpdateSaleDraftDTO,
+};
+use crate::services::sale_draft_service::{
+    create_sale_draft_service, delete_sale_draft_service, finalize_sale_draft_service,
+    get_sale_draft_service, get_sale_drafts_service, update_sale_draft_service,
+};
+use crate::utils::extract_user_id_from_cookie;
+use mongodb::Database;
+use validator::Validate;
+
+pub async fn get_sale_drafts_handler(
+    req: HttpRequest,
+    db: Data<Database>,
+) -> Result<HttpResponse, ApiError> {
+    let user_id_str = extract_user_id_from_cookie(&req)?;
+    let drafts = get_sale_drafts_service(&db, &user_id_str).await?;
+
+    let drafts_response: Vec<SaleDraftResponse> =
+        drafts.into_iter().map(SaleDraftResponse::from).collect();
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "data": drafts_response,
+        "code": 200
+    })))
+}
+
+pub async fn get_sale_draft_handler(
+    req: HttpRequest,
+    db: Data<Database>,
+    path: Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let draft_id = path.into_inner();
+    let user_id_str = extract_user_id_from_cookie(&req)?;
+    let draft = get_sale_draft_service(&draft_id, &db, &user_id_str).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "data": SaleDraftResponse::from(draft),
+        "code": 200
+    })))
+}
+
+pub async fn post_sale_draft_handler(
+    req: HttpRequest,
+    payload: Result<Json<SaleDraftDTO>, ActixError>,
+    db: Data<Database>,
+) -> Result<HttpResponse, ApiError> {
+    let user_id_str = extract_user_id_from_cookie(&req)?;
+    let data = payload?.into_inner();
+    data.validate()?;
+
+    let draft = create_sale_draft_service(data, &db, &user_id_str).await?;
+
+    Ok(HttpResponse::Created().json(serde_json::json!({
+        "status": "success",
+        "data": SaleDraftResponse::from(draft),
+        "code": 201
+    })))
+}
+
+pub async fn patch_sale_draft_handler(
+    req: HttpRequest,
+    payload: Result<Json<UpdateSaleDraftDTO>, ActixError>,
+    db: Data<Database>,
+    path: Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let draft_id = path.into_inner();
+    let user_id_str = extract_user_id_from_cookie(&req)?;
+    let data = payload?.into_inner();
+    data.validate()?;
+
+    let draft = update_sale_draft_service(&draft_id, data, &db, &user_id_str).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "data": SaleDraftResponse::from(draft),
+        "code": 200
+    })))
+}
+
+pub async fn delete_sale_draft_handler(
+    req: HttpRequest,
+    db: Data<Database>,
+    path: Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let draft_id = path.into_inner();
+    let user_id_str = extract_user_id_from_cookie(&req)?;
+    delete_sale_draft_service(&draft_id, &db, &user_id_str).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "code": 204
+    })))
+}
+
+pub async fn finalize_sale_draft_handler(
+    req: HttpRequest,
+    payload: Result<Json<FinalizeSaleDraftDTO>, ActixError>,
+    db: Data<Database>,
+    path: Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let draft_id = path.into_inner();
+    let user_id_str = extract_user_id_from_cookie(&req)?;
+    let data = payload?.into_inner();
+    data.validate()?;
+
+    let sale = finalize_sale_draft_service(&draft_id, data, &db, &user_id_str).await?;
+
+    Ok(HttpResponse::Created().json(serde_json::json!({
+        "status": "success",
+        "data": SaleResponse::from(sale),
+        "code": 201
+    })))
+}
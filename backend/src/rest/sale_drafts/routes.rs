@@ -0,0 +1,19 @@
+use super::handler::{
+    delete_sale_draft_handler, finalize_sale_draft_handler, get_sale_draft_handler,
+    get_sale_drafts_handler, patch_sale_draft_handler, post_sale_draft_handler,
+};
+use crate::middlewares::auth_middleware::AuthMiddleware;
+use actix_web::web;
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/sale-drafts")
+            .wrap(AuthMiddleware)
+            .route("", web::get().to(get_sale_drafts_handler))
+            .route("", web::post().to(post_sale_draft_handler))
+            .route("{id}", web::get().to(get_sale_draft_handler))
+            .route("{id}", web::patch().to(patch_sale_draft_handler))
+            .route("{id}", web::delete().to(delete_sale_draft_handler))
+            .route("{id}/finalize", web::post().to(finalize_sale_draft_handler)),
+    );
+}
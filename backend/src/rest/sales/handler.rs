@@ -1,12 +1,32 @@
-use crate::models::sale::{Sale, SaleDTO, SaleItem, SaleResponse};
+use crate::models::sale::{ReceiptQuery, SaleDTO, SaleListQuery, SaleResponse, VoidSaleDTO};
+use crate::models::sale_payment::{SalePaymentDTO, SalePaymentResponse};
+use crate::models::sale_return::{SaleReturnDTO, SaleReturnResponse};
+use crate::models::sale_sync::SaleSyncDTO;
 use actix_web::{
     Error as ActixError, HttpRequest, HttpResponse, Result,
-    web::{Data, Json, Path},
+    web::{Data, Json, Path, Query},
 };
 
 use crate::errors::ApiError;
-use crate::models::product::{ProductDTO, ProductResponse, UpdateProductDTO};
-use crate::services::sale_service::{create_sale_service, get_sales_service};
+use crate::models::idempotency::IdempotentSale;
+use crate::services::idempotency_service::{
+    IDEMPOTENCY_KEY_HEADER, create_sale_idempotent_service,
+};
+use crate::services::invoice_pdf_service::get_sale_invoice_pdf_service;
+use crate::services::receipt_service::{
+    get_sale_receipt_service, render_escpos, render_text,
+};
+use crate::services::sale_payment_service::{
+    create_sale_payment_service, get_sale_payments_service,
+};
+use crate::services::sale_return_service::{
+    create_sale_return_service, get_sale_returns_service,
+};
+use crate::services::sale_service::{
+    create_sale_service, get_sale_service, get_sales_service, get_sales_summary_service,
+    void_sale_service,
+};
+use crate::services::sale_sync_service::sync_sales_service;
 use crate::utils::extract_user_id_from_cookie;
 use mongodb::Database;
 use validator::Validate;
@@ -14,15 +34,31 @@ use validator::Validate;
 pub async fn get_sales_handler(
     req: HttpRequest,
     db: Data<Database>,
+    query: Query<SaleListQuery>,
 ) -> Result<HttpResponse, ApiError> {
     let user_id_str = extract_user_id_from_cookie(&req)?;
-    let sales = get_sales_service(&db, &user_id_str).await?;
+    let (sales, pagination) = get_sales_service(&db, &user_id_str, query.into_inner()).await?;
 
     let sales_response: Vec<SaleResponse> = sales.into_iter().map(SaleResponse::from).collect();
 
     Ok(HttpResponse::Ok().json(serde_json::json!({
         "status": "success",
         "data": sales_response,
+        "pagination": pagination,
+        "code": 200
+    })))
+}
+
+pub async fn get_sales_summary_handler(
+    req: HttpRequest,
+    db: Data<Database>,
+) -> Result<HttpResponse, ApiError> {
+    let user_id_str = extract_user_id_from_cookie(&req)?;
+    let summary = get_sales_summary_service(&db, &user_id_str).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "data": summary,
         "code": 200
     })))
 }
@@ -32,11 +68,76 @@ pub async fn get_sale_handler(
     db: Data<Database>,
     path: Path<String>,
 ) -> Result<HttpResponse, ApiError> {
-    // let sale_id = path.into_inner();
-    // let user_id_str = extract_user_id_from_cookie(&req)?;
-    // let product = get_sale_service();
+    let sale_id = path.into_inner();
+    let user_id_str = extract_user_id_from_cookie(&req)?;
+    let sale = get_sale_service(&sale_id, &db, &user_id_str).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "data": SaleResponse::from(sale),
+        "code": 200
+    })))
+}
+
+pub async fn get_sale_invoice_pdf_handler(
+    req: HttpRequest,
+    db: Data<Database>,
+    path: Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let sale_id = path.into_inner();
+    let user_id_str = extract_user_id_from_cookie(&req)?;
+    let (sale, pdf) = get_sale_invoice_pdf_service(&sale_id, &db, &user_id_str).await?;
+
+    // Nomor invoice bisa mengandung '/', ganti supaya aman jadi nama file
+    let filename = sale
+        .invoice_number
+        .unwrap_or_else(|| sale_id.clone())
+        .replace(['/', '\\', '"'], "-");
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/pdf")
+        .append_header((
+            "Content-Disposition",
+            format!("inline; filename=\"{}.pdf\"", filename),
+        ))
+        .body(pdf))
+}
+
+pub async fn get_sale_receipt_handler(
+    req: HttpRequest,
+    db: Data<Database>,
+    path: Path<String>,
+    query: Query<ReceiptQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let sale_id = path.into_inner();
+    let user_id_str = extract_user_id_from_cookie(&req)?;
+    let (lines, _columns) =
+        get_sale_receipt_service(&sale_id, query.paper_width, &db, &user_id_str).await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/octet-stream")
+        .append_header((
+            "Content-Disposition",
+            format!("attachment; filename=\"receipt-{}.bin\"", sale_id),
+        ))
+        .body(render_escpos(&lines)))
 }
 
+pub async fn get_sale_receipt_preview_handler(
+    req: HttpRequest,
+    db: Data<Database>,
+    path: Path<String>,
+    query: Query<ReceiptQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let sale_id = path.into_inner();
+    let user_id_str = extract_user_id_from_cookie(&req)?;
+    let (lines, columns) =
+        get_sale_receipt_service(&sale_id, query.paper_width, &db, &user_id_str).await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/plain; charset=utf-8")
+        .body(render_text(&lines, columns)))
+}
 
 pub async fn post_sale_handler(
     req: HttpRequest,
@@ -47,7 +148,36 @@ pub async fn post_sale_handler(
     let data = payload?.into_inner();
     data.validate()?;
 
-    let sale = create_sale_service(data, &db, &user_id_str).await?;
+    // Header dengan karakter non-ASCII ditolak, bukan dianggap key kosong
+    let idempotency_key = match req.headers().get(IDEMPOTENCY_KEY_HEADER) {
+        Some(value) => match value.to_str() {
+            Ok(key) => Some(key.to_string()),
+            Err(_) => {
+                return Err(ApiError::BadRequest(format!(
+                    "Header {} tidak valid, hanya boleh berisi karakter ASCII",
+                    IDEMPOTENCY_KEY_HEADER
+                )));
+            }
+        },
+        None => None,
+    };
+
+    let sale = match idempotency_key {
+        Some(key) => match create_sale_idempotent_service(&key, data, &db, &user_id_str).await? {
+            IdempotentSale::Created(sale) => *sale,
+            // Retry: kirim ulang respons yang tersimpan dari request pertama
+            IdempotentSale::Replayed(response) => {
+                return Ok(HttpResponse::Created()
+                    .insert_header(("Idempotent-Replayed", "true"))
+                    .json(serde_json::json!({
+                        "status" : "success",
+                        "data" : response,
+                        "code" : 201
+                    })));
+            }
+        },
+        None => create_sale_service(data, &db, &user_id_str).await?,
+    };
 
     Ok(HttpResponse::Created().json({
         serde_json::json!({
@@ -57,3 +187,122 @@ pub async fn post_sale_handler(
         })
     }))
 }
+
+pub async fn void_sale_handler(
+    req: HttpRequest,
+    payload: Result<Json<VoidSaleDTO>, ActixError>,
+    db: Data<Database>,
+    path: Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let sale_id = path.into_inner();
+    let user_id_str = extract_user_id_from_cookie(&req)?;
+    let data = payload?.into_inner();
+    data.validate()?;
+
+    let sale = void_sale_service(&sale_id, data, &db, &user_id_str).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "data": SaleResponse::from(sale),
+        "code": 200
+    })))
+}
+
+pub async fn get_sale_returns_handler(
+    req: HttpRequest,
+    db: Data<Database>,
+    path: Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let sale_id = path.into_inner();
+    let user_id_str = extract_user_id_from_cookie(&req)?;
+    let returns = get_sale_returns_service(&sale_id, &db, &user_id_str).await?;
+
+    let returns_response: Vec<SaleReturnResponse> =
+        returns.into_iter().map(SaleReturnResponse::from).collect();
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "data": returns_response,
+        "code": 200
+    })))
+}
+
+pub async fn post_sale_return_handler(
+    req: HttpRequest,
+    payload: Result<Json<SaleReturnDTO>, ActixError>,
+    db: Data<Database>,
+    path: Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let sale_id = path.into_inner();
+    let user_id_str = extract_user_id_from_cookie(&req)?;
+    let data = payload?.into_inner();
+    data.validate()?;
+
+    let sale_return = create_sale_return_service(&sale_id, data, &db, &user_id_str).await?;
+
+    Ok(HttpResponse::Created().json(serde_json::json!({
+        "status": "success",
+        "data": SaleReturnResponse::from(sale_return),
+        "code": 201
+    })))
+}
+
+pub async fn get_sale_payments_handler(
+    req: HttpRequest,
+    db: Data<Database>,
+    path: Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let sale_id = path.into_inner();
+    let user_id_str = extract_user_id_from_cookie(&req)?;
+    let payments = get_sale_payments_service(&sale_id, &db, &user_id_str).await?;
+
+    let payments_response: Vec<SalePaymentResponse> =
+        payments.into_iter().map(SalePaymentResponse::from).collect();
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "data": payments_response,
+        "code": 200
+    })))
+}
+
+pub async fn post_sale_payment_handler(
+    req: HttpRequest,
+    payload: Result<Json<SalePaymentDTO>, ActixError>,
+    db: Data<Database>,
+    path: Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let sale_id = path.into_inner();
+    let user_id_str = extract_user_id_from_cookie(&req)?;
+    let data = payload?.into_inner();
+    data.validate()?;
+
+    let (sale, payment) = create_sale_payment_service(&sale_id, data, &db, &user_id_str).await?;
+
+    Ok(HttpResponse::Created().json(serde_json::json!({
+        "status": "success",
+        "data": {
+            "payment": SalePaymentResponse::from(payment),
+            "sale": SaleResponse::from(sale),
+        },
+        "code": 201
+    })))
+}
+
+pub async fn post_sales_sync_handler(
+    req: HttpRequest,
+    payload: Result<Json<SaleSyncDTO>, ActixError>,
+    db: Data<Database>,
+) -> Result<HttpResponse, ApiError> {
+    let user_id_str = extract_user_id_from_cookie(&req)?;
+    let data = payload?.into_inner();
+    data.validate()?;
+
+    let result = sync_sales_service(data, &db, &user_id_str).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "data": result,
+        "code": 200
+    })))
+}
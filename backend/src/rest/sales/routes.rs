@@ -1,4 +1,9 @@
-use super::handler::{get_sales_handler, post_sale_handler};
+use super::handler::{
+    get_sale_handler, get_sale_invoice_pdf_handler, get_sale_payments_handler,
+    get_sale_receipt_handler, get_sale_receipt_preview_handler, get_sale_returns_handler,
+    get_sales_handler, get_sales_summary_handler, post_sale_handler, post_sale_payment_handler,
+    post_sale_return_handler, post_sales_sync_handler, void_sale_handler,
+};
 use crate::middlewares::auth_middleware::AuthMiddleware;
 use actix_web::web;
 
@@ -7,8 +12,20 @@ pub fn config(cfg: &mut web::ServiceConfig) {
         web::scope("/sales")
             .wrap(AuthMiddleware)
             .route("", web::get().to(get_sales_handler))
-            .route("", web::post().to(post_sale_handler)), // .route("{id}", web::get().to(get_product_handler))
-                                                           // .route("{id}", web::patch().to(patch_product_handler))
-                                                           // .route("{id}", web::delete().to(delete_product_handler)),
+            .route("", web::post().to(post_sale_handler))
+            .route("summary", web::get().to(get_sales_summary_handler))
+            .route("sync", web::post().to(post_sales_sync_handler))
+            .route("{id}", web::get().to(get_sale_handler))
+            .route("{id}/invoice", web::get().to(get_sale_invoice_pdf_handler))
+            .route("{id}/receipt", web::get().to(get_sale_receipt_handler))
+            .route(
+                "{id}/receipt/preview",
+                web::get().to(get_sale_receipt_preview_handler),
+            )
+            .route("{id}/void", web::post().to(void_sale_handler))
+            .route("{id}/returns", web::get().to(get_sale_returns_handler))
+            .route("{id}/returns", web::post().to(post_sale_return_handler))
+            .route("{id}/payments", web::get().to(get_sale_payments_handler))
+            .route("{id}/payments", web::post().to(post_sale_payment_handler)),
     );
 }
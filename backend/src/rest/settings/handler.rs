@@ -0,0 +1,45 @@
+use actix_web::{
+    Error as ActixError, HttpRequest, HttpResponse, Result,
+    web::{Data, Json},
+};
+
+use crate::errors::ApiError;
+use crate::models::store_setting::{StoreSettingResponse, UpdateStoreSettingDTO};
+use crate::services::store_setting_service::{
+    get_store_setting_service, update_store_setting_service,
+};
+use crate::utils::extract_user_id_from_cookie;
+use mongodb::Database;
+use validator::Validate;
+
+pub async fn get_store_setting_handler(
+    req: HttpRequest,
+    db: Data<Database>,
+) -> Result<HttpResponse, ApiError> {
+    let user_id_str = extract_user_id_from_cookie(&req)?;
+    let setting = get_store_setting_service(&db, &user_id_str).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "data": StoreSettingResponse::from(setting),
+        "code": 200
+    })))
+}
+
+pub async fn patch_store_setting_handler(
+    req: HttpRequest,
+    payload: Result<Json<UpdateStoreSettingDTO>, ActixError>,
+    db: Data<Database>,
+) -> Result<HttpResponse, ApiError> {
+    let user_id_str = extract_user_id_from_cookie(&req)?;
+    let data = payload?.into_inner();
+    data.validate()?;
+
+    let setting = update_store_setting_service(data, &db, &user_id_str).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "data": StoreSettingResponse::from(setting),
+        "code": 200
+    })))
+}
@@ -0,0 +1,12 @@
+use super::handler::{get_store_setting_handler, patch_store_setting_handler};
+use crate::middlewares::auth_middleware::AuthMiddleware;
+use actix_web::web;
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/settings")
+            .wrap(AuthMiddleware)
+            .route("", web::get().to(get_store_setting_handler))
+            .route("", web::patch().to(patch_store_setting_handler)),
+    );
+}
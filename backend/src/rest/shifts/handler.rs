@@ -0,0 +1,117 @@
+use actix_web::{
+    Error as ActixError, HttpRequest, HttpResponse, Result,
+    web::{Data, Json, Path, Query},
+};
+
+use crate::errors::ApiError;
+use crate::models::shift::{
+    CashierReportQuery, CloseShiftDTO, OpenShiftDTO, ShiftListQuery, ShiftResponse,
+};
+use crate::services::shift_service::{
+    close_shift_service, get_cashier_report_service, get_current_shift_service, get_shift_service,
+    get_shifts_service, open_shift_service,
+};
+use crate::utils::extract_user_id_from_cookie;
+use mongodb::Database;
+use validator::Validate;
+
+pub async fn get_shifts_handler(
+    req: HttpRequest,
+    db: Data<Database>,
+    query: Query<ShiftListQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let user_id_str = extract_user_id_from_cookie(&req)?;
+    let (shifts, pagination) = get_shifts_service(&db, &user_id_str, query.into_inner()).await?;
+
+    let shifts_response: Vec<ShiftResponse> = shifts.into_iter().map(ShiftResponse::from).collect();
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "data": shifts_response,
+        "pagination": pagination,
+        "code": 200
+    })))
+}
+
+pub async fn get_current_shift_handler(
+    req: HttpRequest,
+    db: Data<Database>,
+) -> Result<HttpResponse, ApiError> {
+    let user_id_str = extract_user_id_from_cookie(&req)?;
+    let shift = get_current_shift_service(&db, &user_id_str).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "data": ShiftResponse::from(shift),
+        "code": 200
+    })))
+}
+
+pub async fn get_cashier_report_handler(
+    req: HttpRequest,
+    db: Data<Database>,
+    query: Query<CashierReportQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let user_id_str = extract_user_id_from_cookie(&req)?;
+    let report = get_cashier_report_service(&db, &user_id_str, query.into_inner()).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "data": report,
+        "code": 200
+    })))
+}
+
+pub async fn get_shift_handler(
+    req: HttpRequest,
+    db: Data<Database>,
+    path: Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let shift_id = path.into_inner();
+    let user_id_str = extract_user_id_from_cookie(&req)?;
+    let shift = get_shift_service(&shift_id, &db, &user_id_str).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "data": ShiftResponse::from(shift),
+        "code": 200
+    })))
+}
+
+pub async fn open_shift_handler(
+    req: HttpRequest,
+    payload: Result<Json<OpenShiftDTO>, ActixError>,
+    db: Data<Database>,
+) -> Result<HttpResponse, ApiError> {
+    let user_id_str = extract_user_id_from_cookie(&req)?;
+    let data = payload?.into_inner();
+    data.validate()?;
+
+    let shift = open_shift_service(data, &db, &user_id_str).await?;
+
+    Ok(HttpResponse::Created().json(serde_json::json!({
+        "status": "success",
+        "data": ShiftResponse::from(shift),
+        "code": 201
+    })))
+}
+
+pub async fn close_shift_handler(
+    req: HttpRequest,
+    payload: Result<Json<CloseShiftDTO>, ActixError>,
+    db: Data<Database>,
+    path: Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let shift_id = path.into_inner();
+    let user_id_str = extract_user_id_from_cookie(&req)?;
+    let data = payload?.into_inner();
+    data.validate()?;
+
+    let shift = close_shift_service(&shift_id, data, &db, &user_id_str).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "data": ShiftResponse::from(shift),
+        "code": 200
+    })))
+}
@@ -0,0 +1,19 @@
+use super::handler::{
+    close_shift_handler, get_cashier_report_handler, get_current_shift_handler, get_shift_handler,
+    get_shifts_handler, open_shift_handler,
+};
+use crate::middlewares::auth_middleware::AuthMiddleware;
+use actix_web::web;
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/shifts")
+            .wrap(AuthMiddleware)
+            .route("", web::get().to(get_shifts_handler))
+            .route("open", web::post().to(open_shift_handler))
+            .route("current", web::get().to(get_current_shift_handler))
+            .route("report/cashiers", web::get().to(get_cashier_report_handler))
+            .route("{id}", web::get().to(get_shift_handler))
+            .route("{id}/close", web::post().to(close_shift_handler)),
+    );
+}
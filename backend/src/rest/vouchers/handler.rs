@@ -0,0 +1,103 @@
+use actix_web::{
+    Error as ActixError, HttpRequest, HttpResponse, Result,
+    web::{Data, Json, Path, Query},
+};
+
+use crate::errors::ApiError;
+use crate::models::voucher::{UpdateVoucherDTO, VoucherDTO, VoucherListQuery, VoucherResponse};
+use crate::services::voucher_service::{
+    create_voucher_service, delete_voucher_service, get_voucher_service, get_vouchers_service,
+    update_voucher_service,
+};
+use crate::utils::extract_user_id_from_cookie;
+use mongodb::Database;
+use validator::Validate;
+
+pub async fn get_vouchers_handler(
+    req: HttpRequest,
+    db: Data<Database>,
+    query: Query<VoucherListQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let user_id_str = extract_user_id_from_cookie(&req)?;
+    let (vouchers, pagination) =
+        get_vouchers_service(&db, &user_id_str, query.into_inner()).await?;
+
+    let vouchers_response: Vec<VoucherResponse> =
+        vouchers.into_iter().map(VoucherResponse::from).collect();
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "data": vouchers_response,
+        "pagination": pagination,
+        "code": 200
+    })))
+}
+
+pub async fn get_voucher_handler(
+    req: HttpRequest,
+    path: Path<String>,
+    db: Data<Database>,
+) -> Result<HttpResponse, ApiError> {
+    let voucher_id = path.into_inner();
+    let user_id_str = extract_user_id_from_cookie(&req)?;
+    let voucher = get_voucher_service(&voucher_id, &db, &user_id_str).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "data": VoucherResponse::from(voucher),
+        "code": 200
+    })))
+}
+
+pub async fn post_voucher_handler(
+    req: HttpRequest,
+    payload: Result<Json<VoucherDTO>, ActixError>,
+    db: Data<Database>,
+) -> Result<HttpResponse, ApiError> {
+    let user_id_str = extract_user_id_from_cookie(&req)?;
+    let data = payload?.into_inner();
+    data.validate()?;
+
+    let voucher = create_voucher_service(data, &db, &user_id_str).await?;
+
+    Ok(HttpResponse::Created().json(serde_json::json!({
+        "status": "success",
+        "data": VoucherResponse::from(voucher),
+        "code": 201
+    })))
+}
+
+pub async fn patch_voucher_handler(
+    req: HttpRequest,
+    payload: Result<Json<UpdateVoucherDTO>, ActixError>,
+    db: Data<Database>,
+    path: Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let voucher_id = path.into_inner();
+    let user_id_str = extract_user_id_from_cookie(&req)?;
+    let data = payload?.into_inner();
+    data.validate()?;
+
+    let voucher = update_voucher_service(&voucher_id, data, &db, &user_id_str).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "data": VoucherResponse::from(voucher),
+        "code": 200
+    })))
+}
+
+pub async fn delete_voucher_handler(
+    req: HttpRequest,
+    db: Data<Database>,
+    path: Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let voucher_id = path.into_inner();
+    let user_id_str = extract_user_id_from_cookie(&req)?;
+    delete_voucher_service(&voucher_id, &db, &user_id_str).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "code": 204
+    })))
+}
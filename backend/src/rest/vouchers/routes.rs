@@ -0,0 +1,18 @@
+use super::handler::{
+    delete_voucher_handler, get_voucher_handler, get_vouchers_handler, patch_voucher_handler,
+    post_voucher_handler,
+};
+use crate::middlewares::auth_middleware::AuthMiddleware;
+use actix_web::web;
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/vouchers")
+            .wrap(AuthMiddleware)
+            .route("", web::get().to(get_vouchers_handler))
+            .route("", web::post().to(post_voucher_handler))
+            .route("{id}", web::get().to(get_voucher_handler))
+            .route("{id}", web::patch().to(patch_voucher_handler))
+            .route("{id}", web::delete().to(delete_voucher_handler)),
+    );
+}
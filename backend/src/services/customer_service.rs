@@ -0,0 +1,372 @@
+use crate::errors::ServiceError;
+use crate::models::customer::{
+    Customer, CustomerDTO, CustomerListQuery, CustomerPurchaseSummary, UpdateCustomerDTO,
+};
+use crate::models::money::Money;
+use crate::models::pagination::{Pagination, page_and_limit};
+use crate::models::sale::{Sale, SaleListQuery};
+use crate::services::sale_service::get_sales_service;
+use crate::utils::{handle_duplicate_key_error, string_id_to_obj_id};
+use bson::datetime::DateTime as BsonDateTime;
+use bson::oid::ObjectId;
+use chrono::Utc;
+use futures::stream::TryStreamExt;
+use mongodb::{
+    Collection, Database,
+    bson::{doc, from_document},
+    error::Error as MongoError,
+    options::ReturnDocument,
+};
+
+pub async fn get_customers_service(
+    db: &Database,
+    user_id: &str,
+    query: CustomerListQuery,
+) -> Result<(Vec<Customer>, Pagination), ServiceError> {
+    let user_id = match string_id_to_obj_id(user_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let mut filter = doc! { "user_id": user_id };
+    if let Some(search) = query
+        .search
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+    {
+        let pattern = doc! { "$regex": regex::escape(search), "$options": "i" };
+        filter.insert(
+            "$or",
+            vec![doc! { "name": pattern.clone() }, doc! { "phone": pattern }],
+        );
+    }
+
+    let (page, limit) = page_and_limit(query.page, query.limit);
+    let collection: Collection<Customer> = db.collection("customers");
+
+    let total = collection
+        .count_documents(filter.clone())
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+
+    let mut cursor = collection
+        .find(filter)
+        .sort(doc! { "name": 1, "_id": 1 })
+        .skip((page - 1) * limit)
+        .limit(limit as i64)
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+
+    let mut customers: Vec<Customer> = Vec::new();
+
+    while let Some(customer) = cursor
+        .try_next()
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?
+    {
+        customers.push(customer);
+    }
+
+    Ok((customers, Pagination::new(page, limit, total)))
+}
+
+pub async fn get_customer_service(
+    customer_id: &str,
+    db: &Database,
+    user_id: &str,
+) -> Result<Customer, ServiceError> {
+    let user_id = match string_id_to_obj_id(user_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let customer_id = match string_id_to_obj_id(customer_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    find_customer(customer_id, user_id, db).await
+}
+
+pub async fn create_customer_service(
+    payload: CustomerDTO,
+    db: &Database,
+    user_id: &str,
+) -> Result<Customer, ServiceError> {
+    let user_id = match string_id_to_obj_id(user_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let now = BsonDateTime::from_chrono(Utc::now());
+
+    let customer = Customer {
+        id: None,
+        user_id,
+        name: payload.name.trim().to_string(),
+        phone: normalize_phone(payload.phone)?,
+        address: payload.address,
+        notes: payload.notes,
+        loyalty_points: 0,
+        created_at: Some(now),
+        updated_at: Some(now),
+    };
+
+    let collection: Collection<Customer> = db.collection("customers");
+    let result = collection
+        .insert_one(&customer)
+        .await
+        .map_err(customer_write_error)?;
+
+    Ok(Customer {
+        id: result.inserted_id.as_object_id(),
+        ..customer
+    })
+}
+
+pub async fn update_customer_service(
+    customer_id: &str,
+    payload: UpdateCustomerDTO,
+    db: &Database,
+    user_id: &str,
+) -> Result<Customer, ServiceError> {
+    let user_id = match string_id_to_obj_id(user_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let customer_id = match string_id_to_obj_id(customer_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let mut update_doc = doc! {};
+    let mut unset_doc = doc! {};
+
+    if let Some(name) = payload.name {
+        update_doc.insert("name", name.trim());
+    }
+    if payload.phone.is_some() {
+        match normalize_phone(payload.phone)? {
+            Some(phone) => update_doc.insert("phone", phone),
+            // Nomor HP dikosongkan: field dihapus, index unik hanya berlaku untuk yang terisi
+            None => unset_doc.insert("phone", ""),
+        };
+    }
+    if let Some(address) = payload.address {
+        update_doc.insert("address", address);
+    }
+    if let Some(notes) = payload.notes {
+        update_doc.insert("notes", notes);
+    }
+
+    if update_doc.is_empty() && unset_doc.is_empty() {
+        return Err(ServiceError::BadRequest(
+            "Tidak ada data untuk di-update".to_string(),
+        ));
+    }
+
+    update_doc.insert("updated_at", BsonDateTime::from_chrono(Utc::now()));
+    let mut update = doc! { "$set": update_doc };
+    if !unset_doc.is_empty() {
+        update.insert("$unset", unset_doc);
+    }
+
+    let collection: Collection<Customer> = db.collection("customers");
+
+    collection
+        .find_one_and_update(doc! { "_id": customer_id, "user_id": user_id }, update)
+        .return_document(ReturnDocument::After)
+        .await
+        .map_err(customer_write_error)?
+        .ok_or_else(|| {
+            ServiceError::NotFound(format!(
+                "Pelanggan dengan ID '{}' tidak ditemukan",
+                customer_id
+            ))
+        })
+}
+
+/// Pelanggan yang sudah punya transaksi tidak bisa dihapus supaya riwayat sale tetap utuh
+pub async fn delete_customer_service(
+    customer_id: &str,
+    db: &Database,
+    user_id: &str,
+) -> Result<bool, ServiceError> {
+    let user_id = match string_id_to_obj_id(user_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let customer_id = match string_id_to_obj_id(customer_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let sales: Collection<Sale> = db.collection("sales");
+    let sale_count = sales
+        .count_documents(doc! { "user_id": user_id, "customer_id": customer_id })
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+
+    if sale_count > 0 {
+        return Err(ServiceError::Conflict(format!(
+            "Pelanggan sudah memiliki {} transaksi dan tidak bisa dihapus",
+            sale_count
+        )));
+    }
+
+    let collection: Collection<Customer> = db.collection("customers");
+
+    let result = collection
+        .delete_one(doc! { "_id": customer_id, "user_id": user_id })
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+
+    if result.deleted_count == 0 {
+        return Err(ServiceError::NotFound(format!(
+            "Pelanggan dengan ID '{}' tidak ditemukan",
+            customer_id
+        )));
+    }
+
+    Ok(true)
+}
+
+/// Riwayat belanja pelanggan: list sale (filter/sort/paging sama dengan list sale) + ringkasan
+pub async fn get_customer_sales_service(
+    customer_id: &str,
+    db: &Database,
+    user_id: &str,
+    mut query: SaleListQuery,
+) -> Result<(Vec<Sale>, Pagination, CustomerPurchaseSummary), ServiceError> {
+    let customer = get_customer_service(customer_id, db, user_id).await?;
+    let customer_id = customer.id.expect("Customer.id harus ada");
+
+    query.customer_id = Some(customer_id.to_hex());
+    let (sales, pagination) = get_sales_service(db, user_id, query).await?;
+    let summary = get_customer_purchase_summary(customer_id, customer.user_id, db).await?;
+
+    Ok((sales, pagination, summary))
+}
+
+/// Ringkasan total belanja pelanggan, sale yang di-void tidak dihitung
+async fn get_customer_purchase_summary(
+    customer_id: ObjectId,
+    user_id: ObjectId,
+    db: &Database,
+) -> Result<CustomerPurchaseSummary, ServiceError> {
+    let collection: Collection<Sale> = db.collection("sales");
+
+    let pipeline = vec![
+        doc! { "$match": {
+            "user_id": user_id,
+            "customer_id": customer_id,
+            "status": { "$ne": "voided" },
+        } },
+        doc! { "$group": {
+            "_id": null,
+            "transaction_count": { "$sum": 1 },
+            "total_amount": { "$sum": "$total_amount" },
+            "paid_amount": { "$sum": "$paid_amount" },
+            "remaining_amount": { "$sum": "$remaining_amount" },
+            "refunded_amount": { "$sum": "$refunded_amount" },
+            "last_purchase_at": { "$max": "$sale_date" },
+        } },
+        doc! { "$addFields": {
+            "last_purchase_at": { "$dateToString": {
+                "date": "$last_purchase_at",
+                "format": "%Y-%m-%dT%H:%M:%S.%LZ",
+            } },
+        } },
+    ];
+
+    let mut cursor = collection
+        .aggregate(pipeline)
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+
+    match cursor
+        .try_next()
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?
+    {
+        Some(document) => {
+            from_document(document).map_err(|e| ServiceError::DatabaseError(e.to_string()))
+        }
+        None => Ok(CustomerPurchaseSummary {
+            transaction_count: 0,
+            total_amount: Money::ZERO,
+            paid_amount: Money::ZERO,
+            remaining_amount: Money::ZERO,
+            refunded_amount: Money::ZERO,
+            last_purchase_at: None,
+        }),
+    }
+}
+
+/// Pelanggan milik toko ini, dipakai juga untuk validasi `customer_id` di sale
+pub async fn find_customer(
+    customer_id: ObjectId,
+    user_id: ObjectId,
+    db: &Database,
+) -> Result<Customer, ServiceError> {
+    let collection: Collection<Customer> = db.collection("customers");
+
+    collection
+        .find_one(doc! { "_id": customer_id, "user_id": user_id })
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?
+        .ok_or_else(|| {
+            ServiceError::NotFound(format!(
+                "Pelanggan dengan ID '{}' tidak ditemukan",
+                customer_id
+            ))
+        })
+}
+
+/// Nomor HP di-trim dulu baru dicek panjangnya, string kosong berarti tanpa nomor HP
+fn normalize_phone(phone: Option<String>) -> Result<Option<String>, ServiceError> {
+    let Some(phone) = phone
+        .map(|p| p.trim().to_string())
+        .filter(|p| !p.is_empty())
+    else {
+        return Ok(None);
+    };
+
+    if !(6..=20).contains(&phone.chars().count()) {
+        return Err(ServiceError::BadRequest("Nomor HP 6-20 karakter".into()));
+    }
+
+    Ok(Some(phone))
+}
+
+fn customer_write_error(e: MongoError) -> ServiceError {
+    // Index unik (user_id, phone): pesan bawaan akan menyebut user_id, bukan phone
+    if handle_duplicate_key_error(&e).is_some() {
+        return ServiceError::Conflict("Nomor HP sudah dipakai pelanggan lain".into());
+    }
+    ServiceError::DatabaseError(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_phone_trims_before_checking_length() {
+        assert_eq!(
+            normalize_phone(Some("  0812345  ".to_string())).unwrap(),
+            Some("0812345".to_string())
+        );
+        assert!(normalize_phone(Some("  08123  ".to_string())).is_err());
+        assert!(normalize_phone(Some("0".repeat(21))).is_err());
+    }
+
+    #[test]
+    fn normalize_phone_treats_blank_as_no_phone() {
+        assert_eq!(normalize_phone(None).unwrap(), None);
+        assert_eq!(normalize_phone(Some("   ".to_string())).unwrap(), None);
+    }
+}
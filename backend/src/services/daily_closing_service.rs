@@ -0,0 +1,554 @@
+use crate::errors::ServiceError;
+use crate::models::daily_closing::{CloseDayDTO, DailyClosing, ZReport, ZReportStatus};
+use crate::models::money::Money;
+use crate::models::payment_method::PaymentMethod;
+use crate::models::sale::{PaymentMethodTotal, Sale};
+use crate::models::sale_payment::SalePayment;
+use crate::models::sale_return::SaleReturn;
+use crate::models::store_setting::StoreSetting;
+use crate::services::sale_service::parse_query_date;
+use crate::services::store_setting_service::find_store_setting;
+use crate::utils::{
+    finish_transaction, handle_duplicate_key_error, start_transaction, string_id_to_obj_id,
+    transaction_error,
+};
+use bson::datetime::DateTime as BsonDateTime;
+use bson::oid::ObjectId;
+use chrono::{DateTime, NaiveDate, Utc};
+use mongodb::{
+    ClientSession, Collection, Database,
+    bson::{Document, doc},
+};
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+
+const MAX_TRANSACTION_ATTEMPTS: u32 = 3;
+
+/// Z-report tanggal tertentu: snapshot jika sudah ditutup, dihitung langsung jika belum
+pub async fn get_z_report_service(
+    date: Option<String>,
+    db: &Database,
+    user_id: &str,
+) -> Result<ZReportStatus, ServiceError> {
+    let user_id = match string_id_to_obj_id(user_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let setting = find_store_setting(user_id, db).await?;
+    let date = report_date(date.as_deref(), &setting)?;
+
+    // Dibaca dalam satu transaksi supaya semua collection berasal dari snapshot yang sama
+    let mut session = start_transaction(db).await?;
+    let result = async {
+        if let Some(closing) = find_closing(user_id, date, db, &mut session).await? {
+            return Ok(ZReportStatus::Closed(closing));
+        }
+        let report = build_z_report(user_id, date, &setting, db, &mut session).await?;
+        Ok(ZReportStatus::Open(report))
+    }
+    .await;
+    finish_transaction(&mut session, result).await
+}
+
+/// Tutup hari: simpan snapshot Z-report dan kunci tanggalnya dari sale baru
+pub async fn close_day_service(
+    payload: CloseDayDTO,
+    db: &Database,
+    user_id: &str,
+) -> Result<DailyClosing, ServiceError> {
+    let user_id = match string_id_to_obj_id(user_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let setting = find_store_setting(user_id, db).await?;
+    let date = report_date(payload.date.as_deref(), &setting)?;
+
+    if date > setting.business_date(Utc::now()) {
+        return Err(ServiceError::BadRequest(
+            "Tanggal yang belum terjadi tidak bisa ditutup".into(),
+        ));
+    }
+
+    let mut attempt = 1;
+    loop {
+        let mut session = start_transaction(db).await?;
+        let result = insert_closing(
+            user_id,
+            date,
+            payload.notes.clone(),
+            &setting,
+            db,
+            &mut session,
+        )
+        .await;
+
+        match finish_transaction(&mut session, result).await {
+            // Bentrok dengan sale/void/retur/cicilan di tanggal yang sama, hitung ulang
+            Err(ServiceError::TransactionConflict(msg)) if attempt < MAX_TRANSACTION_ATTEMPTS => {
+                log::warn!("Tutup hari bentrok (percobaan {}): {}", attempt, msg);
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Snapshot dan insert closing dalam satu transaksi. Penanda hari ikut ditulis sehingga
+/// transaksi lain yang sedang menulis ke tanggal ini bentrok dan tidak lolos dari snapshot
+async fn insert_closing(
+    user_id: ObjectId,
+    date: NaiveDate,
+    notes: Option<String>,
+    setting: &StoreSetting,
+    db: &Database,
+    session: &mut ClientSession,
+) -> Result<DailyClosing, ServiceError> {
+    touch_business_day(user_id, date, db, session).await?;
+
+    if find_closing(user_id, date, db, session).await?.is_some() {
+        return Err(ServiceError::Conflict(format!(
+            "Tanggal {} sudah ditutup",
+            date
+        )));
+    }
+
+    let report = build_z_report(user_id, date, setting, db, session).await?;
+
+    let closing = DailyClosing {
+        id: None,
+        user_id,
+        date: date.to_string(),
+        report,
+        notes,
+        closed_by: user_id,
+        closed_at: BsonDateTime::from_chrono(Utc::now()),
+    };
+
+    // Index unik (user_id, date) menolak tutup hari dua kali yang bersamaan
+    let collection: Collection<DailyClosing> = db.collection("daily_closings");
+    let result = collection
+        .insert_one(&closing)
+        .session(&mut *session)
+        .await
+        .map_err(|e| {
+            if handle_duplicate_key_error(&e).is_some() {
+                return ServiceError::Conflict(format!("Tanggal {} sudah ditutup", date));
+            }
+            transaction_error(e)
+        })?;
+
+    Ok(DailyClosing {
+        id: result.inserted_id.as_object_id(),
+        ..closing
+    })
+}
+
+/// Tolak sale, void, retur atau cicilan yang tanggal bisnisnya (waktu `time`) sudah ditutup.
+/// Dipanggil di dalam transaksi tersebut
+pub async fn ensure_day_open(
+    user_id: ObjectId,
+    time: DateTime<Utc>,
+    setting: &StoreSetting,
+    db: &Database,
+    session: &mut ClientSession,
+) -> Result<(), ServiceError> {
+    let date = setting.business_date(time);
+    touch_business_day(user_id, date, db, session).await?;
+
+    if find_closing(user_id, date, db, session).await?.is_some() {
+        return Err(ServiceError::BadRequest(format!(
+            "Tanggal {} sudah ditutup (Z-report), tidak bisa menambah transaksi",
+            date
+        )));
+    }
+
+    Ok(())
+}
+
+/// Tulis penanda tanggal bisnis. Transaksi yang menulis ke tanggal yang sama dan tutup hari
+/// saling bentrok (write conflict), sehingga transaksi yang belum commit tidak terlewat
+/// dari snapshot Z-report
+async fn touch_business_day(
+    user_id: ObjectId,
+    date: NaiveDate,
+    db: &Database,
+    session: &mut ClientSession,
+) -> Result<(), ServiceError> {
+    let collection: Collection<Document> = db.collection("business_days");
+
+    collection
+        .update_one(
+            doc! { "user_id": user_id, "date": date.to_string() },
+            doc! { "$inc": { "write_count": 1_i64 } },
+        )
+        .upsert(true)
+        .session(&mut *session)
+        .await
+        .map_err(transaction_error)?;
+
+    Ok(())
+}
+
+fn report_date(date: Option<&str>, setting: &StoreSetting) -> Result<NaiveDate, ServiceError> {
+    match date {
+        Some(date) => parse_query_date(date),
+        None => Ok(setting.business_date(Utc::now())),
+    }
+}
+
+async fn find_closing(
+    user_id: ObjectId,
+    date: NaiveDate,
+    db: &Database,
+    session: &mut ClientSession,
+) -> Result<Option<DailyClosing>, ServiceError> {
+    let collection: Collection<DailyClosing> = db.collection("daily_closings");
+
+    collection
+        .find_one(doc! { "user_id": user_id, "date": date.to_string() })
+        .session(&mut *session)
+        .await
+        .map_err(transaction_error)
+}
+
+/// Data mentah satu tanggal bisnis untuk Z-report
+struct DayEvents {
+    sales: Vec<Sale>,                // sale dengan `sale_date` di tanggal ini
+    sale_payments: Vec<SalePayment>, // semua cicilan milik `sales`, kapan pun diterimanya
+    payments: Vec<SalePayment>,      // cicilan yang diterima di tanggal ini
+    returns: Vec<SaleReturn>,        // retur yang dibuat di tanggal ini
+    voided: Vec<Sale>,               // sale yang di-void di tanggal ini
+}
+
+/// Setiap kejadian masuk ke tanggal terjadinya: sale menurut `sale_date`, cicilan menurut
+/// `paid_at`, retur menurut `created_at` dan void menurut `void.voided_at`
+async fn build_z_report(
+    user_id: ObjectId,
+    date: NaiveDate,
+    setting: &StoreSetting,
+    db: &Database,
+    session: &mut ClientSession,
+) -> Result<ZReport, ServiceError> {
+    let (start, end) = setting.business_day_bounds(date);
+    let range = doc! {
+        "$gte": BsonDateTime::from_chrono(start),
+        "$lt": BsonDateTime::from_chrono(end),
+    };
+
+    let sale_collection: Collection<Sale> = db.collection("sales");
+    let payment_collection: Collection<SalePayment> = db.collection("sale_payments");
+    let return_collection: Collection<SaleReturn> = db.collection("sale_returns");
+
+    let sales: Vec<Sale> = find_all(
+        &sale_collection,
+        doc! { "user_id": user_id, "sale_date": range.clone() },
+        session,
+    )
+    .await?;
+    let sale_ids: Vec<ObjectId> = sales.iter().filter_map(|s| s.id).collect();
+
+    let events = DayEvents {
+        sales,
+        sale_payments: find_all(
+            &payment_collection,
+            doc! { "user_id": user_id, "sale_id": { "$in": sale_ids } },
+            session,
+        )
+        .await?,
+        payments: find_all(
+            &payment_collection,
+            doc! { "user_id": user_id, "paid_at": range.clone() },
+            session,
+        )
+        .await?,
+        returns: find_all(
+            &return_collection,
+            doc! { "user_id": user_id, "created_at": range.clone() },
+            session,
+        )
+        .await?,
+        voided: find_all(
+            &sale_collection,
+            doc! { "user_id": user_id, "void.voided_at": range },
+            session,
+        )
+        .await?,
+    };
+
+    Ok(summarize_day(date, &events))
+}
+
+fn summarize_day(date: NaiveDate, events: &DayEvents) -> ZReport {
+    // Cicilan sale-sale ini dihitung di tanggal diterimanya, bukan di tanggal sale
+    let mut installment_amounts: HashMap<ObjectId, Money> = HashMap::new();
+    for payment in &events.sale_payments {
+        *installment_amounts.entry(payment.sale_id).or_default() += payment.amount;
+    }
+
+    let mut report = ZReport {
+        date: date.to_string(),
+        transaction_count: 0,
+        gross_sales: Money::ZERO,
+        discount_amount: Money::ZERO,
+        net_sales: Money::ZERO,
+        tax_amount: Money::ZERO,
+        total_amount: Money::ZERO,
+        average_basket: Money::ZERO,
+        paid_amount: Money::ZERO,
+        remaining_amount: Money::ZERO,
+        change_amount: Money::ZERO,
+        installment_amount: Money::ZERO,
+        refunded_amount: Money::ZERO,
+        void_count: 0,
+        void_amount: Money::ZERO,
+        payment_methods: Vec::new(),
+    };
+
+    // Sale yang sudah di-void hanya dilaporkan di void_amount (tanggal void-nya),
+    // tidak ikut omzet maupun total per metode pembayaran
+    for sale in events.sales.iter().filter(|s| s.status != "voided") {
+        report.transaction_count += 1;
+        report.gross_sales += sale
+            .items
+            .iter()
+            .map(|item| item.price * item.quantity as i64)
+            .sum();
+        report.discount_amount += sale.total_discount;
+        report.tax_amount += sale.tax_amount;
+        report.total_amount += sale.total_amount;
+
+        let installments = sale
+            .id
+            .and_then(|id| installment_amounts.get(&id).copied())
+            .unwrap_or_default();
+        let mut paid = Money::ZERO;
+        for tender in &sale.sale_time_tenders(installments) {
+            paid += tender.amount - tender.change_amount;
+            report.change_amount += tender.change_amount;
+            add_payment_method_total(
+                &mut report.payment_methods,
+                &tender.payment_method,
+                tender.amount,
+                tender.change_amount,
+            );
+        }
+        report.paid_amount += paid;
+        report.remaining_amount += (sale.total_amount - paid).max(Money::ZERO);
+    }
+
+    // Cicilan yang diterima hari ini, termasuk untuk sale dari hari sebelumnya
+    for payment in &events.payments {
+        report.installment_amount += payment.amount;
+        add_payment_method_total(
+            &mut report.payment_methods,
+            &payment.payment_method,
+            payment.amount,
+            Money::ZERO,
+        );
+    }
+
+    report.refunded_amount = events.returns.iter().map(|r| r.refund_amount).sum();
+
+    report.void_count = events.voided.len() as i64;
+    report.void_amount = events.voided.iter().map(|s| s.total_amount).sum();
+
+    report.net_sales = report.gross_sales - report.discount_amount;
+    if report.transaction_count > 0 {
+        report.average_basket = report.total_amount.mul_div(1, report.transaction_count);
+    }
+    report
+        .payment_methods
+        .sort_by_key(|t| std::cmp::Reverse(t.amount));
+
+    report
+}
+
+async fn find_all<T>(
+    collection: &Collection<T>,
+    filter: Document,
+    session: &mut ClientSession,
+) -> Result<Vec<T>, ServiceError>
+where
+    T: DeserializeOwned + Send + Sync,
+{
+    let mut cursor = collection
+        .find(filter)
+        .session(&mut *session)
+        .await
+        .map_err(transaction_error)?;
+
+    let mut documents = Vec::new();
+    while let Some(document) = cursor
+        .next(&mut *session)
+        .await
+        .transpose()
+        .map_err(transaction_error)?
+    {
+        documents.push(document);
+    }
+
+    Ok(documents)
+}
+
+fn add_payment_method_total(
+    totals: &mut Vec<PaymentMethodTotal>,
+    payment_method: &PaymentMethod,
+    tendered: Money,
+    change: Money,
+) {
+    let payment_method_id = payment_method.id.map(|id| id.to_hex());
+    let total = match totals
+        .iter_mut()
+        .position(|t| t.payment_method_id == payment_method_id)
+    {
+        Some(index) => &mut totals[index],
+        None => {
+            totals.push(PaymentMethodTotal {
+                payment_method_id,
+                name: payment_method.name.clone(),
+                tendered_amount: Money::ZERO,
+                change_amount: Money::ZERO,
+                amount: Money::ZERO,
+                transaction_count: 0,
+            });
+            totals.last_mut().expect("baru ditambahkan")
+        }
+    };
+
+    total.tendered_amount += tendered;
+    total.change_amount += change;
+    total.amount += tendered - change;
+    total.transaction_count += 1;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mongodb::bson::from_document;
+
+    fn cash() -> Document {
+        doc! { "name": "Tunai", "is_active": true, "is_cash": true }
+    }
+
+    fn credit_sale(sale_id: ObjectId, tenders: Vec<Document>) -> Sale {
+        let mut sale = doc! {
+            "_id": sale_id,
+            "user_id": ObjectId::new(),
+            "customer_id": null,
+            "items": [{
+                "product_id": ObjectId::new(),
+                "product_name": "Beras 5 kg",
+                "sku": "SKU-1",
+                "quantity": 1,
+                "price": 10_000_000_i64,
+                "subtotal": 10_000_000_i64,
+            }],
+            "subtotal_amount": 10_000_000_i64,
+            "total_amount": 10_000_000_i64,
+            "paid_amount": 10_000_000_i64,
+            "remaining_amount": 0_i64,
+            "status": "paid",
+            "invoice_number": "INV/00001",
+            "notes": null,
+            "tenders": tenders,
+        };
+        if sale.get_array("tenders").is_ok_and(|t| t.is_empty()) {
+            sale.insert("payment_method", cash());
+        }
+        from_document(sale).unwrap()
+    }
+
+    fn installment(sale_id: ObjectId, amount: i64) -> SalePayment {
+        SalePayment {
+            id: Some(ObjectId::new()),
+            user_id: ObjectId::new(),
+            sale_id,
+            payment_method: from_document(cash()).unwrap(),
+            amount: Money::from_minor(amount),
+            paid_at: BsonDateTime::now(),
+            notes: None,
+            created_at: None,
+        }
+    }
+
+    fn events(sales: Vec<Sale>, sale_payments: Vec<SalePayment>) -> DayEvents {
+        DayEvents {
+            sales,
+            sale_payments,
+            payments: Vec::new(),
+            returns: Vec::new(),
+            voided: Vec::new(),
+        }
+    }
+
+    fn date() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 6, 1).unwrap()
+    }
+
+    #[test]
+    fn legacy_sale_tender_excludes_later_installments() {
+        let sale_id = ObjectId::new();
+        let sale = credit_sale(sale_id, Vec::new());
+        let report = summarize_day(
+            date(),
+            &events(vec![sale], vec![installment(sale_id, 4_000_000)]),
+        );
+
+        assert_eq!(report.paid_amount, Money::from_minor(6_000_000));
+        assert_eq!(report.remaining_amount, Money::from_minor(4_000_000));
+        assert_eq!(report.payment_methods.len(), 1);
+        assert_eq!(
+            report.payment_methods[0].amount,
+            Money::from_minor(6_000_000)
+        );
+    }
+
+    #[test]
+    fn installment_tenders_are_skipped_by_payment_id() {
+        let sale_id = ObjectId::new();
+        let payment = installment(sale_id, 4_000_000);
+        let sale = credit_sale(
+            sale_id,
+            vec![
+                doc! { "payment_method": cash(), "amount": 6_000_000_i64 },
+                doc! {
+                    "payment_method": cash(),
+                    "amount": 4_000_000_i64,
+                    "payment_id": payment.id,
+                },
+            ],
+        );
+        let report = summarize_day(date(), &events(vec![sale], vec![payment]));
+
+        assert_eq!(report.paid_amount, Money::from_minor(6_000_000));
+        assert_eq!(report.remaining_amount, Money::from_minor(4_000_000));
+        assert_eq!(report.installment_amount, Money::ZERO);
+    }
+
+    #[test]
+    fn voided_sales_are_only_reported_as_voids() {
+        let (voided_id, paid_id) = (ObjectId::new(), ObjectId::new());
+        let voided = || {
+            let mut sale = credit_sale(voided_id, Vec::new());
+            sale.status = "voided".to_string();
+            sale
+        };
+        let paid = credit_sale(paid_id, Vec::new());
+
+        let mut day = events(vec![voided(), paid], Vec::new());
+        day.voided = vec![voided()];
+        let report = summarize_day(date(), &day);
+
+        assert_eq!(report.transaction_count, 1);
+        assert_eq!(report.total_amount, Money::from_minor(10_000_000));
+        assert_eq!(report.paid_amount, Money::from_minor(10_000_000));
+        assert_eq!(
+            report.payment_methods[0].amount,
+            Money::from_minor(10_000_000)
+        );
+        assert_eq!(report.void_count, 1);
+        assert_eq!(report.void_amount, Money::from_minor(10_000_000));
+    }
+}
@@ -0,0 +1,143 @@
+use crate::errors::ServiceError;
+use crate::models::idempotency::{IdempotencyKey, IdempotentSale};
+use crate::models::sale::{Sale, SaleDTO, SaleResponse};
+use crate::services::sale_service::{
+    IdempotentRequest, create_idempotent_sale_service, get_sale_service, normalize_legacy_payment,
+};
+use crate::utils::{string_id_to_obj_id, transaction_error};
+use bson::datetime::DateTime as BsonDateTime;
+use bson::oid::ObjectId;
+use chrono::Utc;
+use mongodb::{ClientSession, Collection, Database, bson::doc};
+use sha2::{Digest, Sha256};
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+const MAX_KEY_LENGTH: usize = 255;
+
+/// Buat sale sekali per Idempotency-Key. Key ditulis di transaksi yang sama dengan sale
+/// (index unik), retry dengan payload yang sama mendapat sale yang sama, payload berbeda ditolak 409
+pub async fn create_sale_idempotent_service(
+    key: &str,
+    mut payload: SaleDTO,
+    db: &Database,
+    user_id: &str,
+) -> Result<IdempotentSale, ServiceError> {
+    let user_oid = match string_id_to_obj_id(user_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let key = key.trim();
+    if key.is_empty() || key.len() > MAX_KEY_LENGTH {
+        return Err(ServiceError::BadRequest(format!(
+            "{} harus 1-{} karakter",
+            IDEMPOTENCY_KEY_HEADER, MAX_KEY_LENGTH
+        )));
+    }
+
+    // Format lama dan split tender dengan isi yang sama menghasilkan hash yang sama
+    normalize_legacy_payment(&mut payload)?;
+    let request = IdempotentRequest {
+        key: key.to_string(),
+        request_hash: hash_payload(&payload)?,
+    };
+
+    if let Some(existing) = find_idempotency_key(user_oid, key, db).await? {
+        return replay(existing, &request, db, user_id).await;
+    }
+
+    match create_idempotent_sale_service(payload, &request, db, user_id).await {
+        Ok(sale) => Ok(IdempotentSale::Created(Box::new(sale))),
+        // Request lain dengan key yang sama commit lebih dulu (transaksi ini gagal
+        // di index unik atau bentrok), kembalikan hasil request tersebut
+        Err(err) => match find_idempotency_key(user_oid, key, db).await? {
+            Some(existing) => replay(existing, &request, db, user_id).await,
+            None => Err(err),
+        },
+    }
+}
+
+/// Simpan key beserta sale dan respons yang dihasilkan, dipanggil di dalam transaksi sale
+pub async fn insert_idempotency_key(
+    request: &IdempotentRequest,
+    sale: &Sale,
+    db: &Database,
+    session: &mut ClientSession,
+) -> Result<(), ServiceError> {
+    let collection: Collection<IdempotencyKey> = db.collection("idempotency_keys");
+    let response = serde_json::to_string(&SaleResponse::from(sale.clone()))
+        .map_err(|e| ServiceError::Unexpected(e.to_string()))?;
+
+    collection
+        .insert_one(IdempotencyKey {
+            id: None,
+            user_id: sale.user_id,
+            key: request.key.clone(),
+            request_hash: request.request_hash.clone(),
+            sale_id: sale.id,
+            response: Some(response),
+            created_at: BsonDateTime::from_chrono(Utc::now()),
+        })
+        .session(&mut *session)
+        .await
+        .map_err(transaction_error)?;
+
+    Ok(())
+}
+
+async fn find_idempotency_key(
+    user_id: ObjectId,
+    key: &str,
+    db: &Database,
+) -> Result<Option<IdempotencyKey>, ServiceError> {
+    let collection: Collection<IdempotencyKey> = db.collection("idempotency_keys");
+
+    collection
+        .find_one(doc! { "user_id": user_id, "key": key })
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))
+}
+
+async fn replay(
+    existing: IdempotencyKey,
+    request: &IdempotentRequest,
+    db: &Database,
+    user_id: &str,
+) -> Result<IdempotentSale, ServiceError> {
+    if existing.request_hash != request.request_hash {
+        return Err(ServiceError::Conflict(format!(
+            "{} '{}' sudah dipakai untuk request yang berbeda",
+            IDEMPOTENCY_KEY_HEADER, request.key
+        )));
+    }
+
+    // Key lama dari sebelum key disimpan bersama sale bisa belum punya sale_id
+    let Some(sale_id) = existing.sale_id else {
+        return Err(ServiceError::Conflict(format!(
+            "Request dengan {} '{}' masih diproses",
+            IDEMPOTENCY_KEY_HEADER, request.key
+        )));
+    };
+
+    let response = match existing.response {
+        Some(response) => {
+            serde_json::from_str(&response).map_err(|e| ServiceError::Unexpected(e.to_string()))?
+        }
+        // Key lama tanpa respons tersimpan: bentuk ulang dari sale-nya
+        None => {
+            let sale = get_sale_service(&sale_id.to_hex(), db, user_id).await?;
+            serde_json::to_value(SaleResponse::from(sale))
+                .map_err(|e| ServiceError::Unexpected(e.to_string()))?
+        }
+    };
+    Ok(IdempotentSale::Replayed(response))
+}
+
+/// SHA-256 dari payload yang sudah di-parse, jadi beda spasi/urutan key JSON tidak berpengaruh
+fn hash_payload(payload: &SaleDTO) -> Result<String, ServiceError> {
+    let bytes = serde_json::to_vec(payload).map_err(|e| ServiceError::Unexpected(e.to_string()))?;
+    Ok(Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect())
+}
@@ -0,0 +1,258 @@
+use crate::errors::ServiceError;
+use crate::models::money::Money;
+use crate::models::sale::Sale;
+use crate::models::store_setting::StoreSetting;
+use crate::services::sale_service::get_sale_service;
+use crate::services::store_setting_service::find_store_setting;
+use crate::utils::{ascii_printable, format_rupiah};
+use mongodb::Database;
+use printpdf::{BuiltinFont, IndirectFontRef, Mm, PdfDocument, PdfLayerReference};
+
+const PAGE_WIDTH: f32 = 210.0;
+const PAGE_HEIGHT: f32 = 297.0;
+const MARGIN: f32 = 15.0;
+const LINE_HEIGHT: f32 = 5.0;
+const TABLE_FONT_SIZE: f32 = 9.0;
+
+/// Ambil sale + pengaturan toko lalu render invoice PDF-nya
+pub async fn get_sale_invoice_pdf_service(
+    sale_id: &str,
+    db: &Database,
+    user_id: &str,
+) -> Result<(Sale, Vec<u8>), ServiceError> {
+    let sale = get_sale_service(sale_id, db, user_id).await?;
+    let setting = find_store_setting(sale.user_id, db).await?;
+
+    let pdf = render_invoice_pdf(&sale, &setting)?;
+    Ok((sale, pdf))
+}
+
+/// Penulis baris demi baris, otomatis pindah halaman jika ruang habis
+struct InvoiceWriter {
+    doc: printpdf::PdfDocumentReference,
+    layer: PdfLayerReference,
+    regular: IndirectFontRef,
+    bold: IndirectFontRef,
+    mono: IndirectFontRef,
+    y: f32,
+}
+
+impl InvoiceWriter {
+    fn text(&self, text: &str, size: f32, x: f32, font: &IndirectFontRef) {
+        self.layer
+            .use_text(ascii_printable(text), size, Mm(x), Mm(self.y), font);
+    }
+
+    fn line(&mut self, text: &str, size: f32, font: &IndirectFontRef) {
+        self.ensure_space();
+        self.text(text, size, MARGIN, font);
+        self.y -= LINE_HEIGHT;
+    }
+
+    fn mono_line(&mut self, text: &str) {
+        let font = self.mono.clone();
+        self.line(text, TABLE_FONT_SIZE, &font);
+    }
+
+    fn gap(&mut self) {
+        self.y -= LINE_HEIGHT / 2.0;
+    }
+
+    fn ensure_space(&mut self) {
+        if self.y > MARGIN {
+            return;
+        }
+        let (page, layer) = self
+            .doc
+            .add_page(Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Invoice");
+        self.layer = self.doc.get_page(page).get_layer(layer);
+        self.y = PAGE_HEIGHT - MARGIN;
+    }
+}
+
+pub fn render_invoice_pdf(sale: &Sale, setting: &StoreSetting) -> Result<Vec<u8>, ServiceError> {
+    let invoice_number = sale.invoice_number.clone().unwrap_or_else(|| {
+        sale.id
+            .map(|id| id.to_hex())
+            .unwrap_or_else(|| "-".to_string())
+    });
+
+    let (doc, page, layer) = PdfDocument::new(
+        format!("Invoice {}", invoice_number),
+        Mm(PAGE_WIDTH),
+        Mm(PAGE_HEIGHT),
+        "Invoice",
+    );
+    let layer = doc.get_page(page).get_layer(layer);
+
+    let font_error = |e: printpdf::Error| ServiceError::Unexpected(e.to_string());
+    let regular = doc
+        .add_builtin_font(BuiltinFont::Helvetica)
+        .map_err(font_error)?;
+    let bold = doc
+        .add_builtin_font(BuiltinFont::HelveticaBold)
+        .map_err(font_error)?;
+    let mono = doc
+        .add_builtin_font(BuiltinFont::Courier)
+        .map_err(font_error)?;
+
+    let mut w = InvoiceWriter {
+        doc,
+        layer,
+        regular,
+        bold,
+        mono,
+        y: PAGE_HEIGHT - MARGIN,
+    };
+
+    // Header toko
+    let bold = w.bold.clone();
+    let regular = w.regular.clone();
+    let store_name = setting.store_name.as_deref().unwrap_or("Toko");
+    w.text("INVOICE", 18.0, PAGE_WIDTH - MARGIN - 35.0, &bold);
+    w.line(store_name, 16.0, &bold);
+    w.gap();
+    if let Some(address) = &setting.store_address {
+        w.line(address, 10.0, &regular);
+    }
+    if let Some(phone) = &setting.store_phone {
+        w.line(&format!("Telp: {}", phone), 10.0, &regular);
+    }
+    w.gap();
+
+    // Info invoice
+    let sale_date = sale
+        .sale_date
+        .map(|t| {
+            setting
+                .local_time(t.to_chrono())
+                .format("%d-%m-%Y %H:%M")
+                .to_string()
+        })
+        .unwrap_or_else(|| "-".to_string());
+    w.line(&format!("No. Invoice : {}", invoice_number), 10.0, &regular);
+    w.line(&format!("Tanggal     : {}", sale_date), 10.0, &regular);
+    w.line(
+        &format!("Status      : {}", sale.status.to_uppercase()),
+        10.0,
+        &regular,
+    );
+    if let Some(void) = &sale.void {
+        w.line(&format!("Dibatalkan (void): {}", void.reason), 10.0, &bold);
+    }
+    w.gap();
+
+    // Tabel item (font monospace supaya kolom rata)
+    let separator = "-".repeat(88);
+    w.mono_line(&format!(
+        "{:<4}{:<40}{:>6}{:>18}{:>20}",
+        "No", "Produk", "Qty", "Harga", "Subtotal"
+    ));
+    w.mono_line(&separator);
+    for (i, item) in sale.items.iter().enumerate() {
+        w.mono_line(&format!(
+            "{:<4}{:<40}{:>6}{:>18}{:>20}",
+            i + 1,
+            truncate(&format!("{} ({})", item.product_name, item.sku), 39),
+            item.quantity,
+            format_rupiah(item.price),
+            format_rupiah(item.subtotal)
+        ));
+        if let Some(discount) = &item.discount {
+            w.mono_line(&format!(
+                "{:<4}{:<64}{:>20}",
+                "",
+                discount.label(),
+                format!("-{}", format_rupiah(discount.amount))
+            ));
+        }
+        if let Some(promotion) = &item.promotion {
+            w.mono_line(&format!(
+                "{:<4}{:<64}{:>20}",
+                "",
+                truncate(&promotion.label(), 63),
+                format!("-{}", format_rupiah(promotion.amount))
+            ));
+        }
+    }
+    w.mono_line(&separator);
+
+    // Total
+    let mut totals = Vec::new();
+    let tax_added = sale.tax_amount.is_positive() && !sale.tax_inclusive;
+    if sale.discount.is_some() || sale.voucher.is_some() || tax_added {
+        totals.push(("Subtotal".to_string(), format_rupiah(sale.subtotal_amount)));
+    }
+    if let Some(discount) = &sale.discount {
+        totals.push((
+            discount.label(),
+            format!("-{}", format_rupiah(discount.amount)),
+        ));
+    }
+    if let Some(voucher) = &sale.voucher {
+        totals.push((
+            voucher.label(),
+            format!("-{}", format_rupiah(voucher.amount)),
+        ));
+    }
+    if tax_added {
+        totals.push(("DPP".to_string(), format_rupiah(sale.tax_base)));
+        totals.push(("PPN".to_string(), format_rupiah(sale.tax_amount)));
+    }
+    totals.push(("Total".to_string(), format_rupiah(sale.total_amount)));
+    if sale.tax_amount.is_positive() && sale.tax_inclusive {
+        totals.push(("DPP".to_string(), format_rupiah(sale.tax_base)));
+        totals.push(("PPN (termasuk)".to_string(), format_rupiah(sale.tax_amount)));
+    }
+    // Dibayar = uang yang diterima, kelebihannya tampil sebagai kembalian
+    let tenders = sale.payment_tenders();
+    let change: Money = tenders.iter().map(|t| t.change_amount).sum();
+    totals.push((
+        "Dibayar".to_string(),
+        format_rupiah(sale.paid_amount + sale.change_amount),
+    ));
+    if change.is_positive() {
+        totals.push(("Kembalian".to_string(), format_rupiah(change)));
+    }
+    totals.push(("Sisa".to_string(), format_rupiah(sale.remaining_amount.max(Money::ZERO))));
+    if sale.refunded_amount.is_positive() {
+        totals.push(("Refund".to_string(), format_rupiah(sale.refunded_amount)));
+    }
+    for (label, value) in totals {
+        w.mono_line(&format!("{:>68}{:>20}", label, value));
+    }
+    w.gap();
+
+    if tenders.is_empty() {
+        w.line("Metode Pembayaran: -", 10.0, &regular);
+    } else {
+        w.line("Metode Pembayaran:", 10.0, &regular);
+        for tender in &tenders {
+            w.line(
+                &format!(
+                    "  {} - {}",
+                    tender.payment_method.name,
+                    format_rupiah(tender.amount)
+                ),
+                10.0,
+                &regular,
+            );
+        }
+    }
+    if let Some(notes) = &sale.notes {
+        w.line(&format!("Catatan: {}", notes), 10.0, &regular);
+    }
+
+    w.doc
+        .save_to_bytes()
+        .map_err(|e| ServiceError::Unexpected(e.to_string()))
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let mut truncated: String = text.chars().take(max_chars - 1).collect();
+    truncated.push('~');
+    truncated
+}
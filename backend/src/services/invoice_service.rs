@@ -0,0 +1,175 @@
+use crate::errors::ServiceError;
+use crate::models::store_setting::StoreSetting;
+use crate::utils::transaction_error;
+use bson::oid::ObjectId;
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use mongodb::{
+    ClientSession, Collection, Database, bson::Document, bson::doc, options::ReturnDocument,
+};
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+static INVOICE_TOKEN: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\{(YYYY|YY|MM|DD|seq)(?::0?(\d{1,2}))?\}").unwrap());
+
+/// Batas percobaan melewati nomor yang sudah terpakai sebelum menyerah
+const MAX_INVOICE_SKIPS: u32 = 1000;
+
+/// Format harus punya token {seq} supaya nomor invoice unik, ditambah token periode
+/// sesuai `invoice_reset` karena counter mulai dari 1 lagi di tiap periode
+pub fn validate_invoice_format(format: &str, invoice_reset: &str) -> Result<(), ServiceError> {
+    let tokens: Vec<String> = INVOICE_TOKEN
+        .captures_iter(format)
+        .map(|caps| caps[1].to_string())
+        .collect();
+    let has = |token: &str| tokens.iter().any(|t| t == token);
+
+    if !has("seq") {
+        return Err(ServiceError::BadRequest(
+            "Format invoice wajib mengandung {seq}, contoh: INV/{YYYY}/{MM}/{seq:05}".into(),
+        ));
+    }
+
+    let required: &[&str] = match invoice_reset {
+        "yearly" => &["YYYY"],
+        "monthly" => &["YYYY", "MM"],
+        _ => &[],
+    };
+    if let Some(missing) = required.iter().find(|token| !has(token)) {
+        return Err(ServiceError::BadRequest(format!(
+            "Format invoice dengan reset '{}' wajib mengandung {{{}}} supaya nomor tidak berulang",
+            invoice_reset, missing
+        )));
+    }
+
+    Ok(())
+}
+
+/// Contoh: "INV/{YYYY}/{MM}/{seq:05}" + seq 42 => "INV/2025/06/00042".
+/// `date` adalah tanggal bisnis toko, bukan tanggal UTC
+pub fn render_invoice_number(format: &str, date: NaiveDate, seq: i64) -> String {
+    INVOICE_TOKEN
+        .replace_all(format, |caps: &regex::Captures| match &caps[1] {
+            "YYYY" => format!("{:04}", date.year()),
+            "YY" => format!("{:02}", date.year() % 100),
+            "MM" => format!("{:02}", date.month()),
+            "DD" => format!("{:02}", date.day()),
+            _ => {
+                let width = caps
+                    .get(2)
+                    .and_then(|w| w.as_str().parse::<usize>().ok())
+                    .unwrap_or(0);
+                format!("{:0width$}", seq, width = width)
+            }
+        })
+        .into_owned()
+}
+
+/// Kunci periode counter sesuai aturan reset
+fn counter_period(invoice_reset: &str, date: NaiveDate) -> String {
+    match invoice_reset {
+        "yearly" => format!("{:04}", date.year()),
+        "monthly" => format!("{:04}-{:02}", date.year(), date.month()),
+        _ => "all".to_string(),
+    }
+}
+
+/// Ambil nomor invoice berikutnya di dalam transaksi sale.
+/// Counter ikut di-rollback jika sale gagal, jadi nomor tidak pernah loncat,
+/// dan dua transaksi yang menaikkan counter yang sama akan bentrok (tidak dobel).
+/// Nomor yang sudah dipakai sale lain (misalnya setelah format/reset diganti) dilewati
+pub async fn next_invoice_number(
+    setting: &StoreSetting,
+    user_id: ObjectId,
+    date: DateTime<Utc>,
+    db: &Database,
+    session: &mut ClientSession,
+) -> Result<String, ServiceError> {
+    let collection: Collection<Document> = db.collection("counters");
+    let sales: Collection<Document> = db.collection("sales");
+    let date = setting.business_date(date);
+    let period = counter_period(&setting.invoice_reset, date);
+    let counter_id = format!("invoice:{}:{}", user_id.to_hex(), period);
+
+    for _ in 0..MAX_INVOICE_SKIPS {
+        let counter = collection
+            .find_one_and_update(
+                doc! { "_id": &counter_id },
+                doc! {
+                    "$inc": { "seq": 1_i64 },
+                    "$setOnInsert": { "user_id": user_id, "period": &period },
+                },
+            )
+            .upsert(true)
+            .return_document(ReturnDocument::After)
+            .session(&mut *session)
+            .await
+            .map_err(transaction_error)?
+            .ok_or_else(|| ServiceError::DatabaseError("Counter invoice gagal dibuat".into()))?;
+
+        let seq = counter
+            .get_i64("seq")
+            .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+        let invoice_number = render_invoice_number(&setting.invoice_format, date, seq);
+
+        let used = sales
+            .find_one(doc! { "user_id": user_id, "invoice_number": &invoice_number })
+            .session(&mut *session)
+            .await
+            .map_err(transaction_error)?;
+        if used.is_none() {
+            return Ok(invoice_number);
+        }
+    }
+
+    Err(ServiceError::Conflict(
+        "Nomor invoice berikutnya sudah terpakai, periksa format invoice toko".into(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn render_invoice_number_fills_tokens_and_padding() {
+        assert_eq!(
+            render_invoice_number("INV/{YYYY}/{MM}/{seq:05}", date(2025, 6, 1), 42),
+            "INV/2025/06/00042"
+        );
+        assert_eq!(
+            render_invoice_number("{YY}{MM}{DD}-{seq}", date(2025, 1, 9), 7),
+            "250109-7"
+        );
+    }
+
+    #[test]
+    fn render_invoice_number_keeps_seq_wider_than_padding() {
+        assert_eq!(
+            render_invoice_number("INV-{seq:3}", date(2025, 6, 1), 12345),
+            "INV-12345"
+        );
+    }
+
+    #[test]
+    fn validate_invoice_format_requires_period_tokens_for_reset() {
+        assert!(validate_invoice_format("INV/{seq}", "never").is_ok());
+        assert!(validate_invoice_format("INV/{YYYY}", "never").is_err());
+        assert!(validate_invoice_format("INV/{seq}", "yearly").is_err());
+        assert!(validate_invoice_format("INV/{YYYY}/{seq}", "yearly").is_ok());
+        assert!(validate_invoice_format("INV/{YYYY}/{seq}", "monthly").is_err());
+        assert!(validate_invoice_format("INV/{MM}/{seq}", "monthly").is_err());
+        assert!(validate_invoice_format("INV/{YYYY}/{MM}/{seq:05}", "monthly").is_ok());
+    }
+
+    #[test]
+    fn counter_period_follows_reset_mode() {
+        assert_eq!(counter_period("never", date(2025, 6, 1)), "all");
+        assert_eq!(counter_period("yearly", date(2025, 6, 1)), "2025");
+        assert_eq!(counter_period("monthly", date(2025, 6, 1)), "2025-06");
+    }
+}
@@ -0,0 +1,619 @@
+use crate::errors::ServiceError;
+use crate::models::customer::Customer;
+use crate::models::loyalty::{PointHistoryQuery, PointTransaction};
+use crate::models::money::Money;
+use crate::models::pagination::{Pagination, page_and_limit};
+use crate::models::payment_method::PaymentMethod;
+use crate::models::sale::{Sale, SaleTender};
+use crate::models::store_setting::StoreSetting;
+use crate::services::customer_service::find_customer;
+use crate::utils::{string_id_to_obj_id, transaction_error};
+use bson::datetime::DateTime as BsonDateTime;
+use bson::oid::ObjectId;
+use chrono::{Duration, Utc};
+use futures::stream::TryStreamExt;
+use mongodb::{ClientSession, Collection, Database, bson::doc, options::ReturnDocument};
+
+/// Nama metode pembayaran untuk tender penukaran poin (tidak ada di payment_methods)
+pub const POINTS_PAYMENT_NAME: &str = "Poin Loyalti";
+
+/// Saldo dan riwayat poin pelanggan. Hanya membaca: poin yang sudah lewat masa berlaku
+/// dikurangkan dari saldo yang ditampilkan, pencatatan "expire" terjadi di transaksi poin berikutnya
+pub async fn get_customer_points_service(
+    customer_id: &str,
+    query: PointHistoryQuery,
+    db: &Database,
+    user_id: &str,
+) -> Result<(Customer, Vec<PointTransaction>, Pagination), ServiceError> {
+    let user_id = match string_id_to_obj_id(user_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let customer_id = match string_id_to_obj_id(customer_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let mut customer = find_customer(customer_id, user_id, db).await?;
+    customer.loyalty_points -= expired_points(customer_id, user_id, db).await?;
+
+    let filter = doc! { "user_id": user_id, "customer_id": customer_id };
+    let (page, limit) = page_and_limit(query.page, query.limit);
+    let collection: Collection<PointTransaction> = db.collection("point_transactions");
+
+    let total = collection
+        .count_documents(filter.clone())
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+
+    let mut cursor = collection
+        .find(filter)
+        .sort(doc! { "created_at": -1, "_id": -1 })
+        .skip((page - 1) * limit)
+        .limit(limit as i64)
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+
+    let mut transactions: Vec<PointTransaction> = Vec::new();
+
+    while let Some(transaction) = cursor
+        .try_next()
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?
+    {
+        transactions.push(transaction);
+    }
+
+    Ok((customer, transactions, Pagination::new(page, limit, total)))
+}
+
+/// Poin yang didapat dari nominal belanja (dibulatkan ke bawah)
+pub fn points_for_amount(setting: &StoreSetting, amount: Money) -> i64 {
+    if !setting.loyalty_enabled
+        || !setting.loyalty_earn_amount.is_positive()
+        || !amount.is_positive()
+    {
+        return 0;
+    }
+    amount.minor() / setting.loyalty_earn_amount.minor()
+}
+
+/// Poin untuk uang yang sudah diterima dari sale, di luar bagian yang dibayar dengan poin.
+/// Sale kredit mendapat poin sebagian demi sebagian saat cicilannya masuk
+pub fn points_for_paid_amount(
+    setting: &StoreSetting,
+    paid_amount: Money,
+    points_redeemed: i64,
+) -> i64 {
+    // Nilai poin di atas batas nominal berarti seluruh pembayaran dari poin
+    let redeemed_value = setting
+        .loyalty_point_value
+        .checked_mul(points_redeemed)
+        .unwrap_or(Money::MAX_AMOUNT);
+    points_for_amount(setting, paid_amount - redeemed_value)
+}
+
+/// Tender non-tunai senilai poin yang ditukar, jadi tidak bisa menghasilkan kembalian
+pub fn points_tender(setting: &StoreSetting, points: i64) -> Result<SaleTender, ServiceError> {
+    let amount = setting
+        .loyalty_point_value
+        .checked_mul(points)
+        .ok_or_else(|| ServiceError::BadRequest("Nilai poin yang ditukar terlalu besar".into()))?;
+
+    Ok(SaleTender {
+        payment_method: PaymentMethod {
+            id: None,
+            name: POINTS_PAYMENT_NAME.to_string(),
+            is_active: true,
+            is_cash: false,
+        },
+        amount,
+        change_amount: Money::ZERO,
+        shift_id: None,
+        payment_id: None,
+    })
+}
+
+/// Catat penukaran dan perolehan poin sale baru (dipanggil di dalam transaksi sale).
+/// Poin ditukar dulu supaya poin dari sale ini tidak bisa dipakai untuk membayar sale ini
+pub async fn record_sale_points(
+    sale: &Sale,
+    setting: &StoreSetting,
+    db: &Database,
+    session: &mut ClientSession,
+) -> Result<(), ServiceError> {
+    let Some(customer_id) = sale.customer_id else {
+        return Ok(());
+    };
+    if sale.points_redeemed > 0 {
+        expire_points(customer_id, sale.user_id, db, session).await?;
+
+        let consumed =
+            consume_lots(customer_id, sale.user_id, sale.points_redeemed, db, session).await?;
+        if consumed < sale.points_redeemed {
+            return Err(ServiceError::BadRequest(format!(
+                "Poin pelanggan tidak mencukupi (tersedia {} poin)",
+                consumed
+            )));
+        }
+
+        let balance_after = add_balance(
+            customer_id,
+            sale.user_id,
+            -sale.points_redeemed,
+            db,
+            session,
+        )
+        .await?;
+        insert_transaction(
+            new_transaction(
+                sale,
+                customer_id,
+                "redeem",
+                -sale.points_redeemed,
+                balance_after,
+            ),
+            db,
+            session,
+        )
+        .await?;
+    }
+
+    if sale.points_earned > 0 {
+        let balance_after =
+            add_balance(customer_id, sale.user_id, sale.points_earned, db, session).await?;
+        insert_transaction(
+            PointTransaction {
+                remaining_points: sale.points_earned,
+                expires_at: expiry_from_now(setting),
+                ..new_transaction(sale, customer_id, "earn", sale.points_earned, balance_after)
+            },
+            db,
+            session,
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Tambah poin sale kredit setelah cicilan diterima (dipanggil di dalam transaksi pembayaran).
+/// `sale` sudah berisi `paid_amount` terbaru, mengembalikan `points_earned` yang baru
+pub async fn record_payment_points(
+    sale: &Sale,
+    setting: &StoreSetting,
+    db: &Database,
+    session: &mut ClientSession,
+) -> Result<i64, ServiceError> {
+    let Some(customer_id) = sale.customer_id else {
+        return Ok(sale.points_earned);
+    };
+    let sale_id = sale.id.expect("Sale.id harus ada");
+
+    let points_earned = points_for_paid_amount(setting, sale.paid_amount, sale.points_redeemed);
+    let points = points_earned - sale.points_earned;
+    if points <= 0 {
+        return Ok(sale.points_earned);
+    }
+
+    let sale_collection: Collection<Sale> = db.collection("sales");
+    sale_collection
+        .update_one(
+            doc! { "_id": sale_id, "user_id": sale.user_id },
+            doc! { "$inc": { "points_earned": points } },
+        )
+        .session(&mut *session)
+        .await
+        .map_err(transaction_error)?;
+
+    let balance_after = add_balance(customer_id, sale.user_id, points, db, session).await?;
+    insert_transaction(
+        PointTransaction {
+            remaining_points: points,
+            expires_at: expiry_from_now(setting),
+            ..new_transaction(sale, customer_id, "earn", points, balance_after)
+        },
+        db,
+        session,
+    )
+    .await?;
+
+    Ok(points_earned)
+}
+
+/// Batalkan poin sale yang di-void (dipanggil di dalam transaksi void): poin yang didapat
+/// ditarik kembali, poin yang ditukar dikembalikan sebagai lot baru. Poin hasil sale ini yang
+/// sudah terlanjur ditukar hanya ditarik sebatas saldo yang ada, saldo tidak pernah minus
+pub async fn reverse_sale_points(
+    sale: &Sale,
+    setting: &StoreSetting,
+    db: &Database,
+    session: &mut ClientSession,
+) -> Result<(), ServiceError> {
+    let Some(customer_id) = sale.customer_id else {
+        return Ok(());
+    };
+    if sale.points_earned <= 0 && sale.points_redeemed <= 0 {
+        return Ok(());
+    }
+    let sale_id = sale.id.expect("Sale.id harus ada");
+
+    expire_points(customer_id, sale.user_id, db, session).await?;
+
+    if sale.points_earned > 0 {
+        let collection: Collection<PointTransaction> = db.collection("point_transactions");
+
+        // Sisa lot dari sale ini (sale kredit bisa punya beberapa lot dari cicilan)
+        // ditarik dulu, kekurangannya diambil dari lot lain
+        let earn_filter = doc! {
+            "sale_id": sale_id,
+            "transaction_type": "earn",
+            "remaining_points": { "$gt": 0_i64 },
+        };
+        let mut cursor = collection
+            .find(earn_filter.clone())
+            .session(&mut *session)
+            .await
+            .map_err(transaction_error)?;
+
+        let mut from_lot = 0;
+        while let Some(lot) = cursor
+            .next(&mut *session)
+            .await
+            .transpose()
+            .map_err(transaction_error)?
+        {
+            from_lot += lot.remaining_points;
+        }
+
+        collection
+            .update_many(earn_filter, doc! { "$set": { "remaining_points": 0_i64 } })
+            .session(&mut *session)
+            .await
+            .map_err(transaction_error)?;
+
+        let from_other_lots = consume_lots(
+            customer_id,
+            sale.user_id,
+            sale.points_earned - from_lot,
+            db,
+            session,
+        )
+        .await?;
+
+        let reversed = from_lot + from_other_lots;
+        if reversed > 0 {
+            let balance_after =
+                add_balance(customer_id, sale.user_id, -reversed, db, session).await?;
+            insert_transaction(
+                new_transaction(sale, customer_id, "void_earn", -reversed, balance_after),
+                db,
+                session,
+            )
+            .await?;
+        }
+    }
+
+    if sale.points_redeemed > 0 {
+        let balance_after =
+            add_balance(customer_id, sale.user_id, sale.points_redeemed, db, session).await?;
+        insert_transaction(
+            PointTransaction {
+                remaining_points: sale.points_redeemed,
+                expires_at: expiry_from_now(setting),
+                ..new_transaction(
+                    sale,
+                    customer_id,
+                    "void_redeem",
+                    sale.points_redeemed,
+                    balance_after,
+                )
+            },
+            db,
+            session,
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Jumlah sisa poin di lot yang sudah lewat masa berlaku tapi belum dihanguskan
+async fn expired_points(
+    customer_id: ObjectId,
+    user_id: ObjectId,
+    db: &Database,
+) -> Result<i64, ServiceError> {
+    let now = BsonDateTime::from_chrono(Utc::now());
+    let collection: Collection<PointTransaction> = db.collection("point_transactions");
+
+    let mut cursor = collection
+        .find(doc! {
+            "user_id": user_id,
+            "customer_id": customer_id,
+            "remaining_points": { "$gt": 0_i64 },
+            "expires_at": { "$lte": now },
+        })
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+
+    let mut expired = 0;
+    while let Some(lot) = cursor
+        .try_next()
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?
+    {
+        expired += lot.remaining_points;
+    }
+
+    Ok(expired)
+}
+
+/// Hanguskan sisa poin dari lot yang sudah lewat masa berlaku
+async fn expire_points(
+    customer_id: ObjectId,
+    user_id: ObjectId,
+    db: &Database,
+    session: &mut ClientSession,
+) -> Result<(), ServiceError> {
+    let now = BsonDateTime::from_chrono(Utc::now());
+    let collection: Collection<PointTransaction> = db.collection("point_transactions");
+
+    let mut cursor = collection
+        .find(doc! {
+            "user_id": user_id,
+            "customer_id": customer_id,
+            "remaining_points": { "$gt": 0_i64 },
+            "expires_at": { "$lte": now },
+        })
+        .session(&mut *session)
+        .await
+        .map_err(transaction_error)?;
+
+    let mut expired_ids: Vec<ObjectId> = Vec::new();
+    let mut expired_points = 0;
+
+    while let Some(lot) = cursor
+        .next(&mut *session)
+        .await
+        .transpose()
+        .map_err(transaction_error)?
+    {
+        expired_ids.extend(lot.id);
+        expired_points += lot.remaining_points;
+    }
+
+    if expired_points == 0 {
+        return Ok(());
+    }
+
+    collection
+        .update_many(
+            doc! { "_id": { "$in": &expired_ids } },
+            doc! { "$set": { "remaining_points": 0_i64 } },
+        )
+        .session(&mut *session)
+        .await
+        .map_err(transaction_error)?;
+
+    let balance_after = add_balance(customer_id, user_id, -expired_points, db, session).await?;
+    insert_transaction(
+        PointTransaction {
+            id: None,
+            user_id,
+            customer_id,
+            sale_id: None,
+            transaction_type: "expire".to_string(),
+            points: -expired_points,
+            balance_after,
+            remaining_points: 0,
+            expires_at: None,
+            created_at: now,
+        },
+        db,
+        session,
+    )
+    .await
+}
+
+/// Kurangi sisa lot yang masih berlaku, yang paling cepat kedaluwarsa lebih dulu.
+/// Mengembalikan jumlah poin yang berhasil diambil (bisa kurang dari `points`)
+async fn consume_lots(
+    customer_id: ObjectId,
+    user_id: ObjectId,
+    points: i64,
+    db: &Database,
+    session: &mut ClientSession,
+) -> Result<i64, ServiceError> {
+    if points <= 0 {
+        return Ok(0);
+    }
+
+    let collection: Collection<PointTransaction> = db.collection("point_transactions");
+
+    let mut cursor = collection
+        .find(doc! {
+            "user_id": user_id,
+            "customer_id": customer_id,
+            "remaining_points": { "$gt": 0_i64 },
+        })
+        .session(&mut *session)
+        .await
+        .map_err(transaction_error)?;
+
+    let mut lots: Vec<PointTransaction> = Vec::new();
+    while let Some(lot) = cursor
+        .next(&mut *session)
+        .await
+        .transpose()
+        .map_err(transaction_error)?
+    {
+        lots.push(lot);
+    }
+
+    // Lot tanpa masa berlaku dipakai paling akhir
+    lots.sort_by_key(|lot| (lot.expires_at.is_none(), lot.expires_at, lot.created_at));
+
+    let mut consumed = 0;
+    for lot in lots {
+        if consumed == points {
+            break;
+        }
+        let take = lot.remaining_points.min(points - consumed);
+
+        collection
+            .update_one(
+                doc! { "_id": lot.id, "remaining_points": { "$gte": take } },
+                doc! { "$inc": { "remaining_points": -take } },
+            )
+            .session(&mut *session)
+            .await
+            .map_err(transaction_error)?;
+
+        consumed += take;
+    }
+
+    Ok(consumed)
+}
+
+/// Ubah saldo poin pelanggan, mengembalikan saldo setelah diubah
+async fn add_balance(
+    customer_id: ObjectId,
+    user_id: ObjectId,
+    points: i64,
+    db: &Database,
+    session: &mut ClientSession,
+) -> Result<i64, ServiceError> {
+    let collection: Collection<Customer> = db.collection("customers");
+
+    let customer = collection
+        .find_one_and_update(
+            doc! { "_id": customer_id, "user_id": user_id },
+            doc! { "$inc": { "loyalty_points": points } },
+        )
+        .return_document(ReturnDocument::After)
+        .session(&mut *session)
+        .await
+        .map_err(transaction_error)?
+        .ok_or_else(|| {
+            ServiceError::NotFound(format!(
+                "Pelanggan dengan ID '{}' tidak ditemukan",
+                customer_id
+            ))
+        })?;
+
+    Ok(customer.loyalty_points)
+}
+
+async fn insert_transaction(
+    transaction: PointTransaction,
+    db: &Database,
+    session: &mut ClientSession,
+) -> Result<(), ServiceError> {
+    let collection: Collection<PointTransaction> = db.collection("point_transactions");
+
+    collection
+        .insert_one(&transaction)
+        .session(&mut *session)
+        .await
+        .map_err(transaction_error)?;
+
+    Ok(())
+}
+
+fn new_transaction(
+    sale: &Sale,
+    customer_id: ObjectId,
+    transaction_type: &str,
+    points: i64,
+    balance_after: i64,
+) -> PointTransaction {
+    PointTransaction {
+        id: None,
+        user_id: sale.user_id,
+        customer_id,
+        sale_id: sale.id,
+        transaction_type: transaction_type.to_string(),
+        points,
+        balance_after,
+        remaining_points: 0,
+        expires_at: None,
+        created_at: BsonDateTime::from_chrono(Utc::now()),
+    }
+}
+
+fn expiry_from_now(setting: &StoreSetting) -> Option<BsonDateTime> {
+    match setting.loyalty_expiry_days {
+        0 => None,
+        days => Some(BsonDateTime::from_chrono(
+            Utc::now() + Duration::days(days as i64),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setting() -> StoreSetting {
+        StoreSetting {
+            loyalty_enabled: true,
+            loyalty_earn_amount: Money::from_rupiah(10_000),
+            loyalty_point_value: Money::from_rupiah(100),
+            ..StoreSetting::default_for(ObjectId::new())
+        }
+    }
+
+    #[test]
+    fn points_for_paid_amount_only_counts_money_received() {
+        let setting = setting();
+        assert_eq!(points_for_paid_amount(&setting, Money::ZERO, 0), 0);
+        assert_eq!(
+            points_for_paid_amount(&setting, Money::from_rupiah(25_000), 0),
+            2
+        );
+        assert_eq!(
+            points_for_paid_amount(&setting, Money::from_rupiah(50_000), 0),
+            5
+        );
+    }
+
+    #[test]
+    fn points_for_paid_amount_excludes_redeemed_points() {
+        let setting = setting();
+        // 50 poin x Rp 100 = Rp 5.000 dibayar dengan poin
+        assert_eq!(
+            points_for_paid_amount(&setting, Money::from_rupiah(25_000), 50),
+            2
+        );
+        assert_eq!(
+            points_for_paid_amount(&setting, Money::from_rupiah(5_000), 50),
+            0
+        );
+    }
+
+    #[test]
+    fn points_for_paid_amount_is_zero_when_loyalty_disabled() {
+        let setting = StoreSetting {
+            loyalty_enabled: false,
+            ..setting()
+        };
+        assert_eq!(
+            points_for_paid_amount(&setting, Money::from_rupiah(50_000), 0),
+            0
+        );
+    }
+
+    #[test]
+    fn points_tender_rejects_values_that_overflow() {
+        let setting = setting();
+        let tender = points_tender(&setting, 50).unwrap();
+        assert_eq!(tender.amount, Money::from_rupiah(5_000));
+        assert!(points_tender(&setting, i64::MAX / 2).is_err());
+        assert_eq!(
+            points_for_paid_amount(&setting, Money::from_rupiah(50_000), i64::MAX / 2),
+            0
+        );
+    }
+}
@@ -1,4 +1,21 @@
 pub mod auth_service;
+pub mod customer_service;
+pub mod daily_closing_service;
+pub mod invoice_pdf_service;
+pub mod idempotency_service;
+pub mod invoice_service;
+pub mod loyalty_service;
+pub mod payment_method_service;
 pub mod product_service;
+pub mod promotion_service;
+pub mod receipt_service;
+pub mod receivable_service;
+pub mod store_setting_service;
 pub mod user_service;
+pub mod voucher_service;
+pub mod sale_draft_service;
 pub mod sale_service;
+pub mod sale_payment_service;
+pub mod sale_return_service;
+pub mod sale_sync_service;
+pub mod shift_service;
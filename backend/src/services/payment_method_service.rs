@@ -0,0 +1,118 @@
+use crate::errors::ServiceError;
+use crate::models::payment_method::{PaymentMethod, PaymentMethodDTO, UpdatePaymentMethodDTO};
+use crate::utils::string_id_to_obj_id;
+use bson::oid::ObjectId;
+use futures::stream::TryStreamExt;
+use mongodb::{Collection, Database, bson::doc, options::ReturnDocument};
+
+/// Semua metode pembayaran, urut nama
+pub async fn get_payment_methods_service(
+    db: &Database,
+) -> Result<Vec<PaymentMethod>, ServiceError> {
+    let collection: Collection<PaymentMethod> = db.collection("payment_methods");
+
+    collection
+        .find(doc! {})
+        .sort(doc! { "name": 1 })
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?
+        .try_collect()
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))
+}
+
+pub async fn create_payment_method_service(
+    payload: PaymentMethodDTO,
+    db: &Database,
+) -> Result<PaymentMethod, ServiceError> {
+    let collection: Collection<PaymentMethod> = db.collection("payment_methods");
+
+    let mut method = PaymentMethod {
+        id: None,
+        name: payload.name.trim().to_string(),
+        is_active: payload.is_active,
+        is_cash: payload.is_cash,
+    };
+
+    let result = collection
+        .insert_one(&method)
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+    method.id = result.inserted_id.as_object_id();
+
+    Ok(method)
+}
+
+/// Ubah nama, status aktif atau penanda tunai. Sale lama tetap menyimpan salinan metodenya
+pub async fn update_payment_method_service(
+    payment_method_id: &str,
+    payload: UpdatePaymentMethodDTO,
+    db: &Database,
+) -> Result<PaymentMethod, ServiceError> {
+    let payment_method_id = match string_id_to_obj_id(payment_method_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let mut update_doc = doc! {};
+
+    if let Some(name) = payload.name {
+        update_doc.insert("name", name.trim());
+    }
+    if let Some(is_active) = payload.is_active {
+        update_doc.insert("is_active", is_active);
+    }
+    if let Some(is_cash) = payload.is_cash {
+        update_doc.insert("is_cash", is_cash);
+    }
+
+    if update_doc.is_empty() {
+        return Err(ServiceError::BadRequest(
+            "Tidak ada data untuk di-update".to_string(),
+        ));
+    }
+
+    let collection: Collection<PaymentMethod> = db.collection("payment_methods");
+
+    collection
+        .find_one_and_update(
+            doc! { "_id": payment_method_id },
+            doc! { "$set": update_doc },
+        )
+        .return_document(ReturnDocument::After)
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?
+        .ok_or_else(|| {
+            ServiceError::NotFound(format!(
+                "Metode Pembayaran dengan ID '{}' tidak ditemukan",
+                payment_method_id
+            ))
+        })
+}
+
+/// Ambil metode pembayaran dan pastikan statusnya aktif
+pub async fn get_active_payment_method_service(
+    payment_method_id: &ObjectId,
+    db: &Database,
+) -> Result<PaymentMethod, ServiceError> {
+    let collection: Collection<PaymentMethod> = db.collection("payment_methods");
+
+    let found_method = collection
+        .find_one(doc! { "_id": payment_method_id })
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?
+        .ok_or_else(|| {
+            ServiceError::NotFound(format!(
+                "Metode Pembayaran dengan ID '{}' tidak ditemukan",
+                payment_method_id
+            ))
+        })?;
+
+    if !found_method.is_active {
+        return Err(ServiceError::BadRequest(
+            "Metode Pembayaran sedang tidak aktif".to_string(),
+        ));
+    }
+
+    Ok(found_method)
+}
@@ -1,12 +1,26 @@
 use crate::errors::ServiceError;
-use crate::models::product::{Product, ProductDTO, UpdateProductDTO};
-use crate::utils::{generate_random_sku, handle_duplicate_key_error, string_id_to_obj_id};
+use crate::models::product::{
+    DeletedProductResponse, Product, ProductChangesQuery, ProductChangesResponse, ProductDTO,
+    ProductResponse, ProductTombstone, UpdateProductDTO,
+};
+use crate::utils::{
+    finish_transaction, generate_random_sku, handle_duplicate_key_error, start_transaction,
+    string_id_to_obj_id, transaction_error,
+};
 use bson::datetime::DateTime as BsonDateTime;
 
 use bson::oid::ObjectId;
 use chrono::Utc;
 use futures::stream::TryStreamExt;
-use mongodb::{Collection, Database, bson::doc};
+use serde::de::DeserializeOwned;
+use mongodb::{
+    ClientSession, Collection, Database,
+    bson::{Document, doc},
+    options::ReturnDocument,
+};
+
+const DEFAULT_CHANGES_LIMIT: i64 = 500;
+const MAX_CHANGES_LIMIT: i64 = 1000;
 
 pub async fn get_products_service(db: &Database, id: &str) -> Result<Vec<Product>, ServiceError> {
     let user_id = match string_id_to_obj_id(id) {
@@ -89,41 +103,46 @@ pub async fn create_product_service(
         _ => generate_random_sku(),
     };
 
+    let price = payload.price().ok_or_else(|| {
+        ServiceError::BadRequest("Kolom price_minor (sen) atau price (rupiah) wajib diisi".into())
+    })?;
+
     let now = BsonDateTime::from_chrono(Utc::now());
 
     // Buat produk baru (sementara id None dulu)
     let mut product = Product {
         id: None,
-        user_id: user_id,
+        user_id,
         name: payload.name,
         sku: final_sku,
-        price: payload.price,
+        price,
         stock: payload.stock,
         category_id: payload
             .category_id
             .and_then(|id| ObjectId::parse_str(&id).ok()),
+        tax_rate: payload.tax_rate,
+        tax_exempt: payload.tax_exempt,
+        change_seq: 0,
         created_at: Some(now),
         updated_at: Some(now),
     };
 
-    // Insert ke database
-    let result = collection.insert_one(&product).await;
-
-    match result {
-        Ok(insert_result) => {
-            product.id = insert_result
-                .inserted_id
-                .as_object_id()
-                .map(|oid| oid.to_owned());
-            Ok(product)
-        }
-        Err(e) => {
-            if let Some(err) = handle_duplicate_key_error(&e) {
-                return Err(err);
-            }
-            Err(ServiceError::DatabaseError(e.to_string()))
-        }
+    // Nomor urut katalog dan insert dalam satu transaksi supaya urutan nomor sama dengan
+    // urutan commit, klien delta sync tidak melewatkan perubahan
+    let mut session = start_transaction(db).await?;
+    let result = async {
+        product.change_seq = next_catalog_seq(user_id, db, &mut session).await?;
+        collection
+            .insert_one(&product)
+            .session(&mut session)
+            .await
+            .map_err(|e| handle_duplicate_key_error(&e).unwrap_or_else(|| transaction_error(e)))
     }
+    .await;
+    let insert_result = finish_transaction(&mut session, result).await?;
+
+    product.id = insert_result.inserted_id.as_object_id();
+    Ok(product)
 }
 
 pub async fn update_product_service(
@@ -142,6 +161,7 @@ pub async fn update_product_service(
         None => return Err(ServiceError::InvalidId("Invalid user ID".into())),
     };
 
+    let price = payload.price();
     let mut update_doc = doc! {};
 
     if let Some(name) = payload.name {
@@ -150,7 +170,7 @@ pub async fn update_product_service(
     if let Some(sku) = payload.sku {
         update_doc.insert("sku", sku);
     }
-    if let Some(price) = payload.price {
+    if let Some(price) = price {
         update_doc.insert("price", price);
     }
     if let Some(stock) = payload.stock {
@@ -159,6 +179,12 @@ pub async fn update_product_service(
     if let Some(category_id) = payload.category_id {
         update_doc.insert("category_id", category_id);
     }
+    if let Some(tax_rate) = payload.tax_rate {
+        update_doc.insert("tax_rate", tax_rate);
+    }
+    if let Some(tax_exempt) = payload.tax_exempt {
+        update_doc.insert("tax_exempt", tax_exempt);
+    }
 
     if update_doc.is_empty() {
         return Err(ServiceError::BadRequest(
@@ -176,15 +202,25 @@ pub async fn update_product_service(
         "user_id": user_id,
     };
 
-    let update_result = collection
-        .update_one(filter.clone(), doc! { "$set": update_doc })
-        .await
-        .map_err(|err| {
-            if let Some(conflict_error) = handle_duplicate_key_error(&err) {
-                return conflict_error;
-            }
-            ServiceError::DatabaseError(err.to_string())
-        })?;
+    let mut session = start_transaction(db).await?;
+    let result = async {
+        update_doc.insert(
+            "change_seq",
+            next_catalog_seq(user_id, db, &mut session).await?,
+        );
+        collection
+            .update_one(filter.clone(), doc! { "$set": update_doc })
+            .session(&mut session)
+            .await
+            .map_err(|err| {
+                if let Some(conflict_error) = handle_duplicate_key_error(&err) {
+                    return conflict_error;
+                }
+                transaction_error(err)
+            })
+    }
+    .await;
+    let update_result = finish_transaction(&mut session, result).await?;
 
     if update_result.matched_count == 0 {
         return Err(ServiceError::NotFound(
@@ -223,14 +259,156 @@ pub async fn delete_product_service(
         "user_id": user_id,
     };
 
-    let result = collection
-        .delete_one(filter)
+    // Hapus produk dan catat tombstone-nya bersamaan untuk klien delta sync
+    let mut session = start_transaction(db).await?;
+    let result = async {
+        let result = collection
+            .delete_one(filter)
+            .session(&mut session)
+            .await
+            .map_err(transaction_error)?;
+
+        if result.deleted_count == 0 {
+            return Err(ServiceError::NotFound("Product tidak ditemukan!".into()));
+        }
+
+        let tombstone = ProductTombstone {
+            id: None,
+            user_id,
+            product_id,
+            change_seq: next_catalog_seq(user_id, db, &mut session).await?,
+            deleted_at: BsonDateTime::from_chrono(Utc::now()),
+        };
+
+        let tombstones: Collection<ProductTombstone> = db.collection("product_tombstones");
+        tombstones
+            .insert_one(&tombstone)
+            .session(&mut session)
+            .await
+            .map_err(transaction_error)?;
+
+        Ok(true)
+    }
+    .await;
+    finish_transaction(&mut session, result).await
+}
+
+/// Produk yang dibuat/diubah dan produk yang dihapus setelah token `since`, urut sesuai
+/// nomor perubahan. `next_token` dipakai sebagai `since` berikutnya sampai `has_more` false
+pub async fn get_product_changes_service(
+    db: &Database,
+    user_id: &str,
+    query: ProductChangesQuery,
+) -> Result<ProductChangesResponse, ServiceError> {
+    let user_id = match string_id_to_obj_id(user_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let since = query.since.unwrap_or(0).max(0);
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_CHANGES_LIMIT)
+        .clamp(1, MAX_CHANGES_LIMIT);
+    let filter = doc! { "user_id": user_id, "change_seq": { "$gt": since } };
+
+    // Ambil satu lebih banyak dari limit untuk tahu masih ada halaman berikutnya
+    let products: Collection<Product> = db.collection("products");
+    let tombstones: Collection<ProductTombstone> = db.collection("product_tombstones");
+    let mut changed = find_changes(&products, filter.clone(), Some(limit + 1)).await?;
+    let mut deleted = find_changes(&tombstones, filter, Some(limit + 1)).await?;
+
+    // Gabungkan kedua daftar lalu potong di `limit` perubahan pertama
+    let mut seqs: Vec<i64> = changed
+        .iter()
+        .map(|p| p.change_seq)
+        .chain(deleted.iter().map(|t| t.change_seq))
+        .collect();
+    seqs.sort_unstable();
+
+    let has_more = seqs.len() > limit as usize;
+    let next_token = match seqs.get(limit as usize - 1).or(seqs.last()) {
+        Some(seq) => *seq,
+        None => since,
+    };
+
+    changed.retain(|p| p.change_seq < next_token);
+    deleted.retain(|t| t.change_seq < next_token);
+
+    // Satu transaksi (misalnya sale) bisa mengubah banyak produk dengan nomor yang sama,
+    // nomor terakhir diambil utuh supaya tidak ada produk yang terlewat di halaman berikutnya
+    if next_token > since {
+        let last_seq = doc! { "user_id": user_id, "change_seq": next_token };
+        changed.extend(find_changes(&products, last_seq.clone(), None).await?);
+        deleted.extend(find_changes(&tombstones, last_seq, None).await?);
+    }
+
+    Ok(ProductChangesResponse {
+        products: changed.into_iter().map(ProductResponse::from).collect(),
+        deleted: deleted
+            .into_iter()
+            .map(DeletedProductResponse::from)
+            .collect(),
+        next_token,
+        has_more,
+    })
+}
+
+async fn find_changes<T: DeserializeOwned + Send + Sync>(
+    collection: &Collection<T>,
+    filter: Document,
+    limit: Option<i64>,
+) -> Result<Vec<T>, ServiceError> {
+    let mut cursor = collection
+        .find(filter)
+        .sort(doc! { "change_seq": 1, "_id": 1 })
+        .limit(limit.unwrap_or(0))
         .await
-        .map_err(|err| ServiceError::DatabaseError(err.to_string()))?;
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
 
-    if result.deleted_count == 0 {
-        return Err(ServiceError::NotFound("Product tidak ditemukan!".into()));
+    let mut documents: Vec<T> = Vec::new();
+    while let Some(document) = cursor
+        .try_next()
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?
+    {
+        documents.push(document);
     }
 
-    Ok(true)
+    Ok(documents)
+}
+
+/// Nomor urut perubahan katalog berikutnya untuk toko ini. Harus dipanggil di dalam transaksi
+/// yang sama dengan perubahan produknya: counter yang sedang dipakai transaksi lain membuat
+/// transaksi ini bentrok, jadi nomor yang lebih kecil selalu ter-commit lebih dulu.
+/// Cukup sekali per transaksi, semua produk yang diubah transaksi itu memakai nomor yang sama
+pub async fn next_catalog_seq(
+    user_id: ObjectId,
+    db: &Database,
+    session: &mut ClientSession,
+) -> Result<i64, ServiceError> {
+    let collection: Collection<Document> = db.collection("counters");
+
+    let counter = collection
+        .find_one_and_update(
+            doc! { "_id": catalog_counter_id(user_id) },
+            doc! {
+                "$inc": { "seq": 1_i64 },
+                "$setOnInsert": { "user_id": user_id },
+            },
+        )
+        .upsert(true)
+        .return_document(ReturnDocument::After)
+        .session(&mut *session)
+        .await
+        .map_err(transaction_error)?
+        .ok_or_else(|| ServiceError::DatabaseError("Counter katalog gagal dibuat".into()))?;
+
+    counter
+        .get_i64("seq")
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))
+}
+
+pub fn catalog_counter_id(user_id: ObjectId) -> String {
+    format!("catalog:{}", user_id.to_hex())
 }
@@ -0,0 +1,712 @@
+use crate::errors::ServiceError;
+use crate::models::money::Money;
+use crate::models::pagination::{Pagination, page_and_limit};
+use crate::models::product::Product;
+use crate::models::promotion::{
+    Promotion, PromotionDTO, PromotionListQuery, PromotionRule, UpdatePromotionDTO,
+};
+use crate::models::sale::{SaleItem, SaleItemPromotion};
+use crate::utils::{string_id_to_obj_id, transaction_error};
+use bson::datetime::DateTime as BsonDateTime;
+use bson::oid::ObjectId;
+use chrono::{DateTime, Utc};
+use futures::stream::TryStreamExt;
+use mongodb::{ClientSession, Collection, Database, bson::doc};
+
+pub async fn get_promotions_service(
+    db: &Database,
+    user_id: &str,
+    query: PromotionListQuery,
+) -> Result<(Vec<Promotion>, Pagination), ServiceError> {
+    let user_id = match string_id_to_obj_id(user_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let mut filter = doc! { "user_id": user_id };
+    if let Some(search) = query
+        .search
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+    {
+        filter.insert(
+            "name",
+            doc! { "$regex": regex::escape(search), "$options": "i" },
+        );
+    }
+    if let Some(active) = query.active {
+        filter.insert("active", active);
+    }
+
+    let (page, limit) = page_and_limit(query.page, query.limit);
+    let collection: Collection<Promotion> = db.collection("promotions");
+
+    let total = collection
+        .count_documents(filter.clone())
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+
+    let mut cursor = collection
+        .find(filter)
+        .sort(doc! { "starts_at": -1, "_id": -1 })
+        .skip((page - 1) * limit)
+        .limit(limit as i64)
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+
+    let mut promotions: Vec<Promotion> = Vec::new();
+
+    while let Some(promotion) = cursor
+        .try_next()
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?
+    {
+        promotions.push(promotion);
+    }
+
+    Ok((promotions, Pagination::new(page, limit, total)))
+}
+
+pub async fn get_promotion_service(
+    promotion_id: &str,
+    db: &Database,
+    user_id: &str,
+) -> Result<Promotion, ServiceError> {
+    let user_id = match string_id_to_obj_id(user_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let promotion_id = match string_id_to_obj_id(promotion_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    find_promotion(promotion_id, user_id, db).await
+}
+
+pub async fn create_promotion_service(
+    payload: PromotionDTO,
+    db: &Database,
+    user_id: &str,
+) -> Result<Promotion, ServiceError> {
+    let user_id = match string_id_to_obj_id(user_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let now = BsonDateTime::from_chrono(Utc::now());
+
+    let promotion = Promotion {
+        id: None,
+        user_id,
+        name: payload.name.trim().to_string(),
+        rule: payload.rule,
+        product_ids: payload.product_ids,
+        category_ids: payload.category_ids,
+        starts_at: BsonDateTime::from_chrono(payload.starts_at),
+        ends_at: BsonDateTime::from_chrono(payload.ends_at),
+        active: payload.active.unwrap_or(true),
+        created_at: Some(now),
+        updated_at: Some(now),
+    };
+    validate_promotion(&promotion, db).await?;
+
+    let collection: Collection<Promotion> = db.collection("promotions");
+    let result = collection
+        .insert_one(&promotion)
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+
+    Ok(Promotion {
+        id: result.inserted_id.as_object_id(),
+        ..promotion
+    })
+}
+
+/// Promo divalidasi ulang secara utuh karena masa berlaku dan aturan saling bergantung
+pub async fn update_promotion_service(
+    promotion_id: &str,
+    payload: UpdatePromotionDTO,
+    db: &Database,
+    user_id: &str,
+) -> Result<Promotion, ServiceError> {
+    let mut promotion = get_promotion_service(promotion_id, db, user_id).await?;
+    let mut changed = false;
+
+    if let Some(name) = payload.name {
+        promotion.name = name.trim().to_string();
+        changed = true;
+    }
+    if let Some(rule) = payload.rule {
+        promotion.rule = rule;
+        changed = true;
+    }
+    if let Some(product_ids) = payload.product_ids {
+        promotion.product_ids = product_ids;
+        changed = true;
+    }
+    if let Some(category_ids) = payload.category_ids {
+        promotion.category_ids = category_ids;
+        changed = true;
+    }
+    if let Some(starts_at) = payload.starts_at {
+        promotion.starts_at = BsonDateTime::from_chrono(starts_at);
+        changed = true;
+    }
+    if let Some(ends_at) = payload.ends_at {
+        promotion.ends_at = BsonDateTime::from_chrono(ends_at);
+        changed = true;
+    }
+    if let Some(active) = payload.active {
+        promotion.active = active;
+        changed = true;
+    }
+
+    if !changed {
+        return Err(ServiceError::BadRequest(
+            "Tidak ada data untuk di-update".to_string(),
+        ));
+    }
+
+    validate_promotion(&promotion, db).await?;
+
+    let now = BsonDateTime::from_chrono(Utc::now());
+    let rule =
+        bson::to_bson(&promotion.rule).map_err(|e| ServiceError::Unexpected(e.to_string()))?;
+    let collection: Collection<Promotion> = db.collection("promotions");
+
+    collection
+        .update_one(
+            doc! { "_id": promotion.id, "user_id": promotion.user_id },
+            doc! { "$set": {
+                "name": &promotion.name,
+                "rule": rule,
+                "product_ids": &promotion.product_ids,
+                "category_ids": &promotion.category_ids,
+                "starts_at": promotion.starts_at,
+                "ends_at": promotion.ends_at,
+                "active": promotion.active,
+                "updated_at": now,
+            } },
+        )
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+
+    Ok(Promotion {
+        updated_at: Some(now),
+        ..promotion
+    })
+}
+
+/// Sale lama tetap menyimpan nama promo di item, jadi promo aman dihapus
+pub async fn delete_promotion_service(
+    promotion_id: &str,
+    db: &Database,
+    user_id: &str,
+) -> Result<bool, ServiceError> {
+    let user_id = match string_id_to_obj_id(user_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let promotion_id = match string_id_to_obj_id(promotion_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let collection: Collection<Promotion> = db.collection("promotions");
+
+    let result = collection
+        .delete_one(doc! { "_id": promotion_id, "user_id": user_id })
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+
+    if result.deleted_count == 0 {
+        return Err(ServiceError::NotFound(format!(
+            "Promo dengan ID '{}' tidak ditemukan",
+            promotion_id
+        )));
+    }
+
+    Ok(true)
+}
+
+async fn find_promotion(
+    promotion_id: ObjectId,
+    user_id: ObjectId,
+    db: &Database,
+) -> Result<Promotion, ServiceError> {
+    let collection: Collection<Promotion> = db.collection("promotions");
+
+    collection
+        .find_one(doc! { "_id": promotion_id, "user_id": user_id })
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?
+        .ok_or_else(|| {
+            ServiceError::NotFound(format!(
+                "Promo dengan ID '{}' tidak ditemukan",
+                promotion_id
+            ))
+        })
+}
+
+async fn validate_promotion(promotion: &Promotion, db: &Database) -> Result<(), ServiceError> {
+    if promotion.ends_at <= promotion.starts_at {
+        return Err(ServiceError::BadRequest(
+            "ends_at harus setelah starts_at".into(),
+        ));
+    }
+
+    match promotion.rule {
+        PromotionRule::BuyXGetY {
+            buy_quantity,
+            get_quantity,
+        } => {
+            if buy_quantity < 1 || get_quantity < 1 {
+                return Err(ServiceError::BadRequest(
+                    "buy_quantity dan get_quantity minimal 1".into(),
+                ));
+            }
+        }
+        PromotionRule::Bundle { quantity, price } => {
+            if quantity < 2 {
+                return Err(ServiceError::BadRequest(
+                    "Jumlah produk dalam paket minimal 2".into(),
+                ));
+            }
+            if !price.is_positive() {
+                return Err(ServiceError::BadRequest(
+                    "Harga paket harus lebih dari 0".into(),
+                ));
+            }
+        }
+        PromotionRule::QuantityDiscount {
+            min_quantity,
+            percent,
+        } => {
+            if min_quantity < 1 {
+                return Err(ServiceError::BadRequest("min_quantity minimal 1".into()));
+            }
+            if !(percent > 0.0 && percent <= 100.0) {
+                return Err(ServiceError::BadRequest(
+                    "Diskon persen promo harus lebih dari 0 dan maksimal 100".into(),
+                ));
+            }
+        }
+    }
+
+    // Promo tanpa target tidak diizinkan supaya tidak berlaku ke semua produk tanpa sengaja
+    if promotion.product_ids.is_empty() && promotion.category_ids.is_empty() {
+        return Err(ServiceError::BadRequest(
+            "Pilih minimal satu produk atau kategori untuk promo".into(),
+        ));
+    }
+
+    if !promotion.product_ids.is_empty() {
+        let products: Collection<Product> = db.collection("products");
+        let found = products
+            .count_documents(doc! {
+                "_id": { "$in": &promotion.product_ids },
+                "user_id": promotion.user_id,
+            })
+            .await
+            .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+
+        let mut unique = promotion.product_ids.clone();
+        unique.sort();
+        unique.dedup();
+        if found != unique.len() as u64 {
+            return Err(ServiceError::BadRequest(
+                "Sebagian produk promo tidak ditemukan".into(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Promo aktif yang masa berlakunya mencakup `at` (tanggal sale)
+pub async fn find_active_promotions(
+    user_id: ObjectId,
+    at: DateTime<Utc>,
+    db: &Database,
+    session: &mut ClientSession,
+) -> Result<Vec<Promotion>, ServiceError> {
+    let collection: Collection<Promotion> = db.collection("promotions");
+    let at = BsonDateTime::from_chrono(at);
+
+    let mut cursor = collection
+        .find(doc! {
+            "user_id": user_id,
+            "active": true,
+            "starts_at": { "$lte": at },
+            "ends_at": { "$gte": at },
+        })
+        .session(&mut *session)
+        .await
+        .map_err(transaction_error)?;
+
+    let mut promotions: Vec<Promotion> = Vec::new();
+
+    while let Some(promotion) = cursor
+        .next(&mut *session)
+        .await
+        .transpose()
+        .map_err(transaction_error)?
+    {
+        promotions.push(promotion);
+    }
+
+    Ok(promotions)
+}
+
+/// Terapkan promo ke item sale: promo dengan potongan terbesar dipilih lebih dulu, lalu
+/// promo berikutnya dihitung dari item yang belum kena promo. Satu item paling banyak
+/// kena satu promo dan item dengan diskon manual dilewati.
+/// `categories` berisi kategori produk untuk tiap item (urutan sama dengan `items`)
+pub fn apply_promotions(
+    items: &mut [SaleItem],
+    categories: &[Option<ObjectId>],
+    promotions: &[Promotion],
+) {
+    let mut remaining: Vec<&Promotion> = promotions.iter().collect();
+
+    loop {
+        let best = remaining
+            .iter()
+            .enumerate()
+            .filter_map(|(index, promotion)| {
+                let lines: Vec<usize> = (0..items.len())
+                    .filter(|&i| {
+                        items[i].discount.is_none()
+                            && items[i].promotion.is_none()
+                            && promotion.applies_to(items[i].product_id, categories[i])
+                    })
+                    .collect();
+                let amounts = promotion_amounts(&promotion.rule, items, &lines);
+                let total: Money = amounts.iter().map(|(_, amount)| *amount).sum();
+                total.is_positive().then_some((index, amounts, total))
+            })
+            .max_by_key(|(_, _, total)| *total);
+
+        let Some((index, amounts, _)) = best else {
+            break;
+        };
+        let promotion = remaining.remove(index);
+
+        for (line, amount) in amounts {
+            let item = &mut items[line];
+            item.subtotal -= amount;
+            item.promotion = Some(SaleItemPromotion {
+                promotion_id: promotion.id.expect("Promotion.id harus ada"),
+                name: promotion.name.clone(),
+                amount,
+            });
+        }
+    }
+}
+
+/// Potongan per item untuk satu promo, hanya item yang ikut terhitung dalam promo.
+/// Dihitung dari (harga, qty) per baris, bukan per unit, supaya qty besar tetap murah
+fn promotion_amounts(
+    rule: &PromotionRule,
+    items: &[SaleItem],
+    lines: &[usize],
+) -> Vec<(usize, Money)> {
+    // (baris, harga, qty) urut dari yang termahal; unit-unitnya dianggap berderet sesuai urutan ini
+    let mut runs: Vec<(usize, Money, i64)> = lines
+        .iter()
+        .map(|&i| (i, items[i].price, items[i].quantity.max(0) as i64))
+        .collect();
+    runs.sort_by_key(|run| std::cmp::Reverse(run.1));
+    let total_units: i64 = runs.iter().map(|run| run.2).sum();
+
+    let mut amounts: Vec<(usize, Money)> = Vec::new();
+
+    match *rule {
+        PromotionRule::BuyXGetY {
+            buy_quantity,
+            get_quantity,
+        } => {
+            // Unit dikelompokkan per (X + Y) dari yang termahal, Y unit termurah tiap kelompok gratis
+            let buy = buy_quantity as i64;
+            let group_size = buy + get_quantity as i64;
+            if group_size <= 0 {
+                return Vec::new();
+            }
+            let grouped = total_units / group_size * group_size;
+            // Jumlah unit gratis di antara n unit pertama
+            let free_before =
+                |n: i64| n / group_size * (group_size - buy) + (n % group_size - buy).max(0);
+
+            let mut start = 0;
+            for &(line, price, quantity) in &runs {
+                let end = (start + quantity).min(grouped);
+                if start < end {
+                    amounts.push((line, price * (free_before(end) - free_before(start))));
+                }
+                start += quantity;
+            }
+        }
+        PromotionRule::Bundle { quantity, price } => {
+            // Paket diisi unit termahal lebih dulu supaya potongannya maksimal
+            let quantity = quantity as i64;
+            if quantity <= 0 {
+                return Vec::new();
+            }
+            let bundles = total_units / quantity;
+
+            let mut value_per_line: Vec<(usize, Money)> = Vec::new();
+            let mut remaining = bundles * quantity;
+            for &(line, unit_price, line_quantity) in &runs {
+                let bundled = line_quantity.min(remaining);
+                if bundled > 0 {
+                    value_per_line.push((line, unit_price * bundled));
+                }
+                remaining -= bundled;
+            }
+
+            let normal: Money = value_per_line.iter().map(|(_, value)| *value).sum();
+            let discount = normal - price * bundles;
+            if !discount.is_positive() {
+                return Vec::new();
+            }
+
+            // Potongan dibagi proporsional ke tiap item, sisa pembulatan masuk ke item terakhir
+            let mut allocated = Money::ZERO;
+            for (position, (line, value)) in value_per_line.iter().enumerate() {
+                let share = if position + 1 == value_per_line.len() {
+                    discount - allocated
+                } else {
+                    discount.mul_div(value.minor(), normal.minor())
+                };
+                allocated += share;
+                amounts.push((*line, share));
+            }
+        }
+        PromotionRule::QuantityDiscount {
+            min_quantity,
+            percent,
+        } => {
+            if total_units < min_quantity as i64 {
+                return Vec::new();
+            }
+            for &line in lines {
+                amounts.push((line, items[line].subtotal.percent(percent)));
+            }
+        }
+    }
+
+    amounts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::sale::{Discount, DiscountType};
+
+    fn item(price: i64, quantity: i32) -> SaleItem {
+        SaleItem {
+            product_id: ObjectId::new(),
+            product_name: "Produk".into(),
+            sku: "SKU-1".into(),
+            quantity,
+            price: Money::from_rupiah(price),
+            subtotal: Money::from_rupiah(price) * quantity as i64,
+            discount: None,
+            promotion: None,
+            tax_rate: 0.0,
+            tax_amount: Money::ZERO,
+            returned_quantity: 0,
+        }
+    }
+
+    fn promotion(rule: PromotionRule, items: &[&SaleItem]) -> Promotion {
+        Promotion {
+            id: Some(ObjectId::new()),
+            user_id: ObjectId::new(),
+            name: "Promo".into(),
+            rule,
+            product_ids: items.iter().map(|i| i.product_id).collect(),
+            category_ids: Vec::new(),
+            starts_at: BsonDateTime::now(),
+            ends_at: BsonDateTime::now(),
+            active: true,
+            created_at: None,
+            updated_at: None,
+        }
+    }
+
+    fn promotion_amount(item: &SaleItem) -> Money {
+        item.promotion
+            .as_ref()
+            .map(|p| p.amount)
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn buy_x_get_y_frees_cheapest_unit_in_each_group() {
+        let mut items = vec![item(15_000, 2), item(10_000, 1)];
+        let rule = PromotionRule::BuyXGetY {
+            buy_quantity: 2,
+            get_quantity: 1,
+        };
+        let promotions = [promotion(rule, &[&items[0], &items[1]])];
+
+        apply_promotions(&mut items, &[None, None], &promotions);
+
+        assert!(items[0].promotion.is_some());
+        assert_eq!(promotion_amount(&items[0]), Money::ZERO);
+        assert_eq!(promotion_amount(&items[1]), Money::from_rupiah(10_000));
+        assert_eq!(items[1].subtotal, Money::ZERO);
+    }
+
+    #[test]
+    fn buy_x_get_y_ignores_incomplete_group() {
+        let mut items = vec![item(10_000, 5)];
+        let rule = PromotionRule::BuyXGetY {
+            buy_quantity: 2,
+            get_quantity: 1,
+        };
+        let promotions = [promotion(rule, &[&items[0]])];
+
+        apply_promotions(&mut items, &[None], &promotions);
+
+        // 5 unit = 1 kelompok penuh + 2 unit sisa, hanya 1 unit gratis
+        assert_eq!(promotion_amount(&items[0]), Money::from_rupiah(10_000));
+        assert_eq!(items[0].subtotal, Money::from_rupiah(40_000));
+    }
+
+    #[test]
+    fn bundle_splits_discount_proportionally_with_remainder_on_last_line() {
+        let mut items = vec![item(10_000, 2), item(10_000, 1)];
+        let rule = PromotionRule::Bundle {
+            quantity: 3,
+            price: Money::from_rupiah(25_000),
+        };
+        let promotions = [promotion(rule, &[&items[0], &items[1]])];
+
+        apply_promotions(&mut items, &[None, None], &promotions);
+
+        // Potongan Rp 5.000 dibagi 2:1
+        assert_eq!(promotion_amount(&items[0]), Money::from_minor(333_333));
+        assert_eq!(promotion_amount(&items[1]), Money::from_minor(166_667));
+        assert_eq!(
+            items[0].subtotal + items[1].subtotal,
+            Money::from_rupiah(25_000)
+        );
+    }
+
+    #[test]
+    fn bundle_more_expensive_than_normal_price_is_skipped() {
+        let mut items = vec![item(5_000, 3)];
+        let rule = PromotionRule::Bundle {
+            quantity: 3,
+            price: Money::from_rupiah(20_000),
+        };
+        let promotions = [promotion(rule, &[&items[0]])];
+
+        apply_promotions(&mut items, &[None], &promotions);
+
+        assert!(items[0].promotion.is_none());
+        assert_eq!(items[0].subtotal, Money::from_rupiah(15_000));
+    }
+
+    #[test]
+    fn quantity_discount_requires_minimum_quantity() {
+        let rule = PromotionRule::QuantityDiscount {
+            min_quantity: 3,
+            percent: 10.0,
+        };
+
+        let mut items = vec![item(10_000, 2)];
+        let promotions = [promotion(rule.clone(), &[&items[0]])];
+        apply_promotions(&mut items, &[None], &promotions);
+        assert!(items[0].promotion.is_none());
+
+        let mut items = vec![item(10_000, 3)];
+        let promotions = [promotion(rule, &[&items[0]])];
+        apply_promotions(&mut items, &[None], &promotions);
+        assert_eq!(promotion_amount(&items[0]), Money::from_rupiah(3_000));
+        assert_eq!(items[0].subtotal, Money::from_rupiah(27_000));
+    }
+
+    #[test]
+    fn largest_promotion_wins_and_each_item_gets_one_promotion() {
+        let mut items = vec![item(10_000, 3)];
+        let small = PromotionRule::QuantityDiscount {
+            min_quantity: 3,
+            percent: 10.0,
+        };
+        let large = PromotionRule::BuyXGetY {
+            buy_quantity: 2,
+            get_quantity: 1,
+        };
+        let promotions = [
+            promotion(small, &[&items[0]]),
+            promotion(large, &[&items[0]]),
+        ];
+
+        apply_promotions(&mut items, &[None], &promotions);
+
+        let applied = items[0].promotion.as_ref().unwrap();
+        assert_eq!(applied.promotion_id, promotions[1].id.unwrap());
+        assert_eq!(applied.amount, Money::from_rupiah(10_000));
+        assert_eq!(items[0].subtotal, Money::from_rupiah(20_000));
+    }
+
+    #[test]
+    fn items_with_manual_discount_or_other_products_are_skipped() {
+        let mut items = vec![item(10_000, 3), item(10_000, 3)];
+        items[0].discount = Some(Discount {
+            discount_type: DiscountType::Fixed,
+            percent: None,
+            amount: Money::from_rupiah(1_000),
+        });
+        let rule = PromotionRule::QuantityDiscount {
+            min_quantity: 1,
+            percent: 10.0,
+        };
+        let promotions = [promotion(rule, &[&items[0]])];
+
+        apply_promotions(&mut items, &[None, None], &promotions);
+
+        assert!(items[0].promotion.is_none());
+        assert!(items[1].promotion.is_none());
+    }
+
+    #[test]
+    fn promotion_applies_by_category() {
+        let category = ObjectId::new();
+        let mut items = vec![item(10_000, 3)];
+        let rule = PromotionRule::QuantityDiscount {
+            min_quantity: 3,
+            percent: 10.0,
+        };
+        let promotions = [Promotion {
+            category_ids: vec![category],
+            ..promotion(rule, &[])
+        }];
+
+        apply_promotions(&mut items, &[Some(category)], &promotions);
+
+        assert_eq!(promotion_amount(&items[0]), Money::from_rupiah(3_000));
+    }
+
+    #[test]
+    fn large_quantities_are_priced_without_expanding_units() {
+        let mut items = vec![item(1, 2_000_000_000)];
+        let rule = PromotionRule::BuyXGetY {
+            buy_quantity: 2,
+            get_quantity: 1,
+        };
+        let promotions = [promotion(rule, &[&items[0]])];
+
+        apply_promotions(&mut items, &[None], &promotions);
+
+        // 666.666.666 kelompok penuh, 1 unit gratis per kelompok
+        assert_eq!(promotion_amount(&items[0]), Money::from_rupiah(666_666_666));
+    }
+}
@@ -0,0 +1,437 @@
+use crate::errors::ServiceError;
+use crate::models::money::Money;
+use crate::models::sale::Sale;
+use crate::models::store_setting::StoreSetting;
+use crate::services::sale_service::get_sale_service;
+use crate::services::store_setting_service::find_store_setting;
+use crate::utils::{ascii_printable, format_rupiah};
+use mongodb::Database;
+
+// Perintah ESC/POS yang dipakai
+const ESC: u8 = 0x1B;
+const GS: u8 = 0x1D;
+const LF: u8 = 0x0A;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Align {
+    Left,
+    Center,
+}
+
+/// Satu baris struk, dipakai bersama oleh output ESC/POS dan preview teks
+#[derive(Debug, Clone)]
+pub struct ReceiptLine {
+    pub text: String,
+    pub align: Align,
+    pub bold: bool,
+    pub double_size: bool,
+}
+
+impl ReceiptLine {
+    fn new(text: impl Into<String>, align: Align) -> Self {
+        ReceiptLine {
+            text: text.into(),
+            align,
+            bold: false,
+            double_size: false,
+        }
+    }
+
+    fn bold(mut self) -> Self {
+        self.bold = true;
+        self
+    }
+
+    fn double_size(mut self) -> Self {
+        self.double_size = true;
+        self
+    }
+}
+
+/// Lebar kertas yang didukung: 58mm (32 kolom) dan 80mm (48 kolom), font A
+pub fn validate_paper_width(paper_width: u32) -> Result<(), ServiceError> {
+    match paper_width {
+        58 | 80 => Ok(()),
+        _ => Err(ServiceError::BadRequest(
+            "Lebar kertas struk harus 58 atau 80 (mm)".into(),
+        )),
+    }
+}
+
+fn paper_columns(paper_width: u32) -> usize {
+    if paper_width == 80 { 48 } else { 32 }
+}
+
+/// Ambil sale + pengaturan toko lalu susun baris-baris struknya
+pub async fn get_sale_receipt_service(
+    sale_id: &str,
+    paper_width: Option<u32>,
+    db: &Database,
+    user_id: &str,
+) -> Result<(Vec<ReceiptLine>, usize), ServiceError> {
+    let sale = get_sale_service(sale_id, db, user_id).await?;
+    let setting = find_store_setting(sale.user_id, db).await?;
+
+    let paper_width = paper_width.unwrap_or(setting.receipt_paper_width);
+    validate_paper_width(paper_width)?;
+    let columns = paper_columns(paper_width);
+
+    Ok((build_receipt_lines(&sale, &setting, columns), columns))
+}
+
+pub fn build_receipt_lines(
+    sale: &Sale,
+    setting: &StoreSetting,
+    columns: usize,
+) -> Vec<ReceiptLine> {
+    let mut lines = Vec::new();
+    let separator = ReceiptLine::new("-".repeat(columns), Align::Left);
+
+    // Header toko
+    let store_name = setting.store_name.as_deref().unwrap_or("Toko");
+    lines.push(
+        ReceiptLine::new(store_name, Align::Center)
+            .bold()
+            .double_size(),
+    );
+    if let Some(address) = &setting.store_address {
+        for part in wrap(address, columns) {
+            lines.push(ReceiptLine::new(part, Align::Center));
+        }
+    }
+    if let Some(phone) = &setting.store_phone {
+        lines.push(ReceiptLine::new(format!("Telp: {}", phone), Align::Center));
+    }
+    lines.push(separator.clone());
+
+    if let Some(invoice_number) = &sale.invoice_number {
+        lines.push(ReceiptLine::new(invoice_number.clone(), Align::Left));
+    }
+    if let Some(sale_date) = sale.sale_date {
+        lines.push(ReceiptLine::new(
+            setting
+                .local_time(sale_date.to_chrono())
+                .format("%d-%m-%Y %H:%M")
+                .to_string(),
+            Align::Left,
+        ));
+    }
+    if sale.status == "voided" {
+        lines.push(ReceiptLine::new("*** VOID ***", Align::Center).bold());
+    }
+    lines.push(separator.clone());
+
+    // Item: nama di baris pertama, qty x harga dan subtotal di baris kedua
+    for item in &sale.items {
+        for part in wrap(&item.product_name, columns) {
+            lines.push(ReceiptLine::new(part, Align::Left));
+        }
+        lines.push(ReceiptLine::new(
+            two_columns(
+                &format!("  {} x {}", item.quantity, format_rupiah(item.price)),
+                &format_rupiah(item.subtotal),
+                columns,
+            ),
+            Align::Left,
+        ));
+        if let Some(discount) = &item.discount {
+            lines.push(ReceiptLine::new(
+                two_columns(
+                    &format!("  {}", discount.label()),
+                    &format!("-{}", format_rupiah(discount.amount)),
+                    columns,
+                ),
+                Align::Left,
+            ));
+        }
+        if let Some(promotion) = &item.promotion {
+            lines.push(ReceiptLine::new(
+                two_columns(
+                    &format!("  {}", promotion.label()),
+                    &format!("-{}", format_rupiah(promotion.amount)),
+                    columns,
+                ),
+                Align::Left,
+            ));
+        }
+    }
+    lines.push(separator.clone());
+
+    // Total, bayar, kembalian
+    let tenders = sale.payment_tenders();
+    let change: Money = tenders.iter().map(|t| t.change_amount).sum();
+    let tax_added = sale.tax_amount.is_positive() && !sale.tax_inclusive;
+    if sale.discount.is_some() || sale.voucher.is_some() || tax_added {
+        lines.push(ReceiptLine::new(
+            two_columns("SUBTOTAL", &format_rupiah(sale.subtotal_amount), columns),
+            Align::Left,
+        ));
+    }
+    if let Some(discount) = &sale.discount {
+        lines.push(ReceiptLine::new(
+            two_columns(
+                &discount.label().to_uppercase(),
+                &format!("-{}", format_rupiah(discount.amount)),
+                columns,
+            ),
+            Align::Left,
+        ));
+    }
+    if let Some(voucher) = &sale.voucher {
+        lines.push(ReceiptLine::new(
+            two_columns(
+                &voucher.label().to_uppercase(),
+                &format!("-{}", format_rupiah(voucher.amount)),
+                columns,
+            ),
+            Align::Left,
+        ));
+    }
+    if tax_added {
+        lines.push(ReceiptLine::new(
+            two_columns("PPN", &format_rupiah(sale.tax_amount), columns),
+            Align::Left,
+        ));
+    }
+    lines.push(
+        ReceiptLine::new(
+            two_columns("TOTAL", &format_rupiah(sale.total_amount), columns),
+            Align::Left,
+        )
+        .bold(),
+    );
+    if sale.tax_amount.is_positive() && sale.tax_inclusive {
+        lines.push(ReceiptLine::new(
+            two_columns("DPP", &format_rupiah(sale.tax_base), columns),
+            Align::Left,
+        ));
+        lines.push(ReceiptLine::new(
+            two_columns("PPN (termasuk)", &format_rupiah(sale.tax_amount), columns),
+            Align::Left,
+        ));
+    }
+    if tenders.is_empty() {
+        lines.push(ReceiptLine::new(
+            two_columns("BAYAR", &format_rupiah(sale.paid_amount), columns),
+            Align::Left,
+        ));
+    }
+    for tender in &tenders {
+        lines.push(ReceiptLine::new(
+            two_columns(
+                &tender.payment_method.name.to_uppercase(),
+                &format_rupiah(tender.amount),
+                columns,
+            ),
+            Align::Left,
+        ));
+    }
+    if change.is_positive() {
+        lines.push(ReceiptLine::new(
+            two_columns("KEMBALI", &format_rupiah(change), columns),
+            Align::Left,
+        ));
+    }
+    if sale.remaining_amount.is_positive() {
+        lines.push(ReceiptLine::new(
+            two_columns("SISA", &format_rupiah(sale.remaining_amount), columns),
+            Align::Left,
+        ));
+    }
+
+    if let Some(notes) = &sale.notes {
+        lines.push(separator.clone());
+        for part in wrap(notes, columns) {
+            lines.push(ReceiptLine::new(part, Align::Left));
+        }
+    }
+
+    // Footer
+    lines.push(separator);
+    let footer = setting
+        .receipt_footer
+        .as_deref()
+        .unwrap_or("Terima kasih atas kunjungan Anda");
+    for part in wrap(footer, columns) {
+        lines.push(ReceiptLine::new(part, Align::Center));
+    }
+
+    // Potong di sini supaya ESC/POS dan preview mencetak teks yang sama.
+    // Karakter double size memakan dua kolom
+    for line in &mut lines {
+        let max = if line.double_size {
+            columns / 2
+        } else {
+            columns
+        };
+        line.text = line.text.chars().take(max).collect();
+    }
+
+    lines
+}
+
+/// Render baris struk ke byte stream ESC/POS (init, teks, feed, potong kertas)
+pub fn render_escpos(lines: &[ReceiptLine]) -> Vec<u8> {
+    let mut bytes = vec![ESC, b'@'];
+
+    for line in lines {
+        let align = match line.align {
+            Align::Left => 0,
+            Align::Center => 1,
+        };
+        bytes.extend_from_slice(&[ESC, b'a', align]);
+        bytes.extend_from_slice(&[ESC, b'E', line.bold as u8]);
+        bytes.extend_from_slice(&[GS, b'!', if line.double_size { 0x11 } else { 0x00 }]);
+        bytes.extend(ascii_printable(&line.text).bytes());
+        bytes.push(LF);
+    }
+
+    // Reset style, feed beberapa baris lalu partial cut
+    bytes.extend_from_slice(&[ESC, b'E', 0, GS, b'!', 0, ESC, b'a', 0]);
+    bytes.extend_from_slice(&[ESC, b'd', 4]);
+    bytes.extend_from_slice(&[GS, b'V', 66, 0]);
+    bytes
+}
+
+/// Preview teks polos dengan lebar kolom yang sama seperti printer. Baris sudah
+/// dipotong oleh `build_receipt_lines`, di sini hanya diatur perataannya
+pub fn render_text(lines: &[ReceiptLine], columns: usize) -> String {
+    let mut output = String::new();
+
+    for line in lines {
+        let text = ascii_printable(&line.text);
+        let used = if line.double_size {
+            text.chars().count() * 2
+        } else {
+            text.chars().count()
+        };
+        let padding = columns.saturating_sub(used);
+
+        let rendered = match line.align {
+            Align::Left => text,
+            Align::Center => format!("{}{}", " ".repeat(padding / 2), text),
+        };
+        output.push_str(rendered.trim_end());
+        output.push('\n');
+    }
+
+    output
+}
+
+/// Teks kiri dan kanan dalam satu baris, kanan rata kanan
+fn two_columns(left: &str, right: &str, columns: usize) -> String {
+    let right_len = right.chars().count();
+    let max_left = columns.saturating_sub(right_len + 1);
+    let left: String = left.chars().take(max_left).collect();
+    let padding = columns.saturating_sub(left.chars().count() + right_len);
+    format!("{}{}{}", left, " ".repeat(padding), right)
+}
+
+/// Pecah teks per kata supaya tidak melebihi lebar kertas
+fn wrap(text: &str, columns: usize) -> Vec<String> {
+    let mut result = Vec::new();
+    let mut current = String::new();
+
+    for word in text.split_whitespace() {
+        let word: String = word.chars().take(columns).collect();
+        if !current.is_empty() && current.chars().count() + 1 + word.chars().count() > columns {
+            result.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(&word);
+    }
+    if !current.is_empty() {
+        result.push(current);
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use mongodb::bson::{DateTime, doc, from_document, oid::ObjectId};
+
+    fn sample_sale() -> Sale {
+        from_document(doc! {
+            "user_id": ObjectId::new(),
+            "customer_id": null,
+            "items": [{
+                "product_id": ObjectId::new(),
+                "product_name": "Kopi Susu Gula Aren Ukuran Besar Sekali",
+                "sku": "SKU-1",
+                "quantity": 2,
+                "price": 1_500_000_i64,
+                "subtotal": 3_000_000_i64,
+            }],
+            "subtotal_amount": 3_000_000_i64,
+            "total_amount": 3_000_000_i64,
+            "paid_amount": 3_000_000_i64,
+            "remaining_amount": 0_i64,
+            "status": "paid",
+            "invoice_number": "INV/2025/06/00001",
+            "sale_date": DateTime::from_chrono(Utc.with_ymd_and_hms(2025, 6, 1, 17, 30, 0).unwrap()),
+            "notes": null,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn two_columns_right_aligns_and_truncates_left() {
+        assert_eq!(two_columns("TOTAL", "Rp 100", 16), "TOTAL     Rp 100");
+        assert_eq!(
+            two_columns("NAMA PANJANG SEKALI", "Rp 100", 16),
+            "NAMA PANJ Rp 100"
+        );
+    }
+
+    #[test]
+    fn wrap_breaks_on_words_and_cuts_long_words() {
+        assert_eq!(
+            wrap("satu dua tiga empat", 9),
+            vec!["satu dua", "tiga", "empat"]
+        );
+        assert_eq!(wrap("abcdefghijkl", 5), vec!["abcde"]);
+        assert!(wrap("   ", 5).is_empty());
+    }
+
+    #[test]
+    fn render_text_centers_double_size_by_two_columns() {
+        let lines = vec![
+            ReceiptLine::new("AB", Align::Center).double_size(),
+            ReceiptLine::new("AB", Align::Center),
+            ReceiptLine::new("kiri", Align::Left),
+        ];
+        assert_eq!(render_text(&lines, 10), "   AB\n    AB\nkiri\n");
+    }
+
+    #[test]
+    fn build_receipt_lines_truncates_to_paper_width() {
+        let mut setting = StoreSetting::default_for(ObjectId::new());
+        setting.store_name = Some("Toko Kelontong Sumber Rejeki Abadi".into());
+        let lines = build_receipt_lines(&sample_sale(), &setting, 32);
+
+        assert!(lines[0].double_size);
+        assert_eq!(lines[0].text, "Toko Kelontong S");
+        for line in &lines {
+            let max = if line.double_size { 16 } else { 32 };
+            assert!(line.text.chars().count() <= max, "{:?}", line.text);
+        }
+    }
+
+    #[test]
+    fn build_receipt_lines_prints_store_local_time() {
+        let mut setting = StoreSetting::default_for(ObjectId::new());
+        setting.utc_offset_minutes = 420; // WIB
+        let lines = build_receipt_lines(&sample_sale(), &setting, 32);
+        assert!(lines.iter().any(|line| line.text == "02-06-2025 00:30"));
+        assert!(
+            lines
+                .iter()
+                .any(|line| line.text == "  2 x Rp 15.000        Rp 30.000")
+        );
+    }
+}
@@ -0,0 +1,488 @@
+use crate::errors::ServiceError;
+use crate::models::customer::Customer;
+use crate::models::money::Money;
+use crate::models::receivable::{
+    AgingBuckets, AgingReport, CustomerAging, CustomerBalance, CustomerStatement, StatementEntry,
+    StatementQuery,
+};
+use crate::models::sale::Sale;
+use crate::models::sale_payment::SalePayment;
+use crate::models::sale_return::SaleReturn;
+use crate::models::store_setting::StoreSetting;
+use crate::services::customer_service::find_customer;
+use crate::services::sale_service::parse_query_date;
+use crate::services::store_setting_service::find_store_setting;
+use crate::utils::string_id_to_obj_id;
+use bson::datetime::DateTime as BsonDateTime;
+use bson::oid::ObjectId;
+use chrono::{DateTime, Utc};
+use futures::stream::TryStreamExt;
+use mongodb::{
+    Collection, Database,
+    bson::{Document, doc, from_document},
+};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+const NO_CUSTOMER_NAME: &str = "(tanpa pelanggan)";
+
+/// Hasil `$group` piutang per pelanggan
+#[derive(Debug, Deserialize)]
+struct ReceivableGroup {
+    #[serde(rename = "_id")]
+    customer_id: Option<ObjectId>,
+    customer: Option<Customer>,
+    outstanding_amount: Money,
+    invoice_count: i64,
+    oldest_sale_date: Option<BsonDateTime>,
+}
+
+/// Hasil `$group` umur piutang per pelanggan
+#[derive(Debug, Deserialize)]
+struct AgingGroup {
+    #[serde(rename = "_id")]
+    customer_id: Option<ObjectId>,
+    customer: Option<Customer>,
+    days_0_30: Money,
+    days_31_60: Money,
+    days_61_90: Money,
+    days_over_90: Money,
+    total: Money,
+}
+
+/// Saldo piutang semua pelanggan yang masih punya tagihan, terbesar lebih dulu
+pub async fn get_receivables_service(
+    db: &Database,
+    user_id: &str,
+) -> Result<Vec<CustomerBalance>, ServiceError> {
+    let user_id = match string_id_to_obj_id(user_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let setting = find_store_setting(user_id, db).await?;
+    let groups = group_receivables(doc! { "user_id": user_id }, db).await?;
+
+    Ok(groups
+        .into_iter()
+        .map(|group| customer_balance(group, &setting))
+        .collect())
+}
+
+/// Saldo piutang satu pelanggan
+pub async fn get_customer_balance_service(
+    customer_id: &str,
+    db: &Database,
+    user_id: &str,
+) -> Result<CustomerBalance, ServiceError> {
+    let (customer, user_id) = resolve_customer(customer_id, user_id, db).await?;
+
+    let setting = find_store_setting(user_id, db).await?;
+    let group = group_receivables(doc! { "user_id": user_id, "customer_id": customer.id }, db)
+        .await?
+        .into_iter()
+        .next();
+
+    Ok(match group {
+        Some(group) => customer_balance(group, &setting),
+        None => CustomerBalance {
+            customer_id: customer.id.map(|id| id.to_hex()),
+            customer_name: customer.name,
+            phone: customer.phone,
+            outstanding_amount: Money::ZERO,
+            invoice_count: 0,
+            oldest_invoice_date: None,
+        },
+    })
+}
+
+/// Umur piutang per pelanggan dihitung dari tanggal sale sampai hari ini (zona waktu toko)
+pub async fn get_aging_report_service(
+    db: &Database,
+    user_id: &str,
+) -> Result<AgingReport, ServiceError> {
+    let user_id = match string_id_to_obj_id(user_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let setting = find_store_setting(user_id, db).await?;
+    let today = setting.business_date(Utc::now());
+
+    // Umur dihitung di database dengan $dateDiff per hari kalender zona waktu toko
+    let now = BsonDateTime::from_chrono(Utc::now());
+    let in_bucket = |condition: Document| {
+        doc! { "$sum": { "$cond": [condition, "$remaining_amount", 0_i64] } }
+    };
+    let pipeline = vec![
+        doc! { "$match": outstanding_filter(doc! { "user_id": user_id }) },
+        doc! { "$set": { "age_days": { "$dateDiff": {
+            "startDate": "$sale_date",
+            "endDate": now,
+            "unit": "day",
+            "timezone": setting.timezone(),
+        } } } },
+        doc! { "$group": {
+            "_id": "$customer_id",
+            "days_0_30": in_bucket(doc! { "$lte": ["$age_days", 30] }),
+            "days_31_60": in_bucket(doc! { "$and": [
+                { "$gt": ["$age_days", 30] }, { "$lte": ["$age_days", 60] },
+            ] }),
+            "days_61_90": in_bucket(doc! { "$and": [
+                { "$gt": ["$age_days", 60] }, { "$lte": ["$age_days", 90] },
+            ] }),
+            "days_over_90": in_bucket(doc! { "$gt": ["$age_days", 90] }),
+            "total": { "$sum": "$remaining_amount" },
+        } },
+        lookup_customer(),
+        doc! { "$set": { "customer": { "$first": "$customer" } } },
+        doc! { "$sort": { "total": -1 } },
+    ];
+    let groups: Vec<AgingGroup> = aggregate(pipeline, db).await?;
+
+    let mut report = AgingReport {
+        as_of: today.to_string(),
+        totals: AgingBuckets::default(),
+        customers: Vec::new(),
+    };
+
+    for group in groups {
+        let buckets = AgingBuckets {
+            days_0_30: group.days_0_30,
+            days_31_60: group.days_31_60,
+            days_61_90: group.days_61_90,
+            days_over_90: group.days_over_90,
+            total: group.total,
+        };
+        report.totals.days_0_30 += buckets.days_0_30;
+        report.totals.days_31_60 += buckets.days_31_60;
+        report.totals.days_61_90 += buckets.days_61_90;
+        report.totals.days_over_90 += buckets.days_over_90;
+        report.totals.total += buckets.total;
+
+        report.customers.push(CustomerAging {
+            customer_id: group.customer_id.map(|id| id.to_hex()),
+            customer_name: customer_name(group.customer.as_ref()),
+            buckets,
+        });
+    }
+
+    Ok(report)
+}
+
+/// Mutasi piutang pelanggan: invoice (debit) dan pembayaran (kredit) dengan saldo berjalan.
+/// Sale yang di-void tidak ikut karena tagihannya batal
+pub async fn get_customer_statement_service(
+    customer_id: &str,
+    query: StatementQuery,
+    db: &Database,
+    user_id: &str,
+) -> Result<CustomerStatement, ServiceError> {
+    let (customer, user_id) = resolve_customer(customer_id, user_id, db).await?;
+    let customer_id = customer.id.expect("Customer.id harus ada");
+
+    let setting = find_store_setting(user_id, db).await?;
+    let start = match &query.date_from {
+        Some(date) => Some(setting.business_day_bounds(parse_query_date(date)?).0),
+        None => None,
+    };
+    let end = match &query.date_to {
+        Some(date) => Some(setting.business_day_bounds(parse_query_date(date)?).1),
+        None => None,
+    };
+
+    let sale_collection: Collection<Sale> = db.collection("sales");
+    let mut cursor = sale_collection
+        .find(doc! {
+            "user_id": user_id,
+            "customer_id": customer_id,
+            "status": { "$ne": "voided" },
+        })
+        .sort(doc! { "sale_date": 1, "_id": 1 })
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+
+    let mut sales: Vec<Sale> = Vec::new();
+    while let Some(sale) = cursor
+        .try_next()
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?
+    {
+        sales.push(sale);
+    }
+
+    let sale_ids: Vec<ObjectId> = sales.iter().filter_map(|s| s.id).collect();
+    let payment_collection: Collection<SalePayment> = db.collection("sale_payments");
+    let mut cursor = payment_collection
+        .find(doc! { "user_id": user_id, "sale_id": { "$in": &sale_ids } })
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+
+    let mut payments: Vec<SalePayment> = Vec::new();
+    while let Some(payment) = cursor
+        .try_next()
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?
+    {
+        payments.push(payment);
+    }
+
+    let return_collection: Collection<SaleReturn> = db.collection("sale_returns");
+    let mut cursor = return_collection
+        .find(doc! { "user_id": user_id, "sale_id": { "$in": &sale_ids } })
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+
+    let mut returns: Vec<SaleReturn> = Vec::new();
+    while let Some(sale_return) = cursor
+        .try_next()
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?
+    {
+        returns.push(sale_return);
+    }
+
+    let mut installments: HashMap<ObjectId, Money> = HashMap::new();
+    for payment in &payments {
+        *installments.entry(payment.sale_id).or_default() += payment.amount;
+    }
+    let invoice_numbers: HashMap<ObjectId, Option<String>> = sales
+        .iter()
+        .filter_map(|s| Some((s.id?, s.invoice_number.clone())))
+        .collect();
+    let invoice_label = |sale_id: ObjectId| {
+        invoice_numbers
+            .get(&sale_id)
+            .cloned()
+            .flatten()
+            .unwrap_or_else(|| sale_id.to_hex())
+    };
+
+    // (waktu, baris) tanpa saldo, saldo berjalan dihitung setelah diurutkan
+    let mut movements: Vec<(DateTime<Utc>, StatementEntry)> = Vec::new();
+
+    for sale in &sales {
+        let sale_id = sale.id.expect("Sale.id harus ada");
+        let sale_date = sale
+            .sale_date
+            .or(sale.created_at)
+            .map(|t| t.to_chrono())
+            .unwrap_or_default();
+        let invoice = sale
+            .invoice_number
+            .clone()
+            .unwrap_or_else(|| sale_id.to_hex());
+
+        movements.push((
+            sale_date,
+            StatementEntry {
+                date: sale_date.to_rfc3339(),
+                entry_type: "invoice".to_string(),
+                sale_id: sale_id.to_hex(),
+                invoice_number: sale.invoice_number.clone(),
+                description: format!("Penjualan {}", invoice),
+                debit: sale.total_amount,
+                credit: Money::ZERO,
+                balance: Money::ZERO,
+            },
+        ));
+
+        // Pembayaran saat transaksi = total dibayar dikurangi cicilan yang dicatat kemudian
+        let installments = installments.get(&sale_id).copied().unwrap_or_default();
+        let paid_at_sale = sale.paid_amount - installments;
+        if paid_at_sale.is_positive() {
+            movements.push((
+                sale_date,
+                StatementEntry {
+                    date: sale_date.to_rfc3339(),
+                    entry_type: "payment".to_string(),
+                    sale_id: sale_id.to_hex(),
+                    invoice_number: sale.invoice_number.clone(),
+                    description: format!("Pembayaran saat transaksi {}", invoice),
+                    debit: Money::ZERO,
+                    credit: paid_at_sale,
+                    balance: Money::ZERO,
+                },
+            ));
+        }
+    }
+
+    for payment in &payments {
+        let paid_at = payment.paid_at.to_chrono();
+
+        movements.push((
+            paid_at,
+            StatementEntry {
+                date: paid_at.to_rfc3339(),
+                entry_type: "payment".to_string(),
+                sale_id: payment.sale_id.to_hex(),
+                invoice_number: invoice_numbers.get(&payment.sale_id).cloned().flatten(),
+                description: format!(
+                    "Pembayaran {} ({})",
+                    invoice_label(payment.sale_id),
+                    payment.payment_method.name
+                ),
+                debit: Money::ZERO,
+                credit: payment.amount,
+                balance: Money::ZERO,
+            },
+        ));
+    }
+
+    // Retur mengurangi piutang sebesar nilai barang, uang yang dikembalikan ke pelanggan
+    // dicatat di debit sehingga saldo hanya turun sebesar bagian yang memotong tagihan
+    for sale_return in &returns {
+        let returned_at = sale_return
+            .created_at
+            .map(|t| t.to_chrono())
+            .unwrap_or_default();
+
+        movements.push((
+            returned_at,
+            StatementEntry {
+                date: returned_at.to_rfc3339(),
+                entry_type: "return".to_string(),
+                sale_id: sale_return.sale_id.to_hex(),
+                invoice_number: invoice_numbers.get(&sale_return.sale_id).cloned().flatten(),
+                description: format!("Retur {}", invoice_label(sale_return.sale_id)),
+                debit: sale_return.refund_amount,
+                credit: sale_return.return_amount,
+                balance: Money::ZERO,
+            },
+        ));
+    }
+
+    // Urutan stabil: invoice tetap sebelum pembayaran di waktu yang sama
+    movements.sort_by_key(|(time, _)| *time);
+
+    let mut statement = CustomerStatement {
+        customer_id: customer_id.to_hex(),
+        customer_name: customer.name,
+        date_from: query.date_from,
+        date_to: query.date_to,
+        opening_balance: Money::ZERO,
+        total_debit: Money::ZERO,
+        total_credit: Money::ZERO,
+        closing_balance: Money::ZERO,
+        entries: Vec::new(),
+    };
+
+    let mut balance = Money::ZERO;
+    for (time, mut entry) in movements {
+        if end.is_some_and(|end| time >= end) {
+            break;
+        }
+
+        balance += entry.debit;
+        balance -= entry.credit;
+
+        if start.is_some_and(|start| time < start) {
+            statement.opening_balance = balance;
+            continue;
+        }
+
+        entry.balance = balance;
+        statement.total_debit += entry.debit;
+        statement.total_credit += entry.credit;
+        statement.entries.push(entry);
+    }
+    statement.closing_balance = balance;
+
+    Ok(statement)
+}
+
+async fn resolve_customer(
+    customer_id: &str,
+    user_id: &str,
+    db: &Database,
+) -> Result<(Customer, ObjectId), ServiceError> {
+    let user_id = match string_id_to_obj_id(user_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let customer_id = match string_id_to_obj_id(customer_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let customer = find_customer(customer_id, user_id, db).await?;
+    Ok((customer, user_id))
+}
+
+/// Sale yang masih punya sisa tagihan
+fn outstanding_filter(mut filter: Document) -> Document {
+    filter.insert("status", doc! { "$in": ["unpaid", "partial"] });
+    filter.insert("remaining_amount", doc! { "$gt": 0_i64 });
+    filter
+}
+
+fn lookup_customer() -> Document {
+    doc! { "$lookup": {
+        "from": "customers",
+        "localField": "_id",
+        "foreignField": "_id",
+        "as": "customer",
+    } }
+}
+
+/// Saldo piutang per pelanggan lewat `$group` + `$lookup`, terbesar lebih dulu
+async fn group_receivables(
+    filter: Document,
+    db: &Database,
+) -> Result<Vec<ReceivableGroup>, ServiceError> {
+    let pipeline = vec![
+        doc! { "$match": outstanding_filter(filter) },
+        doc! { "$group": {
+            "_id": "$customer_id",
+            "outstanding_amount": { "$sum": "$remaining_amount" },
+            "invoice_count": { "$sum": 1_i64 },
+            "oldest_sale_date": { "$min": "$sale_date" },
+        } },
+        lookup_customer(),
+        doc! { "$set": { "customer": { "$first": "$customer" } } },
+        doc! { "$sort": { "outstanding_amount": -1, "_id": 1 } },
+    ];
+    aggregate(pipeline, db).await
+}
+
+async fn aggregate<T: serde::de::DeserializeOwned>(
+    pipeline: Vec<Document>,
+    db: &Database,
+) -> Result<Vec<T>, ServiceError> {
+    let collection: Collection<Sale> = db.collection("sales");
+    let mut cursor = collection
+        .aggregate(pipeline)
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+
+    let mut rows: Vec<T> = Vec::new();
+    while let Some(document) = cursor
+        .try_next()
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?
+    {
+        rows.push(from_document(document).map_err(|e| ServiceError::Unexpected(e.to_string()))?);
+    }
+
+    Ok(rows)
+}
+
+fn customer_balance(group: ReceivableGroup, setting: &StoreSetting) -> CustomerBalance {
+    CustomerBalance {
+        customer_id: group.customer_id.map(|id| id.to_hex()),
+        customer_name: customer_name(group.customer.as_ref()),
+        phone: group.customer.and_then(|c| c.phone),
+        outstanding_amount: group.outstanding_amount,
+        invoice_count: group.invoice_count,
+        oldest_invoice_date: group
+            .oldest_sale_date
+            .map(|t| setting.business_date(t.to_chrono()).to_string()),
+    }
+}
+
+fn customer_name(customer: Option<&Customer>) -> String {
+    customer
+        .map(|c| c.name.clone())
+        .unwrap_or_else(|| NO_CUSTOMER_NAME.to_string())
+}
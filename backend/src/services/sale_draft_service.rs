@@ -0,0 +1,252 @@
+use crate::errors::ServiceError;
+use crate::models::sale::{Sale, SaleDTO};
+use crate::models::sale_draft::{
+    FinalizeSaleDraftDTO, SaleDraft, SaleDraftDTO, UpdateSaleDraftDTO,
+};
+use crate::services::sale_service::create_sale_from_draft_service;
+use crate::utils::{string_id_to_obj_id, transaction_error};
+use bson::datetime::DateTime as BsonDateTime;
+use bson::oid::ObjectId;
+use chrono::Utc;
+use futures::stream::TryStreamExt;
+use mongodb::{ClientSession, Collection, Database, bson::doc, options::ReturnDocument};
+use validator::Validate;
+
+pub async fn get_sale_drafts_service(
+    db: &Database,
+    user_id: &str,
+) -> Result<Vec<SaleDraft>, ServiceError> {
+    let user_id = match string_id_to_obj_id(user_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let collection: Collection<SaleDraft> = db.collection("sale_drafts");
+
+    let mut cursor = collection
+        .find(doc! { "user_id": user_id })
+        .sort(doc! { "created_at": 1 })
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+
+    let mut drafts: Vec<SaleDraft> = Vec::new();
+
+    while let Some(draft) = cursor
+        .try_next()
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?
+    {
+        drafts.push(draft);
+    }
+
+    Ok(drafts)
+}
+
+pub async fn get_sale_draft_service(
+    draft_id: &str,
+    db: &Database,
+    user_id: &str,
+) -> Result<SaleDraft, ServiceError> {
+    let user_id = match string_id_to_obj_id(user_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let draft_id = match string_id_to_obj_id(draft_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let collection: Collection<SaleDraft> = db.collection("sale_drafts");
+
+    let draft = collection
+        .find_one(doc! { "_id": draft_id, "user_id": user_id })
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+
+    draft.ok_or_else(|| {
+        ServiceError::NotFound(format!("Draft dengan ID '{}' tidak ditemukan", draft_id))
+    })
+}
+
+/// Simpan keranjang tanpa cek pembayaran dan tanpa menyentuh stok
+pub async fn create_sale_draft_service(
+    payload: SaleDraftDTO,
+    db: &Database,
+    user_id: &str,
+) -> Result<SaleDraft, ServiceError> {
+    let user_id = match string_id_to_obj_id(user_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let now = BsonDateTime::from_chrono(Utc::now());
+
+    let draft = SaleDraft {
+        id: None,
+        user_id,
+        label: payload.label,
+        customer_id: payload.customer_id,
+        items: payload.items,
+        discount: payload.discount,
+        notes: payload.notes,
+        created_at: Some(now),
+        updated_at: Some(now),
+    };
+
+    let collection: Collection<SaleDraft> = db.collection("sale_drafts");
+    let result = collection
+        .insert_one(&draft)
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+
+    Ok(SaleDraft {
+        id: result.inserted_id.as_object_id(),
+        ..draft
+    })
+}
+
+pub async fn update_sale_draft_service(
+    draft_id: &str,
+    payload: UpdateSaleDraftDTO,
+    db: &Database,
+    user_id: &str,
+) -> Result<SaleDraft, ServiceError> {
+    let user_id = match string_id_to_obj_id(user_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let draft_id = match string_id_to_obj_id(draft_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let mut update_doc = doc! {};
+
+    if let Some(label) = payload.label {
+        update_doc.insert("label", label);
+    }
+    if let Some(customer_id) = payload.customer_id {
+        update_doc.insert("customer_id", customer_id);
+    }
+    if let Some(items) = payload.items {
+        let items = bson::to_bson(&items).map_err(|e| ServiceError::Unexpected(e.to_string()))?;
+        update_doc.insert("items", items);
+    }
+    if let Some(discount) = payload.discount {
+        let discount =
+            bson::to_bson(&discount).map_err(|e| ServiceError::Unexpected(e.to_string()))?;
+        update_doc.insert("discount", discount);
+    }
+    if let Some(notes) = payload.notes {
+        update_doc.insert("notes", notes);
+    }
+
+    if update_doc.is_empty() {
+        return Err(ServiceError::BadRequest(
+            "Tidak ada data untuk di-update".to_string(),
+        ));
+    }
+
+    update_doc.insert("updated_at", BsonDateTime::from_chrono(Utc::now()));
+
+    let collection: Collection<SaleDraft> = db.collection("sale_drafts");
+
+    collection
+        .find_one_and_update(
+            doc! { "_id": draft_id, "user_id": user_id },
+            doc! { "$set": update_doc },
+        )
+        .return_document(ReturnDocument::After)
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?
+        .ok_or_else(|| {
+            ServiceError::NotFound(format!("Draft dengan ID '{}' tidak ditemukan", draft_id))
+        })
+}
+
+pub async fn delete_sale_draft_service(
+    draft_id: &str,
+    db: &Database,
+    user_id: &str,
+) -> Result<bool, ServiceError> {
+    let user_id = match string_id_to_obj_id(user_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let draft_id = match string_id_to_obj_id(draft_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let collection: Collection<SaleDraft> = db.collection("sale_drafts");
+
+    let result = collection
+        .delete_one(doc! { "_id": draft_id, "user_id": user_id })
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+
+    if result.deleted_count == 0 {
+        return Err(ServiceError::NotFound(format!(
+            "Draft dengan ID '{}' tidak ditemukan",
+            draft_id
+        )));
+    }
+
+    Ok(true)
+}
+
+/// Ubah draft menjadi sale lewat aturan `create_sale_service` yang sama
+/// (validasi, harga terbaru, stok, pembayaran). Draft dihapus di dalam transaksi sale
+pub async fn finalize_sale_draft_service(
+    draft_id: &str,
+    payload: FinalizeSaleDraftDTO,
+    db: &Database,
+    user_id: &str,
+) -> Result<Sale, ServiceError> {
+    let draft = get_sale_draft_service(draft_id, db, user_id).await?;
+    let draft_id = draft.id.expect("SaleDraft.id harus ada");
+
+    let sale_payload = SaleDTO {
+        customer_id: draft.customer_id,
+        items: draft.items,
+        discount: draft.discount,
+        tenders: payload.tenders,
+        payment_method_id: None,
+        paid_amount: None,
+        redeem_points: payload.redeem_points,
+        voucher_code: payload.voucher_code,
+        notes: draft.notes,
+    };
+    sale_payload
+        .validate()
+        .map_err(|e| ServiceError::BadRequest(e.to_string()))?;
+
+    create_sale_from_draft_service(sale_payload, draft_id, db, user_id).await
+}
+
+/// Hapus draft di dalam transaksi sale. Gagal jika draft sudah difinalisasi/dihapus
+/// oleh request lain, sehingga transaksi sale ikut dibatalkan
+pub async fn claim_sale_draft(
+    draft_id: ObjectId,
+    user_id: ObjectId,
+    db: &Database,
+    session: &mut ClientSession,
+) -> Result<(), ServiceError> {
+    let collection: Collection<SaleDraft> = db.collection("sale_drafts");
+
+    collection
+        .find_one_and_delete(doc! { "_id": draft_id, "user_id": user_id })
+        .session(&mut *session)
+        .await
+        .map_err(transaction_error)?
+        .map(|_| ())
+        .ok_or_else(|| {
+            ServiceError::Conflict(format!(
+                "Draft dengan ID '{}' sudah difinalisasi atau dihapus",
+                draft_id
+            ))
+        })
+}
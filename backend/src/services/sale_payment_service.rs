@@ -0,0 +1,235 @@
+use crate::errors::ServiceError;
+use crate::models::money::Money;
+use crate::models::payment_method::PaymentMethod;
+use crate::models::sale::{Sale, SaleTender};
+use crate::models::sale_payment::{SalePayment, SalePaymentDTO};
+use crate::services::daily_closing_service::ensure_day_open;
+use crate::services::loyalty_service::record_payment_points;
+use crate::services::payment_method_service::get_active_payment_method_service;
+use crate::services::sale_service::payment_status;
+use crate::services::shift_service::find_open_shift;
+use crate::services::store_setting_service::find_store_setting;
+use crate::utils::{finish_transaction, format_rupiah, start_transaction, string_id_to_obj_id, transaction_error};
+use bson::datetime::DateTime as BsonDateTime;
+use bson::oid::ObjectId;
+use chrono::Utc;
+use futures::stream::TryStreamExt;
+use mongodb::{ClientSession, Collection, Database, bson::doc};
+
+pub async fn get_sale_payments_service(
+    sale_id: &str,
+    db: &Database,
+    user_id: &str,
+) -> Result<Vec<SalePayment>, ServiceError> {
+    let user_id = match string_id_to_obj_id(user_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let sale_id = match string_id_to_obj_id(sale_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let collection: Collection<SalePayment> = db.collection("sale_payments");
+
+    let mut cursor = collection
+        .find(doc! { "sale_id": sale_id, "user_id": user_id })
+        .sort(doc! { "paid_at": 1 })
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+
+    let mut payments: Vec<SalePayment> = Vec::new();
+
+    while let Some(payment) = cursor
+        .try_next()
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?
+    {
+        payments.push(payment);
+    }
+
+    Ok(payments)
+}
+
+pub async fn create_sale_payment_service(
+    sale_id: &str,
+    payload: SalePaymentDTO,
+    db: &Database,
+    user_id: &str,
+) -> Result<(Sale, SalePayment), ServiceError> {
+    let user_id = match string_id_to_obj_id(user_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let sale_id = match string_id_to_obj_id(sale_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let payment_method = get_active_payment_method_service(&payload.payment_method_id, db).await?;
+
+    // Insert ledger dan update saldo sale harus terjadi bersamaan
+    let mut session = start_transaction(db).await?;
+    let result =
+        insert_payment_and_update_sale(sale_id, payload, payment_method, user_id, db, &mut session)
+            .await;
+    finish_transaction(&mut session, result).await
+}
+
+async fn insert_payment_and_update_sale(
+    sale_id: ObjectId,
+    payload: SalePaymentDTO,
+    payment_method: PaymentMethod,
+    user_id: ObjectId,
+    db: &Database,
+    session: &mut ClientSession,
+) -> Result<(Sale, SalePayment), ServiceError> {
+    let sale_collection: Collection<Sale> = db.collection("sales");
+
+    let sale = sale_collection
+        .find_one(doc! { "_id": sale_id, "user_id": user_id })
+        .session(&mut *session)
+        .await
+        .map_err(transaction_error)?
+        .ok_or_else(|| {
+            ServiceError::NotFound(format!("Sale dengan ID '{}' tidak ditemukan", sale_id))
+        })?;
+
+    if sale.status == "voided" {
+        return Err(ServiceError::BadRequest(
+            "Sale sudah di-void, tidak bisa menerima pembayaran".into(),
+        ));
+    }
+
+    if !sale.remaining_amount.is_positive() {
+        return Err(ServiceError::BadRequest("Sale sudah lunas".into()));
+    }
+
+    if payload.amount > sale.remaining_amount {
+        return Err(ServiceError::BadRequest(format!(
+            "Jumlah bayar melebihi sisa tagihan ({})",
+            format_rupiah(sale.remaining_amount)
+        )));
+    }
+
+    let now = BsonDateTime::from_chrono(Utc::now());
+
+    // Cicilan masuk ke Z-report tanggal diterimanya, tanggal itu harus belum ditutup
+    let setting = find_store_setting(user_id, db).await?;
+    ensure_day_open(user_id, now.to_chrono(), &setting, db, session).await?;
+
+    // Uang cicilan masuk ke laci shift yang sedang terbuka (jika ada)
+    let shift_id = find_open_shift(user_id, db, session).await?.and_then(|s| s.id);
+
+    let payment = SalePayment {
+        id: None,
+        user_id,
+        sale_id,
+        payment_method: payment_method.clone(),
+        amount: payload.amount,
+        paid_at: now,
+        notes: payload.notes,
+        created_at: Some(now),
+    };
+
+    let collection: Collection<SalePayment> = db.collection("sale_payments");
+    let insert_result = collection
+        .insert_one(&payment)
+        .session(&mut *session)
+        .await
+        .map_err(transaction_error)?;
+    let payment = SalePayment {
+        id: insert_result.inserted_id.as_object_id(),
+        ..payment
+    };
+
+    // Cicilan juga dicatat sebagai tender supaya total per metode ikut terhitung,
+    // ditandai dengan payment_id supaya bisa dibedakan dari pembayaran saat sale.
+    // Sale lama belum punya tenders: tender sintetisnya hanya berisi pembayaran saat sale
+    let mut tenders = if sale.tenders.is_empty() {
+        let previous = installment_total(sale_id, payment.id, db, session).await?;
+        sale.sale_time_tenders(previous)
+    } else {
+        sale.tenders.clone()
+    };
+    tenders.push(SaleTender {
+        payment_method,
+        amount: payload.amount,
+        change_amount: Money::ZERO,
+        shift_id,
+        payment_id: payment.id,
+    });
+    let tenders_bson =
+        bson::to_bson(&tenders).map_err(|e| ServiceError::Unexpected(e.to_string()))?;
+    let paid_amount = sale.paid_amount + payload.amount;
+    let remaining_amount = sale.remaining_amount - payload.amount;
+    let status = payment_status(paid_amount, remaining_amount);
+
+    // Filter saldo lama: kalau sale berubah di tengah jalan, pembayaran ditolak
+    let update_result = sale_collection
+        .update_one(
+            doc! {
+                "_id": sale_id,
+                "user_id": user_id,
+                "paid_amount": sale.paid_amount,
+                "remaining_amount": sale.remaining_amount,
+            },
+            doc! { "$set": {
+                "tenders": tenders_bson,
+                "paid_amount": paid_amount,
+                "remaining_amount": remaining_amount,
+                "status": &status,
+                "updated_at": now,
+            } },
+        )
+        .session(&mut *session)
+        .await
+        .map_err(transaction_error)?;
+
+    if update_result.matched_count == 0 {
+        return Err(ServiceError::Conflict(
+            "Sale sedang diubah oleh proses lain, silakan coba lagi".into(),
+        ));
+    }
+
+    let mut sale = Sale {
+        tenders,
+        paid_amount,
+        remaining_amount,
+        status,
+        updated_at: Some(now),
+        ..sale
+    };
+    sale.points_earned = record_payment_points(&sale, &setting, db, session).await?;
+
+    Ok((sale, payment))
+}
+
+/// Total cicilan sale selain `except` (cicilan yang baru saja dicatat)
+async fn installment_total(
+    sale_id: ObjectId,
+    except: Option<ObjectId>,
+    db: &Database,
+    session: &mut ClientSession,
+) -> Result<Money, ServiceError> {
+    let collection: Collection<SalePayment> = db.collection("sale_payments");
+    let mut cursor = collection
+        .find(doc! { "sale_id": sale_id, "_id": { "$ne": except } })
+        .session(&mut *session)
+        .await
+        .map_err(transaction_error)?;
+
+    let mut total = Money::ZERO;
+    while let Some(payment) = cursor
+        .next(&mut *session)
+        .await
+        .transpose()
+        .map_err(transaction_error)?
+    {
+        total += payment.amount;
+    }
+
+    Ok(total)
+}
@@ -0,0 +1,246 @@
+use crate::errors::ServiceError;
+use crate::models::money::Money;
+use crate::models::product::Product;
+use crate::models::sale::Sale;
+use crate::models::sale_return::{SaleReturn, SaleReturnDTO, SaleReturnItem};
+use crate::services::daily_closing_service::ensure_day_open;
+use crate::services::product_service::next_catalog_seq;
+use crate::services::sale_service::payment_status;
+use crate::services::shift_service::find_open_shift;
+use crate::services::store_setting_service::find_store_setting;
+use crate::utils::{finish_transaction, start_transaction, string_id_to_obj_id, transaction_error};
+use bson::datetime::DateTime as BsonDateTime;
+use bson::oid::ObjectId;
+use chrono::Utc;
+use futures::stream::TryStreamExt;
+use mongodb::{ClientSession, Collection, Database, bson::doc};
+
+pub async fn get_sale_returns_service(
+    sale_id: &str,
+    db: &Database,
+    user_id: &str,
+) -> Result<Vec<SaleReturn>, ServiceError> {
+    let user_id = match string_id_to_obj_id(user_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let sale_id = match string_id_to_obj_id(sale_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let collection: Collection<SaleReturn> = db.collection("sale_returns");
+
+    let mut cursor = collection
+        .find(doc! { "sale_id": sale_id, "user_id": user_id })
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+
+    let mut returns: Vec<SaleReturn> = Vec::new();
+
+    while let Some(sale_return) = cursor
+        .try_next()
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?
+    {
+        returns.push(sale_return);
+    }
+
+    Ok(returns)
+}
+
+pub async fn create_sale_return_service(
+    sale_id: &str,
+    payload: SaleReturnDTO,
+    db: &Database,
+    user_id: &str,
+) -> Result<SaleReturn, ServiceError> {
+    let user_id = match string_id_to_obj_id(user_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let sale_id = match string_id_to_obj_id(sale_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    // Update sale, pengembalian stok dan insert retur dalam satu transaksi
+    let mut session = start_transaction(db).await?;
+    let result = insert_return_with_stock(sale_id, payload, user_id, db, &mut session).await;
+    finish_transaction(&mut session, result).await
+}
+
+async fn insert_return_with_stock(
+    sale_id: ObjectId,
+    payload: SaleReturnDTO,
+    user_id: ObjectId,
+    db: &Database,
+    session: &mut ClientSession,
+) -> Result<SaleReturn, ServiceError> {
+    let sale_collection: Collection<Sale> = db.collection("sales");
+
+    // Retur masuk ke Z-report tanggal returnya, tanggal itu harus belum ditutup
+    let setting = find_store_setting(user_id, db).await?;
+    ensure_day_open(user_id, Utc::now(), &setting, db, session).await?;
+
+    let mut sale = sale_collection
+        .find_one(doc! { "_id": sale_id, "user_id": user_id })
+        .session(&mut *session)
+        .await
+        .map_err(transaction_error)?
+        .ok_or_else(|| {
+            ServiceError::NotFound(format!("Sale dengan ID '{}' tidak ditemukan", sale_id))
+        })?;
+
+    if sale.status == "voided" {
+        return Err(ServiceError::BadRequest(
+            "Sale sudah di-void, tidak bisa diretur".into(),
+        ));
+    }
+
+    let mut return_items: Vec<SaleReturnItem> = Vec::new();
+    let mut return_amount = Money::ZERO;
+
+    for item_dto in &payload.items {
+        // Produk yang sama bisa ada di beberapa baris sale, alokasikan qty berurutan
+        let mut remaining = item_dto.quantity;
+
+        for index in 0..sale.items.len() {
+            let sale_item = &sale.items[index];
+            if sale_item.product_id != item_dto.product_id {
+                continue;
+            }
+
+            let returnable = sale_item.quantity - sale_item.returned_quantity;
+            let qty = remaining.min(returnable);
+            if qty <= 0 {
+                continue;
+            }
+
+            // Refund memakai nominal bersih dari snapshot sale (diskon & pajak)
+            let price = sale.paid_amount_for(sale_item, 1);
+            let subtotal = sale.paid_amount_for(sale_item, qty);
+            return_amount += subtotal;
+            remaining -= qty;
+
+            let sale_item = &mut sale.items[index];
+            sale_item.returned_quantity += qty;
+
+            return_items.push(SaleReturnItem {
+                product_id: sale_item.product_id,
+                product_name: sale_item.product_name.clone(),
+                sku: sale_item.sku.clone(),
+                quantity: qty,
+                price,
+                subtotal,
+            });
+        }
+
+        if remaining == item_dto.quantity
+            && !sale
+                .items
+                .iter()
+                .any(|i| i.product_id == item_dto.product_id)
+        {
+            return Err(ServiceError::BadRequest(format!(
+                "Produk '{}' tidak ada di sale ini",
+                item_dto.product_id
+            )));
+        }
+
+        if remaining > 0 {
+            return Err(ServiceError::BadRequest(format!(
+                "Jumlah retur produk '{}' melebihi jumlah yang terjual (sisa bisa diretur: {})",
+                item_dto.product_id,
+                item_dto.quantity - remaining
+            )));
+        }
+    }
+
+    // Sale yang belum lunas: retur mengurangi tagihan dulu, uang hanya dikembalikan
+    // sebesar kelebihannya supaya tidak ada refund untuk uang yang belum diterima
+    let credited_amount = return_amount.min(sale.remaining_amount.max(Money::ZERO));
+    let refund_amount = return_amount - credited_amount;
+    let remaining_amount = sale.remaining_amount - credited_amount;
+
+    // Refund diambil dari laci hanya sebesar uang tunai sale yang belum dikembalikan,
+    // sisanya dikembalikan lewat metode pembayaran non-tunai aslinya
+    let cash_available = (sale.cash_paid_amount() - sale.refunded_amount).max(Money::ZERO);
+    let cash_refund_amount = refund_amount.min(cash_available);
+    let shift_id = find_open_shift(user_id, db, session).await?.and_then(|s| s.id);
+
+    let now = BsonDateTime::from_chrono(Utc::now());
+
+    let items_bson =
+        bson::to_bson(&sale.items).map_err(|e| ServiceError::Unexpected(e.to_string()))?;
+
+    sale_collection
+        .update_one(
+            doc! { "_id": sale_id, "user_id": user_id },
+            doc! {
+                "$set": {
+                    "items": items_bson,
+                    "remaining_amount": remaining_amount,
+                    "status": payment_status(sale.paid_amount, remaining_amount),
+                    "updated_at": now,
+                },
+                "$inc": { "refunded_amount": refund_amount },
+            },
+        )
+        .session(&mut *session)
+        .await
+        .map_err(transaction_error)?;
+
+    let product_collection: Collection<Product> = db.collection("products");
+    let change_seq = next_catalog_seq(user_id, db, session).await?;
+
+    for item in &return_items {
+        let result = product_collection
+            .update_one(
+                doc! { "_id": item.product_id, "user_id": user_id },
+                doc! {
+                    "$inc": { "stock": item.quantity },
+                    "$set": { "updated_at": now, "change_seq": change_seq },
+                },
+            )
+            .session(&mut *session)
+            .await
+            .map_err(transaction_error)?;
+
+        if result.matched_count == 0 {
+            log::warn!(
+                "Produk {} sudah dihapus, stok retur tidak dikembalikan untuk sale {}",
+                item.product_id,
+                sale_id
+            );
+        }
+    }
+
+    let sale_return = SaleReturn {
+        id: None,
+        user_id,
+        sale_id,
+        items: return_items,
+        return_amount,
+        credited_amount,
+        refund_amount,
+        cash_refund_amount: Some(cash_refund_amount),
+        shift_id,
+        reason: payload.reason,
+        created_at: Some(now),
+    };
+
+    let collection: Collection<SaleReturn> = db.collection("sale_returns");
+    let insert_result = collection
+        .insert_one(&sale_return)
+        .session(&mut *session)
+        .await
+        .map_err(transaction_error)?;
+
+    Ok(SaleReturn {
+        id: insert_result.inserted_id.as_object_id(),
+        ..sale_return
+    })
+}
@@ -1,26 +1,88 @@
+use crate::models::money::Money;
+use crate::models::pagination::{Pagination, page_and_limit};
 use crate::models::product::Product;
-use log::error;
 use crate::errors::ServiceError;
 use futures::stream::TryStreamExt;
-use crate::utils::{handle_duplicate_key_error, string_id_to_obj_id};
+use crate::utils::{
+    finish_transaction, format_rupiah, handle_duplicate_key_error, start_transaction, string_id_to_obj_id,
+    transaction_error,
+};
 use bson::datetime::DateTime as BsonDateTime;
-use chrono::Utc;
-use mongodb::{Collection, Database, bson::doc};
-use crate::models::sale::{Sale, SaleItem, SaleDTO};
-use crate::models::payment_method::{PaymentMethod};
+use bson::oid::ObjectId;
+use chrono::{DateTime, NaiveDate, Utc};
+use mongodb::{
+    ClientSession, Collection, Database,
+    bson::{Document, doc, from_document},
+    options::ReturnDocument,
+};
+use crate::models::sale::{
+    Discount, DiscountDTO, DiscountType, PaymentMethodTotal, Sale, SaleDTO, SaleItem,
+    SaleListQuery, SaleSummary, SaleTender, SaleVoid, TenderDTO, VoidSaleDTO,
+};
+use crate::models::store_setting::StoreSetting;
+use crate::services::customer_service::find_customer;
+use crate::services::daily_closing_service::ensure_day_open;
+use crate::services::invoice_service::next_invoice_number;
+use crate::services::loyalty_service::{
+    points_for_paid_amount, points_tender, record_sale_points, reverse_sale_points,
+};
+use crate::services::payment_method_service::get_active_payment_method_service;
+use crate::services::product_service::next_catalog_seq;
+use crate::services::idempotency_service::insert_idempotency_key;
+use crate::services::sale_draft_service::claim_sale_draft;
+use crate::services::promotion_service::{apply_promotions, find_active_promotions};
+use crate::services::shift_service::find_open_shift;
+use crate::services::store_setting_service::find_store_setting;
+use crate::services::voucher_service::{
+    record_voucher_redemption, redeem_voucher, release_sale_voucher,
+};
 
-pub async fn get_sales_service(db: &Database, id:&str) -> Result<Vec<Sale>, ServiceError>{
+const SALE_STATUSES: [&str; 4] = ["paid", "partial", "unpaid", "voided"];
+const SALE_SORT_FIELDS: [&str; 4] = ["sale_date", "total_amount", "invoice_number", "created_at"];
+
+pub async fn get_sales_service(
+    db: &Database,
+    id: &str,
+    query: SaleListQuery,
+) -> Result<(Vec<Sale>, Pagination), ServiceError> {
     let user_id = match string_id_to_obj_id(id) {
         Some(oid) => oid,
         None => return Err(ServiceError::InvalidId("Invalid ID".into())),
     };
     let collection: Collection<Sale> = db.collection("sales");
 
+    let setting = find_store_setting(user_id, db).await?;
+    let filter = build_sales_filter(user_id, &query, &setting)?;
+
+    let sort_by = query.sort_by.as_deref().unwrap_or("sale_date");
+    if !SALE_SORT_FIELDS.contains(&sort_by) {
+        return Err(ServiceError::BadRequest(format!(
+            "sort_by harus salah satu dari: {}",
+            SALE_SORT_FIELDS.join(", ")
+        )));
+    }
+    let direction = match query.sort_dir.as_deref().unwrap_or("desc") {
+        "asc" => 1,
+        "desc" => -1,
+        _ => return Err(ServiceError::BadRequest("sort_dir harus asc atau desc".into())),
+    };
+
+    let (page, limit) = page_and_limit(query.page, query.limit);
+
+    let total = collection
+        .count_documents(filter.clone())
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+
+    // _id sebagai penentu urutan kedua supaya paging stabil untuk nilai yang sama
     let mut cursor = collection
-        .find(doc! {"user_id":user_id})
+        .find(filter)
+        .sort(doc! { sort_by: direction, "_id": direction })
+        .skip((page - 1) * limit)
+        .limit(limit as i64)
         .await
         .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
-    
+
     let mut sales: Vec<Sale> = Vec::new();
 
     while let Some(sale) = cursor
@@ -31,14 +93,196 @@ pub async fn get_sales_service(db: &Database, id:&str) -> Result<Vec<Sale>, Serv
         sales.push(sale);
     }
 
-    Ok(sales)
+    Ok((sales, Pagination::new(page, limit, total)))
+}
+
+fn build_sales_filter(
+    user_id: ObjectId,
+    query: &SaleListQuery,
+    setting: &StoreSetting,
+) -> Result<Document, ServiceError> {
+    let mut filter = doc! { "user_id": user_id };
+
+    // Batas hari mengikuti zona waktu toko, date_to inklusif
+    let mut sale_date = doc! {};
+    if let Some(date_from) = &query.date_from {
+        let (start, _) = setting.business_day_bounds(parse_query_date(date_from)?);
+        sale_date.insert("$gte", BsonDateTime::from_chrono(start));
+    }
+    if let Some(date_to) = &query.date_to {
+        let (_, end) = setting.business_day_bounds(parse_query_date(date_to)?);
+        sale_date.insert("$lt", BsonDateTime::from_chrono(end));
+    }
+    if !sale_date.is_empty() {
+        filter.insert("sale_date", sale_date);
+    }
+
+    if let Some(status) = &query.status {
+        if !SALE_STATUSES.contains(&status.as_str()) {
+            return Err(ServiceError::BadRequest(format!(
+                "status harus salah satu dari: {}",
+                SALE_STATUSES.join(", ")
+            )));
+        }
+        filter.insert("status", status);
+    }
+
+    if let Some(payment_method_id) = &query.payment_method_id {
+        let payment_method_id = match string_id_to_obj_id(payment_method_id) {
+            Some(oid) => oid.to_hex(),
+            None => return Err(ServiceError::InvalidId("Invalid payment method ID".into())),
+        };
+        // _id metode pembayaran tersimpan sebagai string hex; sale lama pakai payment_method
+        filter.insert(
+            "$or",
+            vec![
+                doc! { "tenders.payment_method._id": &payment_method_id },
+                doc! { "payment_method._id": &payment_method_id },
+            ],
+        );
+    }
+
+    if let Some(shift_id) = &query.shift_id {
+        match string_id_to_obj_id(shift_id) {
+            Some(oid) => filter.insert("shift_id", oid),
+            None => return Err(ServiceError::InvalidId("Invalid shift ID".into())),
+        };
+    }
+
+    if let Some(customer_id) = &query.customer_id {
+        match string_id_to_obj_id(customer_id) {
+            Some(oid) => filter.insert("customer_id", oid),
+            None => return Err(ServiceError::InvalidId("Invalid customer ID".into())),
+        };
+    }
+
+    if let Some(invoice) = query.invoice.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+        filter.insert(
+            "invoice_number",
+            doc! { "$regex": regex::escape(invoice), "$options": "i" },
+        );
+    }
+
+    Ok(filter)
+}
+
+/// Tanggal dari query string, format YYYY-MM-DD
+pub fn parse_query_date(value: &str) -> Result<NaiveDate, ServiceError> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| {
+        ServiceError::BadRequest(format!("Format tanggal '{}' harus YYYY-MM-DD", value))
+    })
 }
 
+pub async fn get_sale_service(
+    sale_id: &str,
+    db: &Database,
+    user_id: &str,
+) -> Result<Sale, ServiceError> {
+    let user_id = match string_id_to_obj_id(user_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let sale_id = match string_id_to_obj_id(sale_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let collection: Collection<Sale> = db.collection("sales");
+
+    // Filter user_id supaya sale milik user lain dianggap tidak ada (404)
+    let filter = doc! {
+        "_id": sale_id,
+        "user_id": user_id,
+    };
+
+    let sale = collection
+        .find_one(filter)
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+
+    sale.ok_or_else(|| {
+        ServiceError::NotFound(format!("Sale dengan ID '{}' tidak ditemukan", sale_id))
+    })
+}
+
+const MAX_TRANSACTION_ATTEMPTS: u32 = 3;
+
+/// Sale yang dicatat perangkat POS saat offline lalu dikirim lewat sinkronisasi
+pub struct OfflineSale {
+    pub client_id: String,
+    pub sale_date: DateTime<Utc>,
+}
+
+/// Asal sale, menentukan data yang ikut dibaca/ditulis di dalam transaksi sale
+enum SaleSource<'a> {
+    Direct,
+    Offline(&'a OfflineSale),
+    Draft(ObjectId), // draft dihapus dalam transaksi yang sama dengan insert sale
+    Idempotent(&'a IdempotentRequest), // key dan sale_id ditulis bersama sale
+}
+
+/// Idempotency-Key request POST /sales beserta hash payload-nya
+pub struct IdempotentRequest {
+    pub key: String,
+    pub request_hash: String,
+}
+
+impl SaleSource<'_> {
+    fn offline(&self) -> Option<&OfflineSale> {
+        match self {
+            SaleSource::Offline(offline) => Some(offline),
+            _ => None,
+        }
+    }
+}
 
 pub async fn create_sale_service(
     payload: SaleDTO,
     db: &Database,
     id: &str,
+) -> Result<Sale, ServiceError> {
+    create_sale(payload, SaleSource::Direct, db, id).await
+}
+
+/// Sale dari draft: draft diklaim (dihapus) di dalam transaksi sale, sehingga finalisasi
+/// ganda untuk draft yang sama gagal dan tidak membuat dua sale
+pub async fn create_sale_from_draft_service(
+    payload: SaleDTO,
+    draft_id: ObjectId,
+    db: &Database,
+    id: &str,
+) -> Result<Sale, ServiceError> {
+    create_sale(payload, SaleSource::Draft(draft_id), db, id).await
+}
+
+/// Sale dengan Idempotency-Key: key disimpan di dalam transaksi sale, jadi key hanya ada
+/// jika sale-nya ada, dan request kedua dengan key yang sama gagal di index unik
+pub async fn create_idempotent_sale_service(
+    payload: SaleDTO,
+    request: &IdempotentRequest,
+    db: &Database,
+    id: &str,
+) -> Result<Sale, ServiceError> {
+    create_sale(payload, SaleSource::Idempotent(request), db, id).await
+}
+
+/// Sale offline melewati aturan harga, stok dan pembayaran yang sama dengan sale biasa,
+/// hanya tanggalnya memakai waktu di perangkat dan `client_id` disimpan untuk deteksi duplikat
+pub async fn create_offline_sale_service(
+    payload: SaleDTO,
+    offline: OfflineSale,
+    db: &Database,
+    id: &str,
+) -> Result<Sale, ServiceError> {
+    create_sale(payload, SaleSource::Offline(&offline), db, id).await
+}
+
+async fn create_sale(
+    mut payload: SaleDTO,
+    source: SaleSource<'_>,
+    db: &Database,
+    id: &str,
 ) -> Result<Sale, ServiceError> {
     let user_id = match string_id_to_obj_id(id) {
         Some(oid) => oid,
@@ -48,136 +292,921 @@ pub async fn create_sale_service(
     if payload.items.is_empty() {
         return Err(ServiceError::BadRequest("Items tidak boleh kosong".into()));
     }
+    normalize_legacy_payment(&mut payload)?;
+
+    // customer_id harus pelanggan milik toko ini
+    let customer = match payload.customer_id {
+        Some(customer_id) => Some(find_customer(customer_id, user_id, db).await?),
+        None => None,
+    };
     
-    if payload.paid_amount < 0.0 {
-        return Err(ServiceError::BadRequest("Paid amount tidak boleh negatif".into()));
+    // Setiap metode pembayaran di split tender harus aktif
+    let mut tenders: Vec<SaleTender> = Vec::new();
+    for tender in &payload.tenders {
+        if !tender.amount.is_positive() {
+            return Err(ServiceError::BadRequest("Jumlah bayar harus lebih dari 0".into()));
+        }
+
+        tenders.push(SaleTender {
+            payment_method: get_active_payment_method_service(&tender.payment_method_id, db)
+                .await?,
+            amount: tender.amount,
+            change_amount: Money::ZERO,
+            shift_id: None,
+            payment_id: None,
+        });
     }
-    
+
+    // Harga, pengecekan stok, pengurangan stok dan insert sale
+    // dijalankan dalam satu transaksi supaya tidak ada partial write
+    let setting = find_store_setting(user_id, db).await?;
+
+    // Penukaran poin dicatat sebagai tender non-tunai senilai poinnya
+    if let Some(points) = payload.redeem_points {
+        let Some(customer) = &customer else {
+            return Err(ServiceError::BadRequest(
+                "Penukaran poin membutuhkan customer_id".into(),
+            ));
+        };
+        if !setting.loyalty_enabled {
+            return Err(ServiceError::BadRequest(
+                "Program poin loyalti belum diaktifkan".into(),
+            ));
+        }
+        // Cek saldo sebelum dikalikan nilai poin; saldo per lot dicek lagi di dalam transaksi
+        if points > customer.loyalty_points {
+            return Err(ServiceError::BadRequest(format!(
+                "Poin pelanggan tidak mencukupi (tersedia {} poin)",
+                customer.loyalty_points.max(0)
+            )));
+        }
+        tenders.push(points_tender(&setting, points)?);
+    }
+
+    // Nominal dari klien dibatasi supaya total, kembalian dan pajak tidak bisa overflow
+    Money::checked_sum(tenders.iter().map(|t| t.amount)).ok_or_else(amount_limit_error)?;
+
+    let mut attempt = 1;
+    loop {
+        let mut session = start_transaction(db).await?;
+        let result = insert_sale_with_stock(
+            &payload,
+            &source,
+            tenders.clone(),
+            &setting,
+            user_id,
+            db,
+            &mut session,
+        )
+        .await;
+
+        match finish_transaction(&mut session, result).await {
+            // Bentrok dengan transaksi lain (stok/counter invoice yang sama), ulangi dari awal
+            Err(ServiceError::TransactionConflict(msg)) if attempt < MAX_TRANSACTION_ATTEMPTS => {
+                log::warn!("Transaksi sale bentrok (percobaan {}): {}", attempt, msg);
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+async fn insert_sale_with_stock(
+    payload: &SaleDTO,
+    source: &SaleSource<'_>,
+    mut tenders: Vec<SaleTender>,
+    setting: &StoreSetting,
+    user_id: ObjectId,
+    db: &Database,
+    session: &mut ClientSession,
+) -> Result<Sale, ServiceError> {
+    if let SaleSource::Draft(draft_id) = source {
+        claim_sale_draft(*draft_id, user_id, db, session).await?;
+    }
+
+    let offline = source.offline();
     let product_collection: Collection<Product> = db.collection("products");
     let mut sale_items: Vec<SaleItem> = Vec::new();
-    let mut total_amount = 0.0;
-    
+    let mut categories: Vec<Option<ObjectId>> = Vec::new();
+
+    let now = BsonDateTime::from_chrono(Utc::now());
+    let sale_date = offline.map(|o| o.sale_date).unwrap_or_else(|| now.to_chrono());
+
+    // Total qty per produk (produk yang sama bisa muncul di beberapa baris)
+    let mut requested: Vec<(Product, i32)> = Vec::new();
+
     for item_dto in &payload.items {
-        let filter = doc! { "_id": item_dto.product_id, "user_id": &user_id };
-        
+        let filter = doc! { "_id": item_dto.product_id, "user_id": user_id };
+
         // Ambil detail produk dari DB
         let product = product_collection
             .find_one(filter)
+            .session(&mut *session)
             .await
-            .map_err(|e| ServiceError::DatabaseError(e.to_string()))?
+            .map_err(transaction_error)?
             .ok_or_else(|| ServiceError::NotFound("Produk tidak ditemukan".to_string()))?;
-        
+
         let actual_price = product.price;
-        
-        let subtotal = actual_price * item_dto.quantity as f64;
-        total_amount += subtotal;
-        
+        let gross = actual_price
+            .checked_mul(item_dto.quantity as i64)
+            .ok_or_else(amount_limit_error)?;
+
+        let discount = match &item_dto.discount {
+            Some(discount) => Some(apply_discount(discount, gross, &product.name)?),
+            None => None,
+        };
+        let discount_amount = discount.as_ref().map(|d| d.amount).unwrap_or_default();
+
+        // Tarif per produk menimpa tarif toko, produk bebas pajak selalu 0
+        let tax_rate = if !setting.tax_enabled || product.tax_exempt {
+            0.0
+        } else {
+            product.tax_rate.unwrap_or(setting.tax_rate)
+        };
+
         sale_items.push(SaleItem {
-            product_id: item_dto.product_id.clone(),
-            product_name: product.name,
-            sku: product.sku,
+            product_id: item_dto.product_id,
+            product_name: product.name.clone(),
+            sku: product.sku.clone(),
             quantity: item_dto.quantity,
             price: product.price,
-            subtotal
+            subtotal: gross - discount_amount,
+            discount,
+            promotion: None,
+            tax_rate,
+            tax_amount: Money::ZERO,
+            returned_quantity: 0,
         });
+        categories.push(product.category_id);
+
+        match requested.iter_mut().find(|(p, _)| p.id == product.id) {
+            Some((_, qty)) => *qty = qty.saturating_add(item_dto.quantity),
+            None => requested.push((product, item_dto.quantity)),
+        }
     }
 
-    let payment_method: Option<PaymentMethod>;
-    let pm_collection: Collection<PaymentMethod> = db.collection("payment_methods");
+    // Qty yang melebihi stok ditolak sebelum promo dihitung
+    validate_stock_availability(&requested)?;
 
-    let payment_method = match &payload.payment_method_id {
-        Some(payment_method_id) => {
-            let found_method = pm_collection
-                .find_one(doc! { "_id": payment_method_id.clone() })
-                .await
-                .map_err(|e| ServiceError::DatabaseError(e.to_string()))?
-                .ok_or_else(|| ServiceError::NotFound(format!(
-                    "Metode Pembayaran dengan ID '{}' tidak ditemukan",
-                    payment_method_id
-                )))?;
-
-            if !found_method.is_active {
-                return Err(ServiceError::BadRequest(
-                    "Metode Pembayaran sedang tidak aktif".to_string(),
-                ));
-            }
+    // Promo dicek pada tanggal sale, termasuk untuk sale offline
+    let promotions = find_active_promotions(user_id, sale_date, db, session).await?;
+    apply_promotions(&mut sale_items, &categories, &promotions);
 
-            Some(found_method)
-        }
+    let subtotal_amount = Money::checked_sum(sale_items.iter().map(|i| i.subtotal))
+        .ok_or_else(amount_limit_error)?;
+    let mut total_discount: Money = sale_items
+        .iter()
+        .map(|i| {
+            i.discount.as_ref().map(|d| d.amount).unwrap_or_default()
+                + i.promotion.as_ref().map(|p| p.amount).unwrap_or_default()
+        })
+        .sum();
+
+    // Diskon transaksi dihitung dari subtotal setelah diskon item
+    let discount = match &payload.discount {
+        Some(discount) => Some(apply_discount(discount, subtotal_amount, "transaksi")?),
+        None => None,
+    };
+    let order_discount = discount.as_ref().map(|d| d.amount).unwrap_or_default();
+
+    // Voucher dihitung dari belanja setelah diskon transaksi, kuotanya terpakai di transaksi ini
+    let voucher = match &payload.voucher_code {
+        Some(code) => Some(
+            redeem_voucher(
+                code,
+                payload.customer_id,
+                subtotal_amount - order_discount,
+                sale_date,
+                user_id,
+                db,
+                session,
+            )
+            .await?,
+        ),
         None => None,
     };
+    let voucher_amount = voucher.as_ref().map(|v| v.amount).unwrap_or_default();
+
+    total_discount += order_discount + voucher_amount;
+    let net_amount = subtotal_amount - order_discount - voucher_amount;
+
+    let (tax_base, tax_amount) =
+        apply_tax(&mut sale_items, subtotal_amount, net_amount, setting.tax_inclusive);
+    let total_amount = if setting.tax_inclusive {
+        net_amount
+    } else {
+        net_amount + tax_amount
+    };
+
+    ensure_day_open(user_id, sale_date, setting, db, session).await?;
+
+    // Satu nomor perubahan katalog untuk semua produk di sale ini
+    let change_seq = next_catalog_seq(user_id, db, session).await?;
+    for (product, qty) in &requested {
+        // Filter stok >= qty menjaga agar stok tidak pernah minus walau ada request bersamaan
+        let result = product_collection
+            .update_one(
+                doc! { "_id": product.id, "user_id": user_id, "stock": { "$gte": qty } },
+                doc! {
+                    "$inc": { "stock": -qty },
+                    "$set": { "updated_at": now, "change_seq": change_seq },
+                },
+            )
+            .session(&mut *session)
+            .await
+            .map_err(transaction_error)?;
+
+        if result.matched_count == 0 {
+            return Err(ServiceError::BadRequest(format!(
+                "Stok tidak mencukupi: {} ({})",
+                product.name, product.sku
+            )));
+        }
+    }
+
+    let change_amount = allocate_change(&mut tenders, total_amount)?;
+
+    // Sale dan uang yang diterima masuk ke laci shift yang sedang terbuka (jika ada).
+    // Sale offline dari sebelum shift dibuka tidak ikut ke laci shift ini
+    let shift_id = find_open_shift(user_id, db, session)
+        .await?
+        .filter(|s| s.opened_at.to_chrono() <= sale_date)
+        .and_then(|s| s.id);
+    for tender in tenders.iter_mut() {
+        tender.shift_id = shift_id;
+    }
+
+    let invoice_number = next_invoice_number(setting, user_id, sale_date, db, session).await?;
 
     // let final_amount = total_amount;
-    let remaining_amount = total_amount - payload.paid_amount;
-    let now = BsonDateTime::from_chrono(Utc::now());
-    
+    let tendered: Money = tenders.iter().map(|t| t.amount).sum();
+    let paid_amount = tendered - change_amount;
+    let remaining_amount = (total_amount - paid_amount).max(Money::ZERO);
+
+    // Poin hanya didapat dari uang yang sudah diterima dan tidak dibayar dengan poin,
+    // sisa poin sale kredit ditambahkan saat cicilannya masuk
+    let points_redeemed = payload.redeem_points.unwrap_or_default();
+    let points_earned = match payload.customer_id {
+        Some(_) => points_for_paid_amount(setting, paid_amount, points_redeemed),
+        None => 0,
+    };
+
     let sale = Sale {
         id: None,
         user_id,
-        customer_id: payload.customer_id.clone(),
+        customer_id: payload.customer_id,
         items: sale_items,
+        subtotal_amount,
+        discount,
+        voucher,
+        total_discount,
+        tax_inclusive: setting.tax_inclusive,
+        tax_base,
+        tax_amount,
         total_amount,
-        paid_amount: payload.paid_amount,
+        paid_amount,
         remaining_amount,
-        status: if remaining_amount <= 0.0 {
-            "paid".to_string()
-        } else if payload.paid_amount > 0.0 {
-            "partial".to_string()
-        } else {
-            "unpaid".to_string()
-        },
-        invoice_number: None,
-        payment_method,
-        sale_date: Some(now.clone()),
+        change_amount,
+        status: payment_status(paid_amount, remaining_amount),
+        refunded_amount: Money::ZERO,
+        points_earned,
+        points_redeemed,
+        invoice_number: Some(invoice_number),
+        client_id: offline.map(|o| o.client_id.clone()),
+        tenders,
+        payment_method: None,
+        shift_id,
+        sale_date: Some(BsonDateTime::from_chrono(sale_date)),
         notes: payload.notes.clone(),
-        created_at: Some(now.clone()),
+        void: None,
+        created_at: Some(now),
         updated_at: Some(now),
     };
-    
+
     let collection: Collection<Sale> = db.collection("sales");
-    let result = collection.insert_one(&sale).await;
-    
+    let result = collection.insert_one(&sale).session(&mut *session).await;
+
     match result {
         Ok(insert_result) => {
-            Ok(Sale {
-                id: insert_result.inserted_id.as_object_id().map(|oid| oid.to_owned()),
+            let sale = Sale {
+                id: insert_result.inserted_id.as_object_id(),
                 ..sale
-            })
+            };
+            record_sale_points(&sale, setting, db, session).await?;
+            record_voucher_redemption(&sale, db, session).await?;
+            if let SaleSource::Idempotent(request) = source {
+                insert_idempotency_key(request, &sale, db, session).await?;
+            }
+            Ok(sale)
         }
         Err(e) => {
             if let Some(err) = handle_duplicate_key_error(&e) {
                 return Err(err);
             }
-            Err(ServiceError::DatabaseError(e.to_string()))
+            Err(transaction_error(e))
         }
     }
 }
 
+/// Payload format lama (`payment_method_id` + `paid_amount`) diubah menjadi satu tender.
+/// Ditolak jika dikirim bersama `tenders` atau hanya salah satunya yang diisi
+pub fn normalize_legacy_payment(payload: &mut SaleDTO) -> Result<(), ServiceError> {
+    let payment_method_id = payload.payment_method_id.take();
+    let paid_amount = payload.paid_amount.take();
+    if payment_method_id.is_none() && paid_amount.is_none() {
+        return Ok(());
+    }
+
+    if !payload.tenders.is_empty() {
+        return Err(ServiceError::BadRequest(
+            "Gunakan tenders atau payment_method_id/paid_amount, tidak keduanya".into(),
+        ));
+    }
+
+    match (payment_method_id, paid_amount) {
+        (_, None) => Err(ServiceError::BadRequest(
+            "payment_method_id harus dikirim bersama paid_amount".into(),
+        )),
+        (_, Some(amount)) if amount < Money::ZERO => Err(ServiceError::BadRequest(
+            "Jumlah bayar tidak boleh negatif".into(),
+        )),
+        // Belum bayar sama sekali
+        (_, Some(amount)) if amount == Money::ZERO => Ok(()),
+        (Some(payment_method_id), Some(amount)) => {
+            payload.tenders.push(TenderDTO {
+                payment_method_id,
+                amount,
+            });
+            Ok(())
+        }
+        (None, Some(_)) => Err(ServiceError::BadRequest(
+            "paid_amount harus dikirim bersama payment_method_id".into(),
+        )),
+    }
+}
+
+/// Status pembayaran sale berdasarkan jumlah yang sudah dibayar dan sisa tagihan
+pub fn payment_status(paid_amount: Money, remaining_amount: Money) -> String {
+    if !remaining_amount.is_positive() {
+        "paid".to_string()
+    } else if paid_amount.is_positive() {
+        "partial".to_string()
+    } else {
+        "unpaid".to_string()
+    }
+}
+
+fn amount_limit_error() -> ServiceError {
+    ServiceError::BadRequest(format!(
+        "Nominal transaksi melebihi batas {}",
+        format_rupiah(Money::MAX_AMOUNT)
+    ))
+}
+
+/// Kelebihan bayar dikembalikan sebagai kembalian dari tender tunai.
+/// Ditolak jika pembayaran non-tunai saja sudah melebihi total
+fn allocate_change(
+    tenders: &mut [SaleTender],
+    total_amount: Money,
+) -> Result<Money, ServiceError> {
+    let tendered: Money = tenders.iter().map(|t| t.amount).sum();
+    let change_amount = tendered - total_amount;
+    if !change_amount.is_positive() {
+        return Ok(Money::ZERO);
+    }
+
+    let cash: Money = tenders
+        .iter()
+        .filter(|t| t.payment_method.is_cash)
+        .map(|t| t.amount)
+        .sum();
+    if cash < change_amount {
+        return Err(ServiceError::BadRequest(format!(
+            "Jumlah bayar melebihi total ({}), kelebihan bayar hanya diperbolehkan untuk pembayaran tunai",
+            format_rupiah(total_amount)
+        )));
+    }
+
+    let mut remaining_change = change_amount;
+    for tender in tenders.iter_mut().filter(|t| t.payment_method.is_cash) {
+        let change = remaining_change.min(tender.amount);
+        tender.change_amount = change;
+        remaining_change -= change;
+    }
+
+    Ok(change_amount)
+}
+
+/// Hitung nominal diskon terhadap `base`, ditolak jika hasilnya melebihi base
+/// supaya subtotal/total tidak pernah minus
+fn apply_discount(
+    discount: &DiscountDTO,
+    base: Money,
+    target: &str,
+) -> Result<Discount, ServiceError> {
+    let amount = match (discount.discount_type, discount.percent, discount.amount) {
+        (DiscountType::Percent, Some(percent), None) => {
+            if !(0.0..=100.0).contains(&percent) {
+                return Err(ServiceError::BadRequest(format!(
+                    "Diskon persen untuk {} harus antara 0 dan 100",
+                    target
+                )));
+            }
+            base.percent(percent)
+        }
+        (DiscountType::Fixed, None, Some(amount)) => {
+            if amount < Money::ZERO || amount > base {
+                return Err(ServiceError::BadRequest(format!(
+                    "Diskon untuk {} tidak boleh melebihi {}",
+                    target,
+                    format_rupiah(base)
+                )));
+            }
+            amount
+        }
+        (DiscountType::Percent, _, _) => {
+            return Err(ServiceError::BadRequest(format!(
+                "Diskon persen untuk {} hanya memakai field percent",
+                target
+            )));
+        }
+        (DiscountType::Fixed, _, _) => {
+            return Err(ServiceError::BadRequest(format!(
+                "Diskon fixed untuk {} hanya memakai field amount (sen)",
+                target
+            )));
+        }
+    };
+
+    Ok(Discount {
+        discount_type: discount.discount_type,
+        percent: discount.percent,
+        amount,
+    })
+}
+
+/// Hitung pajak tiap item dari nilai bersihnya (setelah porsi diskon transaksi),
+/// mengembalikan total DPP dan total pajak
+fn apply_tax(
+    items: &mut [SaleItem],
+    subtotal_amount: Money,
+    net_amount: Money,
+    tax_inclusive: bool,
+) -> (Money, Money) {
+    let mut tax_base = Money::ZERO;
+    let mut tax_amount = Money::ZERO;
+    let mut allocated = Money::ZERO;
+    let last = items.len().saturating_sub(1);
+
+    for (index, item) in items.iter_mut().enumerate() {
+        // Sisa pembulatan masuk ke item terakhir supaya DPP + pajak tetap sama dengan total
+        let net = if !subtotal_amount.is_positive() {
+            item.subtotal
+        } else if index == last {
+            net_amount - allocated
+        } else {
+            item.subtotal
+                .mul_div(net_amount.minor(), subtotal_amount.minor())
+        };
+        allocated += net;
+
+        let (base, tax) = if tax_inclusive {
+            let base = net.scale(100.0 / (100.0 + item.tax_rate));
+            (base, net - base)
+        } else {
+            (net, net.percent(item.tax_rate))
+        };
+
+        item.tax_amount = tax;
+        tax_base += base;
+        tax_amount += tax;
+    }
+
+    (tax_base, tax_amount)
+}
+
+/// Cek stok semua produk sekaligus, error berisi daftar item yang stoknya kurang
+fn validate_stock_availability(requested: &[(Product, i32)]) -> Result<(), ServiceError> {
+    let shortages: Vec<String> = requested
+        .iter()
+        .filter(|(product, qty)| (product.stock as i64) < *qty as i64)
+        .map(|(product, qty)| {
+            format!(
+                "{} ({}): tersedia {}, diminta {}",
+                product.name, product.sku, product.stock, qty
+            )
+        })
+        .collect();
+
+    if shortages.is_empty() {
+        return Ok(());
+    }
+
+    Err(ServiceError::BadRequest(format!(
+        "Stok tidak mencukupi: {}",
+        shortages.join(" | ")
+    )))
+}
+
+/// Ringkasan omzet milik user, sale yang sudah di-void tidak ikut dihitung
+pub async fn get_sales_summary_service(
+    db: &Database,
+    id: &str,
+) -> Result<SaleSummary, ServiceError> {
+    let user_id = match string_id_to_obj_id(id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+    let collection: Collection<Sale> = db.collection("sales");
+
+    let pipeline = vec![
+        doc! { "$match": { "user_id": user_id, "status": { "$ne": "voided" } } },
+        doc! { "$group": {
+            "_id": null,
+            "transaction_count": { "$sum": 1 },
+            "total_amount": { "$sum": "$total_amount" },
+            "paid_amount": { "$sum": "$paid_amount" },
+            "remaining_amount": { "$sum": "$remaining_amount" },
+            "refunded_amount": { "$sum": "$refunded_amount" },
+            "total_discount": { "$sum": "$total_discount" },
+            "tax_amount": { "$sum": "$tax_amount" },
+            "change_amount": { "$sum": "$change_amount" },
+        } },
+    ];
+
+    let mut cursor = collection
+        .aggregate(pipeline)
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+
+    let mut summary = match cursor
+        .try_next()
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?
+    {
+        Some(document) => from_document(document)
+            .map_err(|e| ServiceError::DatabaseError(e.to_string()))?,
+        None => SaleSummary {
+            transaction_count: 0,
+            total_amount: Money::ZERO,
+            paid_amount: Money::ZERO,
+            remaining_amount: Money::ZERO,
+            refunded_amount: Money::ZERO,
+            total_discount: Money::ZERO,
+            tax_amount: Money::ZERO,
+            change_amount: Money::ZERO,
+            payment_methods: Vec::new(),
+        },
+    };
+
+    summary.payment_methods = get_payment_method_totals(&collection, user_id).await?;
+
+    Ok(summary)
+}
+
+/// Total pembayaran per metode dari semua tender sale (termasuk cicilan)
+async fn get_payment_method_totals(
+    collection: &Collection<Sale>,
+    user_id: ObjectId,
+) -> Result<Vec<PaymentMethodTotal>, ServiceError> {
+    let pipeline = vec![
+        doc! { "$match": { "user_id": user_id, "status": { "$ne": "voided" } } },
+        // Sale lama belum punya tenders, pakai payment_method + paid_amount
+        doc! { "$project": { "tenders": { "$cond": [
+            { "$gt": [ { "$size": { "$ifNull": [ "$tenders", [] ] } }, 0 ] },
+            "$tenders",
+            { "$cond": [
+                { "$and": [ { "$ifNull": [ "$payment_method", false ] }, { "$gt": [ "$paid_amount", 0 ] } ] },
+                [ {
+                    "payment_method": "$payment_method",
+                    "amount": "$paid_amount",
+                    "change_amount": { "$max": [ { "$subtract": [ "$paid_amount", "$total_amount" ] }, 0 ] },
+                } ],
+                [],
+            ] },
+        ] } } },
+        doc! { "$unwind": "$tenders" },
+        doc! { "$group": {
+            "_id": "$tenders.payment_method._id",
+            "name": { "$first": "$tenders.payment_method.name" },
+            "tendered_amount": { "$sum": "$tenders.amount" },
+            "change_amount": { "$sum": { "$ifNull": [ "$tenders.change_amount", 0 ] } },
+            "transaction_count": { "$sum": 1 },
+        } },
+        doc! { "$addFields": {
+            "amount": { "$subtract": [ "$tendered_amount", "$change_amount" ] },
+        } },
+        doc! { "$sort": { "amount": -1 } },
+    ];
+
+    let mut cursor = collection
+        .aggregate(pipeline)
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+
+    let mut totals: Vec<PaymentMethodTotal> = Vec::new();
+
+    while let Some(document) = cursor
+        .try_next()
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?
+    {
+        totals.push(
+            from_document(document).map_err(|e| ServiceError::DatabaseError(e.to_string()))?,
+        );
+    }
+
+    Ok(totals)
+}
+
+pub async fn void_sale_service(
+    sale_id: &str,
+    payload: VoidSaleDTO,
+    db: &Database,
+    user_id: &str,
+) -> Result<Sale, ServiceError> {
+    let user_id = match string_id_to_obj_id(user_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let sale_id = match string_id_to_obj_id(sale_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    // Update status dan pengembalian stok harus berhasil bersamaan
+    let mut session = start_transaction(db).await?;
+    let result = void_sale_with_stock(sale_id, payload, user_id, db, &mut session).await;
+    finish_transaction(&mut session, result).await
+}
+
+async fn void_sale_with_stock(
+    sale_id: ObjectId,
+    payload: VoidSaleDTO,
+    user_id: ObjectId,
+    db: &Database,
+    session: &mut ClientSession,
+) -> Result<Sale, ServiceError> {
+    let collection: Collection<Sale> = db.collection("sales");
+    let now = BsonDateTime::from_chrono(Utc::now());
+
+    // Void masuk ke Z-report tanggal void-nya, tanggal itu harus belum ditutup
+    let setting = find_store_setting(user_id, db).await?;
+    ensure_day_open(user_id, now.to_chrono(), &setting, db, session).await?;
+
+    let void = SaleVoid {
+        reason: payload.reason,
+        voided_by: user_id,
+        voided_at: now,
+    };
+    let void_doc = bson::to_bson(&void).map_err(|e| ServiceError::Unexpected(e.to_string()))?;
+
+    // Filter status != voided supaya void dua kali (termasuk request bersamaan) ditolak
+    let voided_sale = collection
+        .find_one_and_update(
+            doc! { "_id": sale_id, "user_id": user_id, "status": { "$ne": "voided" } },
+            doc! { "$set": { "status": "voided", "void": void_doc, "updated_at": now } },
+        )
+        .return_document(ReturnDocument::After)
+        .session(&mut *session)
+        .await
+        .map_err(transaction_error)?;
+
+    let sale = match voided_sale {
+        Some(sale) => sale,
+        None => {
+            let existing = collection
+                .find_one(doc! { "_id": sale_id, "user_id": user_id })
+                .session(&mut *session)
+                .await
+                .map_err(transaction_error)?;
+
+            return Err(match existing {
+                Some(_) => ServiceError::Conflict("Sale sudah di-void sebelumnya".into()),
+                None => ServiceError::NotFound(format!(
+                    "Sale dengan ID '{}' tidak ditemukan",
+                    sale_id
+                )),
+            });
+        }
+    };
+
+    // Void tidak mencatat refund, jadi uang yang sudah diretur atau dicicil tidak boleh
+    // ikut hilang dari laporan. Retur dan cicilan juga menulis dokumen sale ini, jadi
+    // yang masuk bersamaan dengan void ikut bentrok
+    ensure_voidable(&sale, db, session).await?;
+
+    if sale.points_earned > 0 || sale.points_redeemed > 0 {
+        reverse_sale_points(&sale, &setting, db, session).await?;
+    }
+    release_sale_voucher(&sale, db, session).await?;
 
-/* pub async fn validate_stock_availability(
-    db: &Database, 
-    items: &[SaleItemDTO],
-    user_id: &ObjectId
-) -> Result<(), ServiceError> {
     let product_collection: Collection<Product> = db.collection("products");
-    
-    for item in items {
-        let filter = doc! { "_id": item.product_id, "user_id": user_id };
-        let product = product_collection
-            .find_one(filter)
+    let change_seq = next_catalog_seq(user_id, db, session).await?;
+
+    for item in &sale.items {
+        let result = product_collection
+            .update_one(
+                doc! { "_id": item.product_id, "user_id": user_id },
+                doc! {
+                    "$inc": { "stock": item.quantity },
+                    "$set": { "updated_at": now, "change_seq": change_seq },
+                },
+            )
+            .session(&mut *session)
             .await
-            .map_err(|e| ServiceError::DatabaseError(e.to_string()))?
-            .ok_or_else(|| ServiceError::NotFound("Produk tidak ditemukan".to_string()))?;
-        
-        // Cek stock jika field stock ada di Product model
-        if let Some(stock) = product.stock {
-            if stock < item.quantity {
-                return Err(ServiceError::BadRequest(
-                    format!("Stok {} tidak mencukupi", product.name)
-                ));
-            }
+            .map_err(transaction_error)?;
+
+        if result.matched_count == 0 {
+            log::warn!(
+                "Produk {} sudah dihapus, stok tidak dikembalikan untuk sale {}",
+                item.product_id,
+                sale_id
+            );
         }
     }
-    
+
+    Ok(sale)
+}
+
+/// Sale yang sudah punya retur atau cicilan ditolak, batalkan lewat retur saja
+async fn ensure_voidable(
+    sale: &Sale,
+    db: &Database,
+    session: &mut ClientSession,
+) -> Result<(), ServiceError> {
+    if sale.refunded_amount > Money::ZERO || sale.items.iter().any(|i| i.returned_quantity > 0) {
+        return Err(ServiceError::Conflict(
+            "Sale yang sudah punya retur tidak bisa di-void, gunakan retur untuk sisa item".into(),
+        ));
+    }
+
+    let payments: Collection<Document> = db.collection("sale_payments");
+    let payment_count = payments
+        .count_documents(doc! { "sale_id": sale.id })
+        .session(&mut *session)
+        .await
+        .map_err(transaction_error)?;
+
+    if payment_count > 0 {
+        return Err(ServiceError::Conflict(format!(
+            "Sale sudah punya {} pembayaran cicilan dan tidak bisa di-void, gunakan retur",
+            payment_count
+        )));
+    }
+
     Ok(())
-} */
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mongodb::bson::oid::ObjectId;
+
+    fn item(subtotal: i64, tax_rate: f64) -> SaleItem {
+        SaleItem {
+            product_id: ObjectId::new(),
+            product_name: "Produk".into(),
+            sku: "SKU-1".into(),
+            quantity: 1,
+            price: Money::from_minor(subtotal),
+            subtotal: Money::from_minor(subtotal),
+            discount: None,
+            promotion: None,
+            tax_rate,
+            tax_amount: Money::ZERO,
+            returned_quantity: 0,
+        }
+    }
+
+    #[test]
+    fn apply_tax_exclusive_rounds_each_item_to_nearest_sen() {
+        let mut items = vec![item(333, 11.0), item(1_000, 11.0)];
+        let (base, tax) = apply_tax(
+            &mut items,
+            Money::from_minor(1_333),
+            Money::from_minor(1_333),
+            false,
+        );
+        assert_eq!(items[0].tax_amount, Money::from_minor(37)); // 36,63
+        assert_eq!(items[1].tax_amount, Money::from_minor(110));
+        assert_eq!(base, Money::from_minor(1_333));
+        assert_eq!(tax, Money::from_minor(147));
+    }
+
+    #[test]
+    fn apply_tax_inclusive_splits_net_into_base_and_tax() {
+        let mut items = vec![item(10_000, 11.0)];
+        let (base, tax) = apply_tax(
+            &mut items,
+            Money::from_minor(10_000),
+            Money::from_minor(10_000),
+            true,
+        );
+        assert_eq!(base, Money::from_minor(9_009));
+        assert_eq!(tax, Money::from_minor(991));
+    }
+
+    #[test]
+    fn apply_tax_keeps_rounding_remainder_of_order_discount() {
+        // Tiga item 1 sen dengan total bersih 2 sen: pembagian proporsional membulatkan
+        // tiap item ke 1 sen, sisanya harus dikoreksi di item terakhir
+        let mut items = vec![item(1, 0.0), item(1, 0.0), item(1, 0.0)];
+        let (base, tax) = apply_tax(
+            &mut items,
+            Money::from_minor(3),
+            Money::from_minor(2),
+            true,
+        );
+        assert_eq!(base + tax, Money::from_minor(2));
+
+        let mut items = vec![item(10_000, 11.0), item(5_000, 11.0), item(2_500, 0.0)];
+        let net = Money::from_minor(15_999);
+        let (base, tax) = apply_tax(&mut items, Money::from_minor(17_500), net, true);
+        assert_eq!(base + tax, net);
+    }
+
+    fn sale_payload(payment_method_id: Option<ObjectId>, paid_amount: Option<i64>) -> SaleDTO {
+        SaleDTO {
+            customer_id: None,
+            items: Vec::new(),
+            discount: None,
+            tenders: Vec::new(),
+            payment_method_id,
+            paid_amount: paid_amount.map(Money::from_minor),
+            redeem_points: None,
+            voucher_code: None,
+            notes: None,
+        }
+    }
+
+    #[test]
+    fn normalize_legacy_payment_maps_pair_to_single_tender() {
+        let method = ObjectId::new();
+        let mut payload = sale_payload(Some(method), Some(5_000));
+        normalize_legacy_payment(&mut payload).unwrap();
+        assert_eq!(payload.tenders.len(), 1);
+        assert_eq!(payload.tenders[0].payment_method_id, method);
+        assert_eq!(payload.tenders[0].amount, Money::from_minor(5_000));
+        assert!(payload.payment_method_id.is_none() && payload.paid_amount.is_none());
+
+        // Dipanggil ulang (mis. setelah hash idempotency) tidak menambah tender
+        normalize_legacy_payment(&mut payload).unwrap();
+        assert_eq!(payload.tenders.len(), 1);
+
+        let mut unpaid = sale_payload(None, Some(0));
+        normalize_legacy_payment(&mut unpaid).unwrap();
+        assert!(unpaid.tenders.is_empty());
+    }
+
+    #[test]
+    fn normalize_legacy_payment_rejects_incomplete_or_mixed_payload() {
+        let mut without_amount = sale_payload(Some(ObjectId::new()), None);
+        assert!(normalize_legacy_payment(&mut without_amount).is_err());
+        let mut without_method = sale_payload(None, Some(5_000));
+        assert!(normalize_legacy_payment(&mut without_method).is_err());
+
+        let mut mixed = sale_payload(Some(ObjectId::new()), Some(5_000));
+        mixed.tenders.push(TenderDTO {
+            payment_method_id: ObjectId::new(),
+            amount: Money::from_minor(1_000),
+        });
+        assert!(normalize_legacy_payment(&mut mixed).is_err());
+    }
+
+    #[test]
+    fn apply_discount_rejects_invalid_values() {
+        let base = Money::from_minor(10_000);
+        let percent = |value| DiscountDTO {
+            discount_type: DiscountType::Percent,
+            percent: Some(value),
+            amount: None,
+        };
+        let fixed = |value| DiscountDTO {
+            discount_type: DiscountType::Fixed,
+            percent: None,
+            amount: Some(Money::from_minor(value)),
+        };
+
+        assert_eq!(
+            apply_discount(&percent(12.5), base, "item").unwrap().amount,
+            Money::from_minor(1_250)
+        );
+        assert!(apply_discount(&percent(101.0), base, "item").is_err());
+        assert_eq!(
+            apply_discount(&fixed(2_500), base, "item").unwrap().amount,
+            Money::from_minor(2_500)
+        );
+        assert!(apply_discount(&fixed(10_001), base, "item").is_err());
+        assert!(apply_discount(&fixed(-1), base, "item").is_err());
+
+        // Field yang tidak sesuai jenis diskon ditolak, bukan diabaikan
+        let mixed = DiscountDTO {
+            discount_type: DiscountType::Fixed,
+            percent: Some(10.0),
+            amount: None,
+        };
+        assert!(apply_discount(&mixed, base, "item").is_err());
+    }
+}
@@ -0,0 +1,134 @@
+use crate::errors::ServiceError;
+use crate::models::sale::Sale;
+use crate::models::sale_sync::{OfflineSaleDTO, SaleSyncDTO, SaleSyncResponse, SaleSyncResult};
+use crate::services::sale_service::{OfflineSale, create_offline_sale_service};
+use crate::utils::string_id_to_obj_id;
+use bson::oid::ObjectId;
+use chrono::{Duration, Utc};
+use mongodb::{Collection, Database, bson::doc};
+use validator::Validate;
+
+/// Toleransi jam perangkat yang lebih cepat dari server
+const MAX_CLOCK_SKEW_MINUTES: i64 = 5;
+
+/// Proses batch sale offline berurutan sesuai `sale_date` supaya stok berkurang
+/// sesuai urutan transaksi sebenarnya. Satu sale gagal tidak menghentikan sale lain
+pub async fn sync_sales_service(
+    payload: SaleSyncDTO,
+    db: &Database,
+    user_id: &str,
+) -> Result<SaleSyncResponse, ServiceError> {
+    let user_oid = match string_id_to_obj_id(user_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let mut sales = payload.sales;
+    sales.sort_by_key(|s| s.sale_date);
+
+    let mut response = SaleSyncResponse {
+        accepted: 0,
+        duplicate: 0,
+        rejected: 0,
+        results: Vec::new(),
+    };
+
+    for mut offline_sale in sales {
+        // Trim sebelum validasi supaya client_id yang hanya berisi spasi ikut ditolak
+        offline_sale.client_id = offline_sale.client_id.trim().to_string();
+        let client_id = offline_sale.client_id.clone();
+
+        let result = match sync_sale(offline_sale, &client_id, user_oid, db, user_id).await {
+            Ok(result) => result,
+            Err(err) => rejected(&client_id, err.to_string()),
+        };
+
+        match result.status.as_str() {
+            "accepted" => response.accepted += 1,
+            "duplicate" => response.duplicate += 1,
+            _ => response.rejected += 1,
+        }
+        response.results.push(result);
+    }
+
+    Ok(response)
+}
+
+async fn sync_sale(
+    offline_sale: OfflineSaleDTO,
+    client_id: &str,
+    user_oid: ObjectId,
+    db: &Database,
+    user_id: &str,
+) -> Result<SaleSyncResult, ServiceError> {
+    if let Err(e) = offline_sale.validate() {
+        return Ok(rejected(client_id, e.to_string()));
+    }
+
+    if offline_sale.sale_date > Utc::now() + Duration::minutes(MAX_CLOCK_SKEW_MINUTES) {
+        return Ok(rejected(
+            client_id,
+            "sale_date tidak boleh di masa depan, periksa jam perangkat".to_string(),
+        ));
+    }
+
+    if let Some(existing) = find_by_client_id(client_id, user_oid, db).await? {
+        return Ok(duplicate(client_id, existing));
+    }
+
+    let offline = OfflineSale {
+        client_id: client_id.to_string(),
+        sale_date: offline_sale.sale_date,
+    };
+
+    match create_offline_sale_service(offline_sale.sale, offline, db, user_id).await {
+        Ok(sale) => Ok(SaleSyncResult {
+            client_id: client_id.to_string(),
+            status: "accepted".to_string(),
+            sale_id: sale.id.map(|id| id.to_hex()),
+            invoice_number: sale.invoice_number,
+            error: None,
+        }),
+        // Batch yang sama dikirim bersamaan: index unik client_id menolak insert kedua
+        Err(ServiceError::Conflict(msg)) => {
+            match find_by_client_id(client_id, user_oid, db).await? {
+                Some(existing) => Ok(duplicate(client_id, existing)),
+                None => Ok(rejected(client_id, msg)),
+            }
+        }
+        Err(err) => Ok(rejected(client_id, err.to_string())),
+    }
+}
+
+async fn find_by_client_id(
+    client_id: &str,
+    user_id: ObjectId,
+    db: &Database,
+) -> Result<Option<Sale>, ServiceError> {
+    let collection: Collection<Sale> = db.collection("sales");
+
+    collection
+        .find_one(doc! { "user_id": user_id, "client_id": client_id })
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))
+}
+
+fn duplicate(client_id: &str, sale: Sale) -> SaleSyncResult {
+    SaleSyncResult {
+        client_id: client_id.to_string(),
+        status: "duplicate".to_string(),
+        sale_id: sale.id.map(|id| id.to_hex()),
+        invoice_number: sale.invoice_number,
+        error: None,
+    }
+}
+
+fn rejected(client_id: &str, error: String) -> SaleSyncResult {
+    SaleSyncResult {
+        client_id: client_id.to_string(),
+        status: "rejected".to_string(),
+        sale_id: None,
+        invoice_number: None,
+        error: Some(error),
+    }
+}
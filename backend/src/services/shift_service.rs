@@ -0,0 +1,535 @@
+use crate::errors::ServiceError;
+use crate::models::money::Money;
+use crate::models::pagination::{Pagination, page_and_limit};
+use crate::models::sale::{PaymentMethodTotal, Sale};
+use crate::models::sale_return::SaleReturn;
+use crate::models::shift::{
+    CashierReportQuery, CashierShiftReport, CloseShiftDTO, OpenShiftDTO, Shift, ShiftListQuery,
+    ShiftSummary,
+};
+use crate::models::store_setting::StoreSetting;
+use crate::services::sale_service::parse_query_date;
+use crate::services::store_setting_service::find_store_setting;
+use crate::utils::{
+    finish_transaction, handle_duplicate_key_error, start_transaction, string_id_to_obj_id,
+    transaction_error,
+};
+use bson::datetime::DateTime as BsonDateTime;
+use bson::oid::ObjectId;
+use chrono::Utc;
+use futures::stream::TryStreamExt;
+use mongodb::{
+    ClientSession, Collection, Database,
+    bson::{Document, doc, from_document},
+    options::ReturnDocument,
+};
+
+const SHIFT_STATUSES: [&str; 2] = ["open", "closed"];
+const MAX_TRANSACTION_ATTEMPTS: u32 = 3;
+
+pub async fn open_shift_service(
+    payload: OpenShiftDTO,
+    db: &Database,
+    user_id: &str,
+) -> Result<Shift, ServiceError> {
+    let user_id = match string_id_to_obj_id(user_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let collection: Collection<Shift> = db.collection("shifts");
+
+    if let Some(open) = collection
+        .find_one(doc! { "user_id": user_id, "status": "open" })
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?
+    {
+        return Err(ServiceError::Conflict(format!(
+            "Shift {} masih terbuka, tutup dulu sebelum membuka shift baru",
+            open.cashier_name
+        )));
+    }
+
+    let shift = Shift {
+        id: None,
+        user_id,
+        cashier_name: payload.cashier_name.trim().to_string(),
+        status: "open".to_string(),
+        opening_float: payload.opening_float,
+        opened_at: BsonDateTime::from_chrono(Utc::now()),
+        opening_notes: payload.notes,
+        closed_at: None,
+        counted_cash: None,
+        summary: None,
+        closing_notes: None,
+    };
+
+    // Index unik parsial (user_id, status=open) menolak dua shift terbuka yang dibuat bersamaan
+    let result = collection.insert_one(&shift).await.map_err(|e| {
+        if handle_duplicate_key_error(&e).is_some() {
+            return ServiceError::Conflict("Masih ada shift yang terbuka".into());
+        }
+        ServiceError::DatabaseError(e.to_string())
+    })?;
+
+    Ok(Shift {
+        id: result.inserted_id.as_object_id(),
+        ..shift
+    })
+}
+
+/// Shift yang sedang terbuka beserta rekap sementaranya
+pub async fn get_current_shift_service(
+    db: &Database,
+    user_id: &str,
+) -> Result<Shift, ServiceError> {
+    let user_id = match string_id_to_obj_id(user_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let collection: Collection<Shift> = db.collection("shifts");
+
+    let shift = collection
+        .find_one(doc! { "user_id": user_id, "status": "open" })
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?
+        .ok_or_else(|| ServiceError::NotFound("Tidak ada shift yang terbuka".into()))?;
+
+    with_live_summary(shift, db).await
+}
+
+pub async fn get_shifts_service(
+    db: &Database,
+    user_id: &str,
+    query: ShiftListQuery,
+) -> Result<(Vec<Shift>, Pagination), ServiceError> {
+    let user_id = match string_id_to_obj_id(user_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let setting = find_store_setting(user_id, db).await?;
+    let mut filter = doc! { "user_id": user_id };
+
+    if let Some(status) = &query.status {
+        if !SHIFT_STATUSES.contains(&status.as_str()) {
+            return Err(ServiceError::BadRequest(format!(
+                "status harus salah satu dari: {}",
+                SHIFT_STATUSES.join(", ")
+            )));
+        }
+        filter.insert("status", status);
+    }
+
+    if let Some(cashier_name) = query
+        .cashier_name
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+    {
+        filter.insert(
+            "cashier_name",
+            doc! { "$regex": format!("^{}$", regex::escape(cashier_name)), "$options": "i" },
+        );
+    }
+
+    let opened_at = opened_at_range(
+        query.date_from.as_deref(),
+        query.date_to.as_deref(),
+        &setting,
+    )?;
+    if !opened_at.is_empty() {
+        filter.insert("opened_at", opened_at);
+    }
+
+    let (page, limit) = page_and_limit(query.page, query.limit);
+    let collection: Collection<Shift> = db.collection("shifts");
+
+    let total = collection
+        .count_documents(filter.clone())
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+
+    let mut cursor = collection
+        .find(filter)
+        .sort(doc! { "opened_at": -1, "_id": -1 })
+        .skip((page - 1) * limit)
+        .limit(limit as i64)
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+
+    let mut shifts: Vec<Shift> = Vec::new();
+
+    while let Some(shift) = cursor
+        .try_next()
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?
+    {
+        shifts.push(shift);
+    }
+
+    Ok((shifts, Pagination::new(page, limit, total)))
+}
+
+/// Detail shift; shift yang masih terbuka dihitung rekapnya saat ini juga
+pub async fn get_shift_service(
+    shift_id: &str,
+    db: &Database,
+    user_id: &str,
+) -> Result<Shift, ServiceError> {
+    let user_id = match string_id_to_obj_id(user_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let shift_id = match string_id_to_obj_id(shift_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let shift = find_shift(shift_id, user_id, db).await?;
+    with_live_summary(shift, db).await
+}
+
+/// Tutup shift: hitung uang tunai yang seharusnya ada di laci dan selisihnya
+pub async fn close_shift_service(
+    shift_id: &str,
+    payload: CloseShiftDTO,
+    db: &Database,
+    user_id: &str,
+) -> Result<Shift, ServiceError> {
+    let user_id = match string_id_to_obj_id(user_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let shift_id = match string_id_to_obj_id(shift_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let mut attempt = 1;
+    loop {
+        let mut session = start_transaction(db).await?;
+        let result = close_shift(shift_id, user_id, &payload, db, &mut session).await;
+
+        match finish_transaction(&mut session, result).await {
+            // Bentrok dengan sale/cicilan/retur yang sedang masuk ke shift ini, hitung ulang
+            Err(ServiceError::TransactionConflict(msg)) if attempt < MAX_TRANSACTION_ATTEMPTS => {
+                log::warn!("Tutup shift bentrok (percobaan {}): {}", attempt, msg);
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Rekap dan penutupan shift dalam satu transaksi. Dokumen shift ditulis lebih dulu,
+/// transaksi lain yang menautkan uang ke shift ini (lewat `find_open_shift`) ikut bentrok
+/// sehingga tidak ada sale yang dapat shift_id tapi terlewat dari rekap
+async fn close_shift(
+    shift_id: ObjectId,
+    user_id: ObjectId,
+    payload: &CloseShiftDTO,
+    db: &Database,
+    session: &mut ClientSession,
+) -> Result<Shift, ServiceError> {
+    let collection: Collection<Shift> = db.collection("shifts");
+
+    // Filter status open supaya tutup shift dua kali yang bersamaan ditolak
+    let shift = collection
+        .find_one_and_update(
+            doc! { "_id": shift_id, "user_id": user_id, "status": "open" },
+            doc! { "$inc": { "write_count": 1_i64 } },
+        )
+        .session(&mut *session)
+        .await
+        .map_err(transaction_error)?;
+    let Some(shift) = shift else {
+        find_shift(shift_id, user_id, db).await?;
+        return Err(ServiceError::Conflict("Shift sudah ditutup".into()));
+    };
+
+    let mut summary = build_shift_summary(&shift, db, session).await?;
+    summary.counted_cash = Some(payload.counted_cash);
+    summary.difference = Some(payload.counted_cash - summary.expected_cash);
+
+    let summary_doc =
+        bson::to_bson(&summary).map_err(|e| ServiceError::Unexpected(e.to_string()))?;
+    let now = BsonDateTime::from_chrono(Utc::now());
+
+    let mut update_doc = doc! {
+        "status": "closed",
+        "closed_at": now,
+        "counted_cash": payload.counted_cash,
+        "summary": summary_doc,
+    };
+    if let Some(notes) = &payload.notes {
+        update_doc.insert("closing_notes", notes);
+    }
+
+    collection
+        .find_one_and_update(doc! { "_id": shift_id }, doc! { "$set": update_doc })
+        .return_document(ReturnDocument::After)
+        .session(&mut *session)
+        .await
+        .map_err(transaction_error)?
+        .ok_or_else(|| ServiceError::Conflict("Shift sudah ditutup".into()))
+}
+
+/// Rekap shift yang sudah ditutup per kasir, termasuk total selisih kas
+pub async fn get_cashier_report_service(
+    db: &Database,
+    user_id: &str,
+    query: CashierReportQuery,
+) -> Result<Vec<CashierShiftReport>, ServiceError> {
+    let user_id = match string_id_to_obj_id(user_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let setting = find_store_setting(user_id, db).await?;
+
+    let mut filter = doc! { "user_id": user_id, "status": "closed" };
+    let opened_at = opened_at_range(
+        query.date_from.as_deref(),
+        query.date_to.as_deref(),
+        &setting,
+    )?;
+    if !opened_at.is_empty() {
+        filter.insert("opened_at", opened_at);
+    }
+
+    let pipeline = vec![
+        doc! { "$match": filter },
+        doc! { "$group": {
+            "_id": "$cashier_name",
+            "shift_count": { "$sum": 1 },
+            "transaction_count": { "$sum": "$summary.transaction_count" },
+            "total_amount": { "$sum": "$summary.total_amount" },
+            "expected_cash": { "$sum": "$summary.expected_cash" },
+            "counted_cash": { "$sum": "$summary.counted_cash" },
+            "difference": { "$sum": "$summary.difference" },
+        } },
+        doc! { "$sort": { "_id": 1 } },
+    ];
+
+    let collection: Collection<Shift> = db.collection("shifts");
+    let mut cursor = collection
+        .aggregate(pipeline)
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+
+    let mut reports: Vec<CashierShiftReport> = Vec::new();
+
+    while let Some(document) = cursor
+        .try_next()
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?
+    {
+        let report: CashierShiftReport =
+            from_document(document).map_err(|e| ServiceError::Unexpected(e.to_string()))?;
+        reports.push(report);
+    }
+
+    Ok(reports)
+}
+
+/// Shift yang sedang terbuka, dipakai di dalam transaksi sale/pembayaran/retur
+/// untuk menautkan uang yang masuk ke laci kasir. Dokumen shift ikut ditulis supaya
+/// transaksi ini dan tutup shift saling bentrok (write conflict) dan salah satunya diulang
+pub async fn find_open_shift(
+    user_id: ObjectId,
+    db: &Database,
+    session: &mut ClientSession,
+) -> Result<Option<Shift>, ServiceError> {
+    let collection: Collection<Shift> = db.collection("shifts");
+
+    collection
+        .find_one_and_update(
+            doc! { "user_id": user_id, "status": "open" },
+            doc! { "$inc": { "write_count": 1_i64 } },
+        )
+        .session(&mut *session)
+        .await
+        .map_err(transaction_error)
+}
+
+async fn find_shift(
+    shift_id: ObjectId,
+    user_id: ObjectId,
+    db: &Database,
+) -> Result<Shift, ServiceError> {
+    let collection: Collection<Shift> = db.collection("shifts");
+
+    collection
+        .find_one(doc! { "_id": shift_id, "user_id": user_id })
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?
+        .ok_or_else(|| {
+            ServiceError::NotFound(format!("Shift dengan ID '{}' tidak ditemukan", shift_id))
+        })
+}
+
+async fn with_live_summary(mut shift: Shift, db: &Database) -> Result<Shift, ServiceError> {
+    if shift.status == "open" {
+        // Rekap dibaca dari satu snapshot, sama seperti saat shift ditutup
+        let mut session = start_transaction(db).await?;
+        let result = build_shift_summary(&shift, db, &mut session).await;
+        shift.summary = Some(finish_transaction(&mut session, result).await?);
+    }
+    Ok(shift)
+}
+
+fn opened_at_range(
+    date_from: Option<&str>,
+    date_to: Option<&str>,
+    setting: &StoreSetting,
+) -> Result<Document, ServiceError> {
+    let mut range = doc! {};
+    if let Some(date_from) = date_from {
+        let (start, _) = setting.business_day_bounds(parse_query_date(date_from)?);
+        range.insert("$gte", BsonDateTime::from_chrono(start));
+    }
+    if let Some(date_to) = date_to {
+        let (_, end) = setting.business_day_bounds(parse_query_date(date_to)?);
+        range.insert("$lt", BsonDateTime::from_chrono(end));
+    }
+    Ok(range)
+}
+
+/// Refund retur yang dibayar dari laci selama shift. Retur lama belum menyimpan shift_id
+/// dan porsi tunainya, jadi dicocokkan dengan waktu shift dan dianggap tunai seluruhnya
+async fn cash_refunded(
+    shift: &Shift,
+    db: &Database,
+    session: &mut ClientSession,
+) -> Result<Money, ServiceError> {
+    let shift_id = shift.id.expect("Shift.id harus ada");
+    let mut window = doc! { "$gte": shift.opened_at };
+    if let Some(closed_at) = shift.closed_at {
+        window.insert("$lt", closed_at);
+    }
+
+    let collection: Collection<SaleReturn> = db.collection("sale_returns");
+    let mut cursor = collection
+        .find(doc! {
+            "user_id": shift.user_id,
+            "$or": [
+                { "shift_id": shift_id },
+                { "shift_id": { "$exists": false }, "created_at": window },
+            ],
+        })
+        .session(&mut *session)
+        .await
+        .map_err(transaction_error)?;
+
+    let mut refunded = Money::ZERO;
+    while let Some(sale_return) = cursor
+        .next(&mut *session)
+        .await
+        .transpose()
+        .map_err(transaction_error)?
+    {
+        refunded += sale_return
+            .cash_refund_amount
+            .unwrap_or(sale_return.refund_amount);
+    }
+
+    Ok(refunded)
+}
+
+/// Rekap sale yang dibuat di shift ini dan tender yang diterima di shift ini
+/// (termasuk cicilan sale dari shift lain). Sale yang di-void tidak dihitung
+/// karena uangnya sudah dikembalikan ke pelanggan
+async fn build_shift_summary(
+    shift: &Shift,
+    db: &Database,
+    session: &mut ClientSession,
+) -> Result<ShiftSummary, ServiceError> {
+    let shift_id = shift.id.expect("Shift.id harus ada");
+    let collection: Collection<Sale> = db.collection("sales");
+
+    let mut cursor = collection
+        .find(doc! {
+            "user_id": shift.user_id,
+            "status": { "$ne": "voided" },
+            "$or": [
+                { "shift_id": shift_id },
+                { "tenders.shift_id": shift_id },
+            ],
+        })
+        .session(&mut *session)
+        .await
+        .map_err(transaction_error)?;
+
+    let mut summary = ShiftSummary {
+        transaction_count: 0,
+        total_amount: Money::ZERO,
+        cash_received: Money::ZERO,
+        cash_change: Money::ZERO,
+        cash_refunded: Money::ZERO,
+        expected_cash: Money::ZERO,
+        counted_cash: None,
+        difference: None,
+        payment_methods: Vec::new(),
+    };
+
+    while let Some(sale) = cursor
+        .next(&mut *session)
+        .await
+        .transpose()
+        .map_err(transaction_error)?
+    {
+        if sale.shift_id == Some(shift_id) {
+            summary.transaction_count += 1;
+            summary.total_amount += sale.total_amount;
+        }
+
+        for tender in sale.tenders.iter().filter(|t| t.shift_id == Some(shift_id)) {
+            if tender.payment_method.is_cash {
+                summary.cash_received += tender.amount;
+                summary.cash_change += tender.change_amount;
+            }
+
+            let payment_method_id = tender.payment_method.id.map(|id| id.to_hex());
+            let total = match summary
+                .payment_methods
+                .iter_mut()
+                .find(|t| t.payment_method_id == payment_method_id)
+            {
+                Some(total) => total,
+                None => {
+                    summary.payment_methods.push(PaymentMethodTotal {
+                        payment_method_id,
+                        name: tender.payment_method.name.clone(),
+                        tendered_amount: Money::ZERO,
+                        change_amount: Money::ZERO,
+                        amount: Money::ZERO,
+                        transaction_count: 0,
+                    });
+                    summary
+                        .payment_methods
+                        .last_mut()
+                        .expect("baru ditambahkan")
+                }
+            };
+
+            total.tendered_amount += tender.amount;
+            total.change_amount += tender.change_amount;
+            total.amount += tender.amount - tender.change_amount;
+            total.transaction_count += 1;
+        }
+    }
+
+    summary.cash_refunded = cash_refunded(shift, db, session).await?;
+    summary.expected_cash = shift.opening_float + summary.cash_received
+        - summary.cash_change
+        - summary.cash_refunded;
+    summary
+        .payment_methods
+        .sort_by_key(|t| std::cmp::Reverse(t.amount));
+
+    Ok(summary)
+}
@@ -0,0 +1,160 @@
+use crate::errors::ServiceError;
+use crate::models::store_setting::{StoreSetting, UpdateStoreSettingDTO};
+use crate::services::invoice_service::validate_invoice_format;
+use crate::services::receipt_service::validate_paper_width;
+use crate::utils::string_id_to_obj_id;
+use bson::datetime::DateTime as BsonDateTime;
+use bson::oid::ObjectId;
+use chrono::Utc;
+use mongodb::{Collection, Database, bson::doc};
+
+/// Ambil pengaturan toko, kembalikan pengaturan bawaan jika belum pernah disimpan
+pub async fn find_store_setting(
+    user_id: ObjectId,
+    db: &Database,
+) -> Result<StoreSetting, ServiceError> {
+    let collection: Collection<StoreSetting> = db.collection("store_settings");
+
+    let setting = collection
+        .find_one(doc! { "user_id": user_id })
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+
+    Ok(setting.unwrap_or_else(|| StoreSetting::default_for(user_id)))
+}
+
+pub async fn get_store_setting_service(
+    db: &Database,
+    id: &str,
+) -> Result<StoreSetting, ServiceError> {
+    let user_id = match string_id_to_obj_id(id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    find_store_setting(user_id, db).await
+}
+
+pub async fn update_store_setting_service(
+    payload: UpdateStoreSettingDTO,
+    db: &Database,
+    id: &str,
+) -> Result<StoreSetting, ServiceError> {
+    let user_id = match string_id_to_obj_id(id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let mut setting = find_store_setting(user_id, db).await?;
+    let mut changed = false;
+
+    if let Some(store_name) = payload.store_name {
+        setting.store_name = Some(store_name);
+        changed = true;
+    }
+    if let Some(store_address) = payload.store_address {
+        setting.store_address = Some(store_address);
+        changed = true;
+    }
+    if let Some(store_phone) = payload.store_phone {
+        setting.store_phone = Some(store_phone);
+        changed = true;
+    }
+    if let Some(invoice_format) = payload.invoice_format {
+        setting.invoice_format = invoice_format;
+        changed = true;
+    }
+    if let Some(invoice_reset) = payload.invoice_reset {
+        if !["never", "yearly", "monthly"].contains(&invoice_reset.as_str()) {
+            return Err(ServiceError::BadRequest(
+                "invoice_reset harus 'never', 'yearly' atau 'monthly'".into(),
+            ));
+        }
+        setting.invoice_reset = invoice_reset;
+        changed = true;
+    }
+    // Format dan reset dicek bersama karena reset menentukan token periode yang wajib ada
+    validate_invoice_format(&setting.invoice_format, &setting.invoice_reset)?;
+
+    if let Some(tax_enabled) = payload.tax_enabled {
+        setting.tax_enabled = tax_enabled;
+        changed = true;
+    }
+    if let Some(tax_rate) = payload.tax_rate {
+        setting.tax_rate = tax_rate;
+        changed = true;
+    }
+    if let Some(tax_inclusive) = payload.tax_inclusive {
+        setting.tax_inclusive = tax_inclusive;
+        changed = true;
+    }
+    if let Some(receipt_paper_width) = payload.receipt_paper_width {
+        validate_paper_width(receipt_paper_width)?;
+        setting.receipt_paper_width = receipt_paper_width;
+        changed = true;
+    }
+    if let Some(receipt_footer) = payload.receipt_footer {
+        setting.receipt_footer = Some(receipt_footer);
+        changed = true;
+    }
+    if let Some(utc_offset_minutes) = payload.utc_offset_minutes {
+        setting.utc_offset_minutes = utc_offset_minutes;
+        changed = true;
+    }
+    if let Some(loyalty_enabled) = payload.loyalty_enabled {
+        setting.loyalty_enabled = loyalty_enabled;
+        changed = true;
+    }
+    if let Some(loyalty_earn_amount) = payload.loyalty_earn_amount {
+        setting.loyalty_earn_amount = loyalty_earn_amount;
+        changed = true;
+    }
+    if let Some(loyalty_point_value) = payload.loyalty_point_value {
+        setting.loyalty_point_value = loyalty_point_value;
+        changed = true;
+    }
+    if let Some(loyalty_expiry_days) = payload.loyalty_expiry_days {
+        setting.loyalty_expiry_days = loyalty_expiry_days;
+        changed = true;
+    }
+
+    if !changed {
+        return Err(ServiceError::BadRequest(
+            "Tidak ada data untuk di-update".to_string(),
+        ));
+    }
+
+    let now = BsonDateTime::from_chrono(Utc::now());
+    let collection: Collection<StoreSetting> = db.collection("store_settings");
+
+    collection
+        .update_one(
+            doc! { "user_id": user_id },
+            doc! {
+                "$set": {
+                    "store_name": &setting.store_name,
+                    "store_address": &setting.store_address,
+                    "store_phone": &setting.store_phone,
+                    "invoice_format": &setting.invoice_format,
+                    "invoice_reset": &setting.invoice_reset,
+                    "tax_enabled": setting.tax_enabled,
+                    "tax_rate": setting.tax_rate,
+                    "tax_inclusive": setting.tax_inclusive,
+                    "receipt_paper_width": setting.receipt_paper_width,
+                    "receipt_footer": &setting.receipt_footer,
+                    "utc_offset_minutes": setting.utc_offset_minutes,
+                    "loyalty_enabled": setting.loyalty_enabled,
+                    "loyalty_earn_amount": setting.loyalty_earn_amount,
+                    "loyalty_point_value": setting.loyalty_point_value,
+                    "loyalty_expiry_days": setting.loyalty_expiry_days,
+                    "updated_at": now,
+                },
+                "$setOnInsert": { "created_at": now },
+            },
+        )
+        .upsert(true)
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+
+    find_store_setting(user_id, db).await
+}
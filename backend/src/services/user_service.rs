@@ -52,7 +52,7 @@ pub async fn create_user_service(
     let username = payload.username;
     let email = payload.email;
     let phone_number = payload.phone_number;
-    let mut password = payload.password.unwrap_or_else(|| "".to_string());
+    let mut password = payload.password.unwrap_or_default();
 
     if password.trim().is_empty() {
         if username.len() < 6 {
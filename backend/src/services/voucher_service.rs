@@ -0,0 +1,661 @@
+use crate::errors::ServiceError;
+use crate::models::money::Money;
+use crate::models::pagination::{Pagination, page_and_limit};
+use crate::models::sale::{DiscountType, Sale, SaleVoucher};
+use crate::models::voucher::{
+    UpdateVoucherDTO, Voucher, VoucherDTO, VoucherListQuery, VoucherRedemption,
+};
+use crate::utils::{
+    finish_transaction, format_rupiah, handle_duplicate_key_error, start_transaction,
+    string_id_to_obj_id, transaction_error,
+};
+use bson::datetime::DateTime as BsonDateTime;
+use bson::oid::ObjectId;
+use chrono::{DateTime, Utc};
+use futures::stream::TryStreamExt;
+use mongodb::{
+    ClientSession, Collection, Database, bson::doc, error::Error as MongoError,
+    options::ReturnDocument,
+};
+
+const MAX_TRANSACTION_ATTEMPTS: u32 = 3;
+
+pub async fn get_vouchers_service(
+    db: &Database,
+    user_id: &str,
+    query: VoucherListQuery,
+) -> Result<(Vec<Voucher>, Pagination), ServiceError> {
+    let user_id = match string_id_to_obj_id(user_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let mut filter = doc! { "user_id": user_id };
+    if let Some(search) = query
+        .search
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+    {
+        filter.insert(
+            "code",
+            doc! { "$regex": regex::escape(search), "$options": "i" },
+        );
+    }
+    if let Some(active) = query.active {
+        filter.insert("active", active);
+    }
+
+    let (page, limit) = page_and_limit(query.page, query.limit);
+    let collection: Collection<Voucher> = db.collection("vouchers");
+
+    let total = collection
+        .count_documents(filter.clone())
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+
+    let mut cursor = collection
+        .find(filter)
+        .sort(doc! { "expires_at": -1, "_id": -1 })
+        .skip((page - 1) * limit)
+        .limit(limit as i64)
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+
+    let mut vouchers: Vec<Voucher> = Vec::new();
+
+    while let Some(voucher) = cursor
+        .try_next()
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?
+    {
+        vouchers.push(voucher);
+    }
+
+    Ok((vouchers, Pagination::new(page, limit, total)))
+}
+
+pub async fn get_voucher_service(
+    voucher_id: &str,
+    db: &Database,
+    user_id: &str,
+) -> Result<Voucher, ServiceError> {
+    let user_id = match string_id_to_obj_id(user_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let voucher_id = match string_id_to_obj_id(voucher_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let collection: Collection<Voucher> = db.collection("vouchers");
+
+    collection
+        .find_one(doc! { "_id": voucher_id, "user_id": user_id })
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?
+        .ok_or_else(|| {
+            ServiceError::NotFound(format!(
+                "Voucher dengan ID '{}' tidak ditemukan",
+                voucher_id
+            ))
+        })
+}
+
+pub async fn create_voucher_service(
+    payload: VoucherDTO,
+    db: &Database,
+    user_id: &str,
+) -> Result<Voucher, ServiceError> {
+    let user_id = match string_id_to_obj_id(user_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let now = BsonDateTime::from_chrono(Utc::now());
+
+    let voucher = Voucher {
+        id: None,
+        user_id,
+        code: normalize_code(&payload.code),
+        discount_type: payload.discount_type,
+        percent: payload.percent,
+        amount: payload.amount,
+        min_spend: payload.min_spend.unwrap_or_default(),
+        per_customer_limit: payload.per_customer_limit,
+        usage_limit: payload.usage_limit,
+        used_count: 0,
+        expires_at: BsonDateTime::from_chrono(payload.expires_at),
+        active: payload.active.unwrap_or(true),
+        created_at: Some(now),
+        updated_at: Some(now),
+    };
+    validate_voucher(&voucher)?;
+
+    let collection: Collection<Voucher> = db.collection("vouchers");
+    let result = collection
+        .insert_one(&voucher)
+        .await
+        .map_err(voucher_write_error)?;
+
+    Ok(Voucher {
+        id: result.inserted_id.as_object_id(),
+        ..voucher
+    })
+}
+
+/// `used_count` tidak bisa diubah dari sini, hanya lewat sale dan void
+pub async fn update_voucher_service(
+    voucher_id: &str,
+    payload: UpdateVoucherDTO,
+    db: &Database,
+    user_id: &str,
+) -> Result<Voucher, ServiceError> {
+    let mut voucher = get_voucher_service(voucher_id, db, user_id).await?;
+    let mut changed = false;
+
+    if let Some(code) = payload.code {
+        voucher.code = normalize_code(&code);
+        changed = true;
+    }
+    if let Some(discount_type) = payload.discount_type {
+        // Ganti jenis voucher: nilai untuk jenis lama tidak dipakai lagi
+        if discount_type != voucher.discount_type {
+            voucher.percent = None;
+            voucher.amount = None;
+        }
+        voucher.discount_type = discount_type;
+        changed = true;
+    }
+    if let Some(percent) = payload.percent {
+        voucher.percent = Some(percent);
+        changed = true;
+    }
+    if let Some(amount) = payload.amount {
+        voucher.amount = Some(amount);
+        changed = true;
+    }
+    if let Some(min_spend) = payload.min_spend {
+        voucher.min_spend = min_spend;
+        changed = true;
+    }
+    if let Some(per_customer_limit) = payload.per_customer_limit {
+        voucher.per_customer_limit = Some(per_customer_limit);
+        changed = true;
+    }
+    if let Some(usage_limit) = payload.usage_limit {
+        voucher.usage_limit = Some(usage_limit);
+        changed = true;
+    }
+    if let Some(expires_at) = payload.expires_at {
+        voucher.expires_at = BsonDateTime::from_chrono(expires_at);
+        changed = true;
+    }
+    if let Some(active) = payload.active {
+        voucher.active = active;
+        changed = true;
+    }
+
+    if !changed {
+        return Err(ServiceError::BadRequest(
+            "Tidak ada data untuk di-update".to_string(),
+        ));
+    }
+
+    validate_voucher(&voucher)?;
+
+    let now = BsonDateTime::from_chrono(Utc::now());
+    let discount_type = bson::to_bson(&voucher.discount_type)
+        .map_err(|e| ServiceError::Unexpected(e.to_string()))?;
+    let collection: Collection<Voucher> = db.collection("vouchers");
+
+    collection
+        .find_one_and_update(
+            doc! { "_id": voucher.id, "user_id": voucher.user_id },
+            doc! { "$set": {
+                "code": &voucher.code,
+                "discount_type": discount_type,
+                "percent": voucher.percent,
+                "amount": voucher.amount,
+                "min_spend": voucher.min_spend,
+                "per_customer_limit": voucher.per_customer_limit,
+                "usage_limit": voucher.usage_limit,
+                "expires_at": voucher.expires_at,
+                "active": voucher.active,
+                "updated_at": now,
+            } },
+        )
+        .return_document(ReturnDocument::After)
+        .await
+        .map_err(voucher_write_error)?
+        .ok_or_else(|| {
+            ServiceError::NotFound(format!(
+                "Voucher dengan ID '{}' tidak ditemukan",
+                voucher_id
+            ))
+        })
+}
+
+/// Voucher yang sudah pernah dipakai tidak bisa dihapus, nonaktifkan saja
+pub async fn delete_voucher_service(
+    voucher_id: &str,
+    db: &Database,
+    user_id: &str,
+) -> Result<bool, ServiceError> {
+    let user_id = match string_id_to_obj_id(user_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let voucher_id = match string_id_to_obj_id(voucher_id) {
+        Some(oid) => oid,
+        None => return Err(ServiceError::InvalidId("Invalid ID".into())),
+    };
+
+    let mut attempt = 1;
+    loop {
+        let mut session = start_transaction(db).await?;
+        let result = delete_voucher(voucher_id, user_id, db, &mut session).await;
+
+        match finish_transaction(&mut session, result).await {
+            // Bentrok dengan sale yang sedang memakai voucher ini, cek ulang pemakaiannya
+            Err(ServiceError::TransactionConflict(msg)) if attempt < MAX_TRANSACTION_ATTEMPTS => {
+                log::warn!("Hapus voucher bentrok (percobaan {}): {}", attempt, msg);
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Dokumen voucher dihapus lebih dulu, sale yang bersamaan menaikkan `used_count`-nya
+/// ikut bentrok, jadi pemakaian yang dihitung sesudahnya sudah lengkap
+async fn delete_voucher(
+    voucher_id: ObjectId,
+    user_id: ObjectId,
+    db: &Database,
+    session: &mut ClientSession,
+) -> Result<bool, ServiceError> {
+    let collection: Collection<Voucher> = db.collection("vouchers");
+
+    let result = collection
+        .delete_one(doc! { "_id": voucher_id, "user_id": user_id })
+        .session(&mut *session)
+        .await
+        .map_err(transaction_error)?;
+
+    if result.deleted_count == 0 {
+        return Err(ServiceError::NotFound(format!(
+            "Voucher dengan ID '{}' tidak ditemukan",
+            voucher_id
+        )));
+    }
+
+    let redemptions: Collection<VoucherRedemption> = db.collection("voucher_redemptions");
+    let redemption_count = redemptions
+        .count_documents(doc! { "user_id": user_id, "voucher_id": voucher_id })
+        .session(&mut *session)
+        .await
+        .map_err(transaction_error)?;
+
+    if redemption_count > 0 {
+        return Err(ServiceError::Conflict(format!(
+            "Voucher sudah dipakai di {} transaksi dan tidak bisa dihapus, nonaktifkan saja",
+            redemption_count
+        )));
+    }
+
+    Ok(true)
+}
+
+/// Validasi voucher untuk sale lalu naikkan `used_count`, dijalankan di dalam transaksi sale.
+/// `spend` adalah belanja setelah diskon transaksi. Sale lain yang memakai voucher yang sama
+/// ikut menulis dokumen voucher ini, jadi pemakaian bersamaan akan bentrok dan diulang
+pub async fn redeem_voucher(
+    code: &str,
+    customer_id: Option<ObjectId>,
+    spend: Money,
+    sale_date: DateTime<Utc>,
+    user_id: ObjectId,
+    db: &Database,
+    session: &mut ClientSession,
+) -> Result<SaleVoucher, ServiceError> {
+    let collection: Collection<Voucher> = db.collection("vouchers");
+    let code = normalize_code(code);
+
+    let voucher = collection
+        .find_one(doc! { "user_id": user_id, "code": &code })
+        .session(&mut *session)
+        .await
+        .map_err(transaction_error)?
+        .ok_or_else(|| ServiceError::NotFound(format!("Voucher '{}' tidak ditemukan", code)))?;
+    let voucher_id = voucher.id.expect("Voucher.id harus ada");
+
+    if !voucher.active {
+        return Err(ServiceError::BadRequest(format!(
+            "Voucher '{}' tidak aktif",
+            code
+        )));
+    }
+    if voucher.expires_at.to_chrono() < sale_date {
+        return Err(ServiceError::BadRequest(format!(
+            "Voucher '{}' sudah kedaluwarsa",
+            code
+        )));
+    }
+    if spend < voucher.min_spend {
+        return Err(ServiceError::BadRequest(format!(
+            "Minimal belanja untuk voucher '{}' adalah {}",
+            code,
+            format_rupiah(voucher.min_spend)
+        )));
+    }
+
+    if let Some(limit) = voucher.per_customer_limit {
+        let Some(customer_id) = customer_id else {
+            return Err(ServiceError::BadRequest(format!(
+                "Voucher '{}' membutuhkan customer_id",
+                code
+            )));
+        };
+
+        let redemptions: Collection<VoucherRedemption> = db.collection("voucher_redemptions");
+        let used = redemptions
+            .count_documents(doc! {
+                "voucher_id": voucher_id,
+                "customer_id": customer_id,
+                "status": "redeemed",
+            })
+            .session(&mut *session)
+            .await
+            .map_err(transaction_error)?;
+
+        if used >= limit as u64 {
+            return Err(ServiceError::BadRequest(format!(
+                "Pelanggan sudah memakai voucher '{}' sebanyak batas maksimal ({}x)",
+                code, limit
+            )));
+        }
+    }
+
+    // Filter kuota dan $inc dalam satu operasi supaya used_count tidak pernah melewati batas
+    let updated = collection
+        .update_one(
+            doc! {
+                "_id": voucher_id,
+                "$or": [
+                    { "usage_limit": null },
+                    { "$expr": { "$lt": [ "$used_count", "$usage_limit" ] } },
+                ],
+            },
+            doc! {
+                "$inc": { "used_count": 1 },
+                "$set": { "updated_at": BsonDateTime::from_chrono(Utc::now()) },
+            },
+        )
+        .session(&mut *session)
+        .await
+        .map_err(transaction_error)?;
+
+    if updated.matched_count == 0 {
+        return Err(ServiceError::BadRequest(format!(
+            "Kuota voucher '{}' sudah habis",
+            code
+        )));
+    }
+
+    Ok(SaleVoucher {
+        voucher_id,
+        code,
+        amount: voucher_amount(&voucher, spend),
+    })
+}
+
+/// Catat pemakaian voucher setelah sale tersimpan (butuh sale.id)
+pub async fn record_voucher_redemption(
+    sale: &Sale,
+    db: &Database,
+    session: &mut ClientSession,
+) -> Result<(), ServiceError> {
+    let Some(voucher) = &sale.voucher else {
+        return Ok(());
+    };
+
+    let redemption = VoucherRedemption {
+        id: None,
+        user_id: sale.user_id,
+        voucher_id: voucher.voucher_id,
+        sale_id: sale.id.expect("Sale.id harus ada"),
+        customer_id: sale.customer_id,
+        amount: voucher.amount,
+        status: "redeemed".to_string(),
+        created_at: BsonDateTime::from_chrono(Utc::now()),
+        released_at: None,
+    };
+
+    let collection: Collection<VoucherRedemption> = db.collection("voucher_redemptions");
+    collection
+        .insert_one(&redemption)
+        .session(&mut *session)
+        .await
+        .map_err(transaction_error)?;
+
+    Ok(())
+}
+
+/// Kembalikan kuota voucher dari sale yang di-void
+pub async fn release_sale_voucher(
+    sale: &Sale,
+    db: &Database,
+    session: &mut ClientSession,
+) -> Result<(), ServiceError> {
+    let Some(voucher) = &sale.voucher else {
+        return Ok(());
+    };
+    let now = BsonDateTime::from_chrono(Utc::now());
+
+    let redemptions: Collection<VoucherRedemption> = db.collection("voucher_redemptions");
+    let released = redemptions
+        .update_one(
+            doc! { "sale_id": sale.id, "voucher_id": voucher.voucher_id, "status": "redeemed" },
+            doc! { "$set": { "status": "released", "released_at": now } },
+        )
+        .session(&mut *session)
+        .await
+        .map_err(transaction_error)?;
+
+    if released.modified_count == 0 {
+        return Ok(());
+    }
+
+    let vouchers: Collection<Voucher> = db.collection("vouchers");
+    vouchers
+        .update_one(
+            doc! { "_id": voucher.voucher_id, "used_count": { "$gt": 0 } },
+            doc! { "$inc": { "used_count": -1 }, "$set": { "updated_at": now } },
+        )
+        .session(&mut *session)
+        .await
+        .map_err(transaction_error)?;
+
+    Ok(())
+}
+
+/// Potongan voucher fixed tidak melebihi belanja supaya total tidak minus
+fn voucher_amount(voucher: &Voucher, spend: Money) -> Money {
+    match voucher.discount_type {
+        DiscountType::Percent => spend.percent(voucher.percent.unwrap_or_default()),
+        DiscountType::Fixed => voucher.amount.unwrap_or_default().min(spend),
+    }
+}
+
+fn validate_voucher(voucher: &Voucher) -> Result<(), ServiceError> {
+    if voucher.code.len() < 3 {
+        return Err(ServiceError::BadRequest(
+            "Kode voucher minimal 3 karakter".into(),
+        ));
+    }
+    if voucher.code.chars().any(char::is_whitespace) {
+        return Err(ServiceError::BadRequest(
+            "Kode voucher tidak boleh mengandung spasi".into(),
+        ));
+    }
+
+    match (voucher.discount_type, voucher.percent, voucher.amount) {
+        (DiscountType::Percent, Some(percent), None) => {
+            if !(percent > 0.0 && percent <= 100.0) {
+                return Err(ServiceError::BadRequest(
+                    "Nilai voucher persen harus lebih dari 0 dan maksimal 100".into(),
+                ));
+            }
+        }
+        (DiscountType::Fixed, None, Some(amount)) => {
+            if amount <= Money::ZERO || amount > Money::MAX_AMOUNT {
+                return Err(ServiceError::BadRequest(format!(
+                    "Nominal voucher fixed harus lebih dari 0 dan maksimal {}",
+                    format_rupiah(Money::MAX_AMOUNT)
+                )));
+            }
+        }
+        (DiscountType::Percent, _, _) => {
+            return Err(ServiceError::BadRequest(
+                "Voucher persen hanya memakai field percent".into(),
+            ));
+        }
+        (DiscountType::Fixed, _, _) => {
+            return Err(ServiceError::BadRequest(
+                "Voucher fixed hanya memakai field amount (sen)".into(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn normalize_code(code: &str) -> String {
+    code.trim().to_uppercase()
+}
+
+fn voucher_write_error(e: MongoError) -> ServiceError {
+    // Index unik (user_id, code): pesan bawaan akan menyebut user_id, bukan code
+    if handle_duplicate_key_error(&e).is_some() {
+        return ServiceError::Conflict("Kode voucher sudah dipakai voucher lain".into());
+    }
+    ServiceError::DatabaseError(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn voucher(
+        discount_type: DiscountType,
+        percent: Option<f64>,
+        amount: Option<Money>,
+    ) -> Voucher {
+        Voucher {
+            id: None,
+            user_id: ObjectId::new(),
+            code: "HEMAT10".to_string(),
+            discount_type,
+            percent,
+            amount,
+            min_spend: Money::ZERO,
+            per_customer_limit: None,
+            usage_limit: None,
+            used_count: 0,
+            expires_at: BsonDateTime::from_chrono(Utc::now()),
+            active: true,
+            created_at: None,
+            updated_at: None,
+        }
+    }
+
+    #[test]
+    fn voucher_amount_uses_percent_or_fixed_amount() {
+        let percent = voucher(DiscountType::Percent, Some(10.0), None);
+        assert_eq!(
+            voucher_amount(&percent, Money::from_rupiah(50_000)),
+            Money::from_rupiah(5_000)
+        );
+
+        let fixed = voucher(
+            DiscountType::Fixed,
+            None,
+            Some(Money::from_minor(1_500_050)),
+        );
+        assert_eq!(
+            voucher_amount(&fixed, Money::from_rupiah(50_000)),
+            Money::from_minor(1_500_050)
+        );
+    }
+
+    #[test]
+    fn voucher_amount_fixed_is_capped_at_spend() {
+        let fixed = voucher(DiscountType::Fixed, None, Some(Money::from_rupiah(20_000)));
+        assert_eq!(
+            voucher_amount(&fixed, Money::from_rupiah(15_000)),
+            Money::from_rupiah(15_000)
+        );
+    }
+
+    #[test]
+    fn validate_voucher_requires_the_field_for_its_type() {
+        assert!(validate_voucher(&voucher(DiscountType::Percent, Some(10.0), None)).is_ok());
+        assert!(
+            validate_voucher(&voucher(
+                DiscountType::Fixed,
+                None,
+                Some(Money::from_rupiah(5_000))
+            ))
+            .is_ok()
+        );
+
+        assert!(validate_voucher(&voucher(DiscountType::Percent, None, None)).is_err());
+        assert!(
+            validate_voucher(&voucher(
+                DiscountType::Percent,
+                None,
+                Some(Money::from_rupiah(5_000))
+            ))
+            .is_err()
+        );
+        assert!(validate_voucher(&voucher(DiscountType::Fixed, Some(10.0), None)).is_err());
+        assert!(
+            validate_voucher(&voucher(
+                DiscountType::Fixed,
+                Some(10.0),
+                Some(Money::from_rupiah(5_000))
+            ))
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn validate_voucher_rejects_out_of_range_values() {
+        assert!(validate_voucher(&voucher(DiscountType::Percent, Some(0.0), None)).is_err());
+        assert!(validate_voucher(&voucher(DiscountType::Percent, Some(100.5), None)).is_err());
+        assert!(validate_voucher(&voucher(DiscountType::Fixed, None, Some(Money::ZERO))).is_err());
+        assert!(
+            validate_voucher(&voucher(
+                DiscountType::Fixed,
+                None,
+                Some(Money::MAX_AMOUNT + Money::from_minor(1))
+            ))
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn validate_voucher_rejects_short_codes_and_spaces() {
+        let mut short = voucher(DiscountType::Percent, Some(10.0), None);
+        short.code = "AB".to_string();
+        assert!(validate_voucher(&short).is_err());
+
+        let mut spaced = voucher(DiscountType::Percent, Some(10.0), None);
+        spaced.code = "HEMAT 10".to_string();
+        assert!(validate_voucher(&spaced).is_err());
+    }
+}
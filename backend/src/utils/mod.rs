@@ -2,6 +2,7 @@ pub mod jwt;
 use nanoid::nanoid;
 
 use crate::errors::ServiceError;
+use crate::models::money::{MINOR_UNITS, Money};
 use crate::utils::jwt::{decode_jwt, is_jwt_expired};
 use actix_web::HttpRequest;
 use argon2::{
@@ -9,16 +10,10 @@ use argon2::{
     password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString, rand_core::OsRng},
 };
 use bson::oid::ObjectId;
-use mongodb::error::{Error, ErrorKind, WriteFailure};
+use mongodb::error::{Error, ErrorKind, TRANSIENT_TRANSACTION_ERROR, WriteFailure};
+use mongodb::{ClientSession, Database};
 use serde::Serializer;
 
-pub fn object_id_as_string<S>(id: &ObjectId, serializer: S) -> Result<S::Ok, S::Error>
-where
-    S: Serializer,
-{
-    serializer.serialize_str(&id.to_hex())
-}
-
 pub fn opt_object_id_as_string<S>(id: &Option<ObjectId>, serializer: S) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
@@ -58,10 +53,10 @@ pub fn handle_duplicate_key_error(err: &Error) -> Option<ServiceError> {
     if let ErrorKind::Write(write_failure) = err.kind.as_ref() {
         match write_failure {
             WriteFailure::WriteError(write_error) => {
-                if write_error.code == 11000 {
-                    if let Some(field) = extract_duplicate_field(&write_error.message) {
-                        return Some(ServiceError::Conflict(format!("{} sudah digunakan", field)));
-                    }
+                if write_error.code == 11000
+                    && let Some(field) = extract_duplicate_field(&write_error.message)
+                {
+                    return Some(ServiceError::Conflict(format!("{} sudah digunakan", field)));
                 }
             }
             _ => {
@@ -80,11 +75,98 @@ fn extract_duplicate_field(message: &str) -> Option<String> {
         .map(|m| m.as_str().to_string())
 }
 
+/// Buka session baru dan mulai transaksi multi-dokumen (MongoDB harus replica set)
+pub async fn start_transaction(db: &Database) -> Result<ClientSession, ServiceError> {
+    let mut session = db
+        .client()
+        .start_session()
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+
+    session
+        .start_transaction()
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+
+    Ok(session)
+}
+
+/// Error di dalam transaksi: bentrok antar transaksi dibedakan supaya bisa di-retry
+pub fn transaction_error(err: Error) -> ServiceError {
+    if err.contains_label(TRANSIENT_TRANSACTION_ERROR) {
+        return ServiceError::TransactionConflict(err.to_string());
+    }
+    ServiceError::DatabaseError(err.to_string())
+}
+
+/// Commit transaksi jika `result` Ok, abort jika Err (tidak ada partial write)
+pub async fn finish_transaction<T>(
+    session: &mut ClientSession,
+    result: Result<T, ServiceError>,
+) -> Result<T, ServiceError> {
+    match result {
+        Ok(value) => {
+            session
+                .commit_transaction()
+                .await
+                .map_err(transaction_error)?;
+            Ok(value)
+        }
+        Err(err) => {
+            if let Err(e) = session.abort_transaction().await {
+                log::warn!("Gagal abort transaksi: {}", e);
+            }
+            Err(err)
+        }
+    }
+}
+
+/// Format nominal ke Rupiah, sen hanya ditampilkan jika ada,
+/// contoh: 125000000 sen => "Rp 1.250.000", 150050 sen => "Rp 1.500,50"
+pub fn format_rupiah(amount: Money) -> String {
+    let minor = amount.minor();
+    let rupiah = minor.unsigned_abs() / MINOR_UNITS as u64;
+    let sen = minor.unsigned_abs() % MINOR_UNITS as u64;
+    let digits = rupiah.to_string();
+
+    let mut grouped = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push('.');
+        }
+        grouped.push(c);
+    }
+
+    if sen > 0 {
+        grouped = format!("{},{:02}", grouped, sen);
+    }
+
+    if minor < 0 {
+        format!("-Rp {}", grouped)
+    } else {
+        format!("Rp {}", grouped)
+    }
+}
+
 /// Generate SKU otomatis, contoh: "SKU-X7D2F"
 pub fn generate_random_sku() -> String {
     format!("SKU-{}", nanoid!(5).to_uppercase())
 }
 
+/// Font bawaan PDF dan code page printer thermal hanya mendukung ASCII,
+/// karakter lain (termasuk karakter kontrol) diganti '?'
+pub fn ascii_printable(text: &str) -> String {
+    text.chars()
+        .map(|c| {
+            if c.is_ascii() && !c.is_ascii_control() {
+                c
+            } else {
+                '?'
+            }
+        })
+        .collect()
+}
+
 /// Ekstrak user_id dari cookie JWT
 pub fn extract_user_id_from_cookie(req: &HttpRequest) -> Result<String, ServiceError> {
     let cookie = req
@@ -94,7 +176,7 @@ pub fn extract_user_id_from_cookie(req: &HttpRequest) -> Result<String, ServiceE
     let token = cookie.value();
 
     let decoded =
-        decode_jwt(&token).map_err(|_| ServiceError::Unauthorized("Token tidak valid".into()))?;
+        decode_jwt(token).map_err(|_| ServiceError::Unauthorized("Token tidak valid".into()))?;
 
     if is_jwt_expired(decoded.claims.exp) {
         return Err(ServiceError::Unauthorized("Token sudah expired".into()));
@@ -102,7 +184,8 @@ pub fn extract_user_id_from_cookie(req: &HttpRequest) -> Result<String, ServiceE
     Ok(decoded.claims.sub) // atau decoded.claims.user_id
 }
 
-fn default_is_active() -> bool {
+#[allow(dead_code)]
+pub fn default_is_active() -> bool {
     true
 }
 